@@ -0,0 +1,123 @@
+using-threads = Verwende { $count } Threads
+scanned = { $dirs } Verzeichnisse durchsucht, { $files } Dateien gefunden
+no-files = Keine Dateien zu verarbeiten.
+starting = { $operation ->
+        [move] Verschiebe
+       *[copy] Kopiere
+    } { $count } Dateien nach '{ $dir }'...
+loaded-categories = Geladene Kategorien:
+serving = Bereitgestellt unter '{ $url }'
+
+errors-listed = Fehler bei der Verarbeitung:
+errors-completed = Verarbeitung mit { $count } Fehlern abgeschlossen:
+errors-hint = Mit --verbose werden sie aufgelistet.
+
+summary = Zusammenfassung:
+summary-processed = Verarbeitete Dateien: { $count }
+summary-blacklisted = Übersprungene Dateien (ausgeschlossen): { $count }
+summary-damaged = Beschädigte Dateien (fehlend, leer oder abgeschnitten): { $count }
+summary-damaged-hint = Mit --repair werden beschädigte Dateien direkt nach dem Sortieren erneut aus ihren Quellen kopiert
+summary-dirs-placed = Als Ganzes abgelegte Verzeichnisse: { $count }
+summary-dirs-projects = Übersprungene Verzeichnisse (Projekte): { $count }
+summary-uncategorized = Übersprungene Dateien (ohne Kategorie): { $count }
+summary-in-use = Übersprungene Dateien (von einem anderen Prozess geöffnet): { $count }
+summary-incomplete = Zurückgehaltene Dateien (scheinbar unvollständig): { $count }
+summary-seeding = Belassene Dateien (werden geseedet): { $count }
+summary-infected = Infizierte Dateien (abgelegt in '{ $dir }'): { $count }
+summary-conflicts = Zielkonflikte: { $count }
+summary-over-quota = Übersprungene Dateien (Kategorie-Kontingent erreicht): { $count }
+summary-renamed = Ohne Kopieren verschoben (umbenannt): { $size }
+summary-copied-across = Über Dateisystemgrenzen kopiert: { $size }
+summary-backed-up = Gesicherte überschriebene Dateien: { $count }
+summary-declined = Übersprungene Dateien (abgelehnt): { $count }
+summary-stopped = Vorzeitig beendet, die übrigen Dateien wurden nicht angerührt
+summary-total = Gefundene Dateien insgesamt: { $count }
+
+notify-summary = { $operation ->
+        [move] Verschieben abgeschlossen
+       *[sort] Sortieren abgeschlossen
+    }
+notify-body = `dirsort` hat das Verzeichnis fertig { $operation ->
+        [move] verschoben
+       *[sort] sortiert
+    }
+
+case-insensitive = Das Ausgabeverzeichnis liegt auf einem Dateisystem ohne Unterscheidung von Groß- und Kleinschreibung
+blacklisted = Ausgeschlossene Endungen: { $extensions }
+macos-only = Finder-Tags und Quarantäne-Behandlung gibt es nur unter macOS, wird ignoriert.
+unix-only = { $options } gibt es nur unter Unix, wird ignoriert.
+linux-only = { $options } gibt es nur unter Linux, wird ignoriert.
+backup-ignored = --backup gilt nur mit --on-conflict overwrite, wird ignoriert.
+tool-missing = { $option } braucht { $tool } im PATH, wird ignoriert.
+convert-unavailable = --convert kann hier keine '{ $from }'-Bilder lesen, { $conversion } wird ignoriert.
+copies-instead = { $option } kopiert die Dateien, statt sie zu verschieben.
+attachments-extracted = { $count } Anhänge extrahiert
+torrents-found = { $count } Torrents gefunden, die geseedet werden könnten
+limited = { $count } von { $found } Dateien werden verarbeitet, der Rest bleibt für spätere Läufe
+stopped-before = Beendet, bevor etwas abgelegt wurde
+removed-stale = { $count } unvollständige Kopien eines früheren Laufs entfernt
+same-filesystem = Alle Dateien liegen auf demselben Dateisystem wie '{ $dir }', Verschieben benennt sie nur um
+other-filesystem = { $size } liegen auf einem anderen Dateisystem als '{ $dir }' und werden kopiert und dann gelöscht (etwa { $minutes } min { $seconds } s bei { $rate }/s); { $renamed } lassen sich durch Umbenennen verschieben
+journal-failed = Journal konnte nicht angelegt werden, dieser Lauf lässt sich nicht rückgängig machen: { $error }
+retrying-in-use = { $count } Dateien, die geöffnet waren, werden erneut versucht...
+repairing = { $count } beschädigte Dateien werden repariert...
+repaired = '{ $path }' repariert
+snapshot-linked = { $count } unveränderte Dateien aus dem vorigen Snapshot verlinkt
+checksums-written = Prüfsummendateien in { $count } Ordnern geschrieben
+par2-creating = Wiederherstellungsdaten für { $count } Ordner werden erstellt...
+rename-plan-written = Umbenennungsplan nach '{ $path }' geschrieben, anwenden mit `dirsort apply-renames`
+report-written = Bericht nach '{ $path }' geschrieben
+quota-overflow = { $category } hat sein Kontingent von { $size } erreicht, weitere Dateien kommen nach '{ $dir }'
+quota-full = { $category } hat sein Kontingent von { $size } erreicht, weitere Dateien bleiben, wo sie sind
+paused = Nach den laufenden Dateien angehalten, fortsetzen mit: kill -USR2 { $pid }
+resumed = Fortgesetzt
+
+ingest-importing = Importiere von '{ $dir }' ({ $id })
+ingest-would-import = { $count } Dateien würden importiert, { $known } wurden schon früher importiert
+ingest-imported = { $count } Dateien importiert, { $known } schon früher importiert, { $failed } fehlgeschlagen
+ingest-not-ejecting = Das Gerät wird nicht ausgeworfen, da einige Dateien fehlgeschlagen sind.
+ingest-ejected = Gerät ausgeworfen, es kann entfernt werden.
+ingest-waiting-mount = Warte, bis '{ $device }' eingehängt ist...
+ingest-waiting-device = Warte auf eine Speicherkarte oder einen USB-Stick...
+
+daemon-dbus-failed = Registrierung bei D-Bus fehlgeschlagen: { $error }
+daemon-no-schedule = '{ $dir }' hat keinen Zeitplan und wird nur auf Anfrage sortiert.
+daemon-scheduled = { $dir } wird nach '{ $schedule }' sortiert, als Nächstes um { $next }
+daemon-sorting = Sortiere { $dir }...
+daemon-failed = Sortieren von { $dir } fehlgeschlagen: { $status }
+daemon-start-failed = Sortieren von { $dir } konnte nicht gestartet werden: { $error }
+
+html-title = Verzeichnisindex
+html-heading = Verzeichnisindex: { $dir }
+html-generated = HTML-Index erstellt unter { $path }
+json-generated = JSON-Index erstellt unter { $path }
+html-category = Kategorie
+html-files = Dateien
+html-size = Größe
+html-total = Gesamt
+
+help-output-dir = Das Verzeichnis, in das sortiert wird; mehrfach angeben, um Kategorien auf mehrere Laufwerke zu verteilen
+help-notify = Nach Abschluss eine Benachrichtigung senden
+help-mv = Dateien verschieben statt kopieren
+help-blacklist = Auszuschließende Endungen (durch Kommas getrennt, z. B. 'txt,log,tmp')
+help-blacklist-file = Datei mit auszuschließenden Endungen (eine pro Zeile)
+help-threads = Anzahl der Threads für die parallele Verarbeitung (Standard: Anzahl der CPU-Kerne)
+help-max-depth = Maximale Verzeichnistiefe (0 = nur das aktuelle Verzeichnis, Standard: unbegrenzt)
+help-config = Pfad zu einer Konfigurationsdatei mit Kategorien
+help-gen-html = Nach dem Sortieren eine HTML-Indexdatei erzeugen
+help-serve = Das sortierte Verzeichnis anschließend bereitstellen
+help-dry-run = Anzeigen, was wohin abgelegt würde, ohne Dateien anzufassen
+help-lang = Sprache für Meldungen und Hilfe, z. B. 'de' (Standard: aus LC_ALL, LC_MESSAGES oder LANG)
+
+about-init = Interaktiv eine Konfigurationsdatei schreiben, optional mit systemd-Units
+about-usage = Anzeigen, wie viel Platz jede Kategorie und Endung belegen würde, ohne zu sortieren
+about-analyze = Die größten Dateien mit ihrem Zielordner auflisten, ohne zu sortieren
+about-classify = Eine Datei von stdin lesen und ausgeben, in welchen Ordner sie sortiert würde und wohin
+about-diff = Zwei Verzeichnisse vergleichen: Dateien, die nur eines enthält, Dateien mit unterschiedlichem Inhalt und wie sich die Summen jeder Kategorie ändern
+about-ingest = Auf eine Kamerakarte oder einen USB-Stick warten und neue Dateien nach `<Kategorie>/<Jahr>/<Datum>/` kopieren, wobei jede Kopie geprüft wird
+about-daemon = Weiterlaufen und die `[[watch]]`-Verzeichnisse der Konfiguration nach Zeitplan sortieren
+about-apply-renames = Dateien so ablegen, wie sie in einem mit --rename-plan geschriebenen und dort bearbeiteten Umbenennungsplan stehen
+about-undo = Die Dateien eines früheren Laufs zurücklegen, standardmäßig des letzten
+about-simulate = Ein Verzeichnis mit typischen Dateien erzeugen und die aktuelle Konfiguration daran testen
+about-bench = Durchsuchen, Einordnen und Kopieren messen und Thread-Anzahlen vorschlagen
+about-log = Frühere Läufe auflisten
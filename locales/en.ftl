@@ -0,0 +1,102 @@
+# Messages are looked up here when the selected locale doesn't have them.
+# Help text for options and subcommands comes from the doc comments in
+# src/main.rs; other locales translate it with `help-<option>` and
+# `about-<subcommand>` messages.
+
+using-threads = Using { $count } threads
+scanned = Scanned { $dirs } directories, found { $files } files
+no-files = No files found to process.
+starting = Starting { $operation ->
+        [move] moving
+       *[copy] copying
+    } { $count } files to '{ $dir }'...
+loaded-categories = Loaded categories:
+serving = Serving at '{ $url }'
+
+errors-listed = Errors encountered during processing:
+errors-completed = Processing completed with { $count } errors:
+errors-hint = Run with --verbose to list them.
+
+summary = Summary:
+summary-processed = Files processed: { $count }
+summary-blacklisted = Files skipped (blacklisted): { $count }
+summary-damaged = Damaged files (missing, empty or cut short): { $count }
+summary-damaged-hint = Pass --repair to copy damaged files over again from their sources right after sorting
+summary-dirs-placed = Directories placed whole: { $count }
+summary-dirs-projects = Directories skipped (projects): { $count }
+summary-uncategorized = Files skipped (uncategorized): { $count }
+summary-in-use = Files skipped (open in another process): { $count }
+summary-incomplete = Files held back (looked incomplete): { $count }
+summary-seeding = Files left in place (being seeded): { $count }
+summary-infected = Infected files (placed in '{ $dir }'): { $count }
+summary-conflicts = Destination conflicts: { $count }
+summary-over-quota = Files skipped (category over quota): { $count }
+summary-renamed = Moved for free (renamed): { $size }
+summary-copied-across = Moved by copying across filesystems: { $size }
+summary-backed-up = Overwritten files backed up: { $count }
+summary-declined = Files skipped (declined): { $count }
+summary-stopped = Stopped early, remaining files were left in place
+summary-total = Total files found: { $count }
+
+notify-summary = { $operation ->
+        [move] Finished moving
+       *[sort] Finished sorting
+    }
+notify-body = `dirsort` has finished { $operation ->
+        [move] moving
+       *[sort] sorting
+    } the directory
+
+case-insensitive = Output directory is on a case-insensitive filesystem
+blacklisted = Blacklisted extensions: { $extensions }
+macos-only = Finder tags and quarantine handling are only available on macOS, ignoring.
+unix-only = { $options } are only available on Unix, ignoring.
+linux-only = { $options } is only available on Linux, ignoring.
+backup-ignored = --backup only applies with --on-conflict overwrite, ignoring.
+tool-missing = { $option } needs { $tool } in PATH, ignoring.
+convert-unavailable = --convert can't read '{ $from }' images here, ignoring { $conversion }.
+copies-instead = { $option } copies files instead of moving them.
+attachments-extracted = Extracted { $count } attachments
+torrents-found = Found { $count } torrents that may be seeding
+limited = Processing { $count } of { $found } files, the rest are left for later runs
+stopped-before = Stopped before placing anything
+removed-stale = Removed { $count } unfinished copies left by an earlier run
+same-filesystem = All files are on the same filesystem as '{ $dir }', moving only renames them
+other-filesystem = { $size } is on a different filesystem than '{ $dir }' and will be copied, then deleted (roughly { $minutes }m { $seconds }s at { $rate }/s); { $renamed } can be moved by renaming
+journal-failed = Failed to start a journal, this run can't be undone: { $error }
+retrying-in-use = Retrying { $count } files that were in use...
+repairing = Repairing { $count } damaged files...
+repaired = Repaired '{ $path }'
+snapshot-linked = Hardlinked { $count } unchanged files from the previous snapshot
+checksums-written = Wrote checksum files into { $count } folders
+par2-creating = Creating recovery data for { $count } folders...
+rename-plan-written = Rename plan written to '{ $path }', apply it with `dirsort apply-renames`
+report-written = Wrote report to '{ $path }'
+quota-overflow = { $category } has reached its quota of { $size }, placing further files in '{ $dir }'
+quota-full = { $category } has reached its quota of { $size }, leaving further files in place
+paused = Paused after the files in progress, resume with: kill -USR2 { $pid }
+resumed = Resumed
+
+ingest-importing = Importing from '{ $dir }' ({ $id })
+ingest-would-import = Would import { $count } files, { $known } already imported earlier
+ingest-imported = Imported { $count } files, { $known } already imported earlier, { $failed } failed
+ingest-not-ejecting = Not ejecting the device, since some files failed.
+ingest-ejected = Device ejected, it can be removed.
+ingest-waiting-mount = Waiting for '{ $device }' to be mounted...
+ingest-waiting-device = Waiting for a camera card or USB stick to be inserted...
+
+daemon-dbus-failed = Failed to register on D-Bus: { $error }
+daemon-no-schedule = '{ $dir }' has no schedule, only sorting it on request.
+daemon-scheduled = Sorting { $dir } at '{ $schedule }', next at { $next }
+daemon-sorting = Sorting { $dir }...
+daemon-failed = Sorting { $dir } failed: { $status }
+daemon-start-failed = Failed to start sorting { $dir }: { $error }
+
+html-title = Directory Index
+html-heading = Directory Index: { $dir }
+html-generated = Generated HTML index at { $path }
+json-generated = Generated JSON index at { $path }
+html-category = Category
+html-files = Files
+html-size = Size
+html-total = Total
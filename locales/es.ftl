@@ -0,0 +1,123 @@
+using-threads = Usando { $count } hilos
+scanned = Se analizaron { $dirs } directorios y se encontraron { $files } archivos
+no-files = No se encontraron archivos para procesar.
+starting = { $operation ->
+        [move] Moviendo
+       *[copy] Copiando
+    } { $count } archivos a '{ $dir }'...
+loaded-categories = Categorías cargadas:
+serving = Sirviendo en '{ $url }'
+
+errors-listed = Errores durante el procesamiento:
+errors-completed = Procesamiento terminado con { $count } errores:
+errors-hint = Ejecute con --verbose para listarlos.
+
+summary = Resumen:
+summary-processed = Archivos procesados: { $count }
+summary-blacklisted = Archivos omitidos (excluidos): { $count }
+summary-damaged = Archivos dañados (ausentes, vacíos o truncados): { $count }
+summary-damaged-hint = Con --repair los archivos dañados se vuelven a copiar desde sus orígenes justo después de ordenar
+summary-dirs-placed = Directorios colocados enteros: { $count }
+summary-dirs-projects = Directorios omitidos (proyectos): { $count }
+summary-uncategorized = Archivos omitidos (sin categoría): { $count }
+summary-in-use = Archivos omitidos (abiertos por otro proceso): { $count }
+summary-incomplete = Archivos retenidos (parecían incompletos): { $count }
+summary-seeding = Archivos dejados en su sitio (se están compartiendo): { $count }
+summary-infected = Archivos infectados (colocados en '{ $dir }'): { $count }
+summary-conflicts = Conflictos de destino: { $count }
+summary-over-quota = Archivos omitidos (categoría sin cuota): { $count }
+summary-renamed = Movido sin copiar (renombrado): { $size }
+summary-copied-across = Movido copiando entre sistemas de archivos: { $size }
+summary-backed-up = Archivos sobrescritos con copia de seguridad: { $count }
+summary-declined = Archivos omitidos (rechazados): { $count }
+summary-stopped = Detenido antes de tiempo, los archivos restantes no se tocaron
+summary-total = Total de archivos encontrados: { $count }
+
+notify-summary = { $operation ->
+        [move] Movimiento terminado
+       *[sort] Ordenación terminada
+    }
+notify-body = `dirsort` ha terminado de { $operation ->
+        [move] mover
+       *[sort] ordenar
+    } el directorio
+
+case-insensitive = El directorio de salida está en un sistema de archivos que no distingue mayúsculas de minúsculas
+blacklisted = Extensiones excluidas: { $extensions }
+macos-only = Las etiquetas de Finder y el manejo de la cuarentena solo están disponibles en macOS, se ignoran.
+unix-only = { $options } solo están disponibles en Unix, se ignoran.
+linux-only = { $options } solo está disponible en Linux, se ignora.
+backup-ignored = --backup solo se aplica con --on-conflict overwrite, se ignora.
+tool-missing = { $option } necesita { $tool } en el PATH, se ignora.
+convert-unavailable = --convert no puede leer imágenes '{ $from }' aquí, se ignora { $conversion }.
+copies-instead = { $option } copia los archivos en lugar de moverlos.
+attachments-extracted = { $count } adjuntos extraídos
+torrents-found = { $count } torrents encontrados que podrían estar compartiéndose
+limited = Procesando { $count } de { $found } archivos, el resto queda para ejecuciones posteriores
+stopped-before = Detenido antes de colocar nada
+removed-stale = { $count } copias incompletas de una ejecución anterior eliminadas
+same-filesystem = Todos los archivos están en el mismo sistema de archivos que '{ $dir }', moverlos solo los renombra
+other-filesystem = { $size } están en otro sistema de archivos que '{ $dir }' y se copiarán y luego se eliminarán (unos { $minutes } min { $seconds } s a { $rate }/s); { $renamed } se pueden mover renombrando
+journal-failed = No se pudo crear el registro, esta ejecución no se podrá deshacer: { $error }
+retrying-in-use = Reintentando { $count } archivos que estaban abiertos...
+repairing = Reparando { $count } archivos dañados...
+repaired = '{ $path }' reparado
+snapshot-linked = { $count } archivos sin cambios enlazados desde la instantánea anterior
+checksums-written = Archivos de sumas de comprobación escritos en { $count } carpetas
+par2-creating = Creando datos de recuperación para { $count } carpetas...
+rename-plan-written = Plan de renombrado escrito en '{ $path }', aplícalo con `dirsort apply-renames`
+report-written = Informe escrito en '{ $path }'
+quota-overflow = { $category } ha alcanzado su cuota de { $size }, los siguientes archivos van a '{ $dir }'
+quota-full = { $category } ha alcanzado su cuota de { $size }, los siguientes archivos se quedan donde están
+paused = En pausa tras los archivos en curso, reanudar con: kill -USR2 { $pid }
+resumed = Reanudado
+
+ingest-importing = Importando desde '{ $dir }' ({ $id })
+ingest-would-import = Se importarían { $count } archivos, { $known } ya importados antes
+ingest-imported = { $count } archivos importados, { $known } ya importados antes, { $failed } fallidos
+ingest-not-ejecting = No se expulsa el dispositivo, porque algunos archivos fallaron.
+ingest-ejected = Dispositivo expulsado, ya se puede retirar.
+ingest-waiting-mount = Esperando a que se monte '{ $device }'...
+ingest-waiting-device = Esperando una tarjeta de cámara o una memoria USB...
+
+daemon-dbus-failed = No se pudo registrar en D-Bus: { $error }
+daemon-no-schedule = '{ $dir }' no tiene programación, solo se ordena a petición.
+daemon-scheduled = Ordenando { $dir } según '{ $schedule }', la próxima vez a las { $next }
+daemon-sorting = Ordenando { $dir }...
+daemon-failed = Falló la ordenación de { $dir }: { $status }
+daemon-start-failed = No se pudo iniciar la ordenación de { $dir }: { $error }
+
+html-title = Índice del directorio
+html-heading = Índice del directorio: { $dir }
+html-generated = Índice HTML generado en { $path }
+json-generated = Índice JSON generado en { $path }
+html-category = Categoría
+html-files = Archivos
+html-size = Tamaño
+html-total = Total
+
+help-output-dir = El directorio donde ordenar los archivos; repítalo para repartir las categorías entre varios discos
+help-notify = Enviar una notificación al terminar
+help-mv = Mover los archivos en lugar de copiarlos
+help-blacklist = Extensiones a excluir (separadas por comas, p. ej. 'txt,log,tmp')
+help-blacklist-file = Archivo con extensiones a excluir (una por línea)
+help-threads = Número de hilos para el procesamiento en paralelo (predeterminado: número de núcleos)
+help-max-depth = Profundidad máxima de recorrido (0 = solo el directorio actual, predeterminado: ilimitada)
+help-config = Ruta a un archivo de configuración con categorías
+help-gen-html = Generar un índice HTML después de ordenar
+help-serve = Servir el directorio ordenado al terminar
+help-dry-run = Mostrar qué se colocaría dónde, sin tocar ningún archivo
+help-lang = Idioma de los mensajes y la ayuda, p. ej. 'es' (predeterminado: de LC_ALL, LC_MESSAGES o LANG)
+
+about-init = Escribir un archivo de configuración de forma interactiva, y opcionalmente unidades de systemd
+about-usage = Mostrar cuánto ocuparía cada categoría y extensión, sin ordenar
+about-analyze = Listar los archivos más grandes con la carpeta a la que irían, sin ordenar
+about-classify = Leer un archivo de stdin e imprimir la carpeta en la que se ordenaría y su destino
+about-diff = Comparar dos directorios: archivos que solo tiene uno, archivos cuyo contenido difiere y cómo cambian los totales de cada categoría
+about-ingest = Esperar una tarjeta de cámara o memoria USB y copiar sus archivos nuevos en `<categoría>/<año>/<fecha>/`, verificando cada copia
+about-daemon = Seguir en ejecución y ordenar los directorios `[[watch]]` de la configuración según su horario
+about-apply-renames = Colocar los archivos como figuran en un plan de renombrado escrito por --rename-plan, con los nombres y carpetas editados allí
+about-undo = Devolver los archivos de una ejecución anterior, por defecto la más reciente
+about-simulate = Generar un directorio de archivos típicos y probar la configuración actual en él
+about-bench = Medir el análisis, la clasificación y la copia, y sugerir el número de hilos
+about-log = Listar ejecuciones anteriores
@@ -0,0 +1,123 @@
+using-threads = Utilisation de { $count } threads
+scanned = { $dirs } répertoires parcourus, { $files } fichiers trouvés
+no-files = Aucun fichier à traiter.
+starting = { $operation ->
+        [move] Déplacement
+       *[copy] Copie
+    } de { $count } fichiers vers '{ $dir }'...
+loaded-categories = Catégories chargées :
+serving = Disponible sur '{ $url }'
+
+errors-listed = Erreurs rencontrées pendant le traitement :
+errors-completed = Traitement terminé avec { $count } erreurs :
+errors-hint = Relancez avec --verbose pour les afficher.
+
+summary = Résumé :
+summary-processed = Fichiers traités : { $count }
+summary-blacklisted = Fichiers ignorés (exclus) : { $count }
+summary-damaged = Fichiers endommagés (manquants, vides ou tronqués) : { $count }
+summary-damaged-hint = Avec --repair, les fichiers endommagés sont recopiés depuis leurs sources juste après le tri
+summary-dirs-placed = Répertoires placés en entier : { $count }
+summary-dirs-projects = Répertoires ignorés (projets) : { $count }
+summary-uncategorized = Fichiers ignorés (sans catégorie) : { $count }
+summary-in-use = Fichiers ignorés (ouverts par un autre processus) : { $count }
+summary-incomplete = Fichiers mis de côté (semblaient incomplets) : { $count }
+summary-seeding = Fichiers laissés en place (en cours de partage) : { $count }
+summary-infected = Fichiers infectés (placés dans '{ $dir }') : { $count }
+summary-conflicts = Conflits de destination : { $count }
+summary-over-quota = Fichiers ignorés (quota de la catégorie atteint) : { $count }
+summary-renamed = Déplacé sans copie (renommé) : { $size }
+summary-copied-across = Déplacé par copie entre systèmes de fichiers : { $size }
+summary-backed-up = Fichiers écrasés sauvegardés : { $count }
+summary-declined = Fichiers ignorés (refusés) : { $count }
+summary-stopped = Arrêté avant la fin, les fichiers restants n'ont pas été touchés
+summary-total = Total des fichiers trouvés : { $count }
+
+notify-summary = { $operation ->
+        [move] Déplacement terminé
+       *[sort] Tri terminé
+    }
+notify-body = `dirsort` a fini de { $operation ->
+        [move] déplacer
+       *[sort] trier
+    } le répertoire
+
+case-insensitive = Le répertoire de sortie est sur un système de fichiers insensible à la casse
+blacklisted = Extensions exclues : { $extensions }
+macos-only = Les étiquettes Finder et la gestion de la quarantaine ne sont disponibles que sur macOS, ignoré.
+unix-only = { $options } ne sont disponibles que sur Unix, ignoré.
+linux-only = { $options } n'est disponible que sur Linux, ignoré.
+backup-ignored = --backup ne s'applique qu'avec --on-conflict overwrite, ignoré.
+tool-missing = { $option } nécessite { $tool } dans le PATH, ignoré.
+convert-unavailable = --convert ne peut pas lire les images '{ $from }' ici, { $conversion } ignoré.
+copies-instead = { $option } copie les fichiers au lieu de les déplacer.
+attachments-extracted = { $count } pièces jointes extraites
+torrents-found = { $count } torrents trouvés qui sont peut-être en partage
+limited = Traitement de { $count } fichiers sur { $found }, les autres sont laissés aux prochaines exécutions
+stopped-before = Arrêté avant de placer quoi que ce soit
+removed-stale = { $count } copies inachevées d'une exécution précédente supprimées
+same-filesystem = Tous les fichiers sont sur le même système de fichiers que '{ $dir }', les déplacer ne fait que les renommer
+other-filesystem = { $size } se trouvent sur un autre système de fichiers que '{ $dir }' et seront copiés, puis supprimés (environ { $minutes } min { $seconds } s à { $rate }/s) ; { $renamed } peuvent être déplacés par renommage
+journal-failed = Impossible de créer le journal, cette exécution ne pourra pas être annulée : { $error }
+retrying-in-use = Nouvel essai pour { $count } fichiers qui étaient ouverts...
+repairing = Réparation de { $count } fichiers endommagés...
+repaired = '{ $path }' réparé
+snapshot-linked = { $count } fichiers inchangés liés depuis l'instantané précédent
+checksums-written = Fichiers de sommes de contrôle écrits dans { $count } dossiers
+par2-creating = Création des données de récupération pour { $count } dossiers...
+rename-plan-written = Plan de renommage écrit dans '{ $path }', à appliquer avec `dirsort apply-renames`
+report-written = Rapport écrit dans '{ $path }'
+quota-overflow = { $category } a atteint son quota de { $size }, les fichiers suivants vont dans '{ $dir }'
+quota-full = { $category } a atteint son quota de { $size }, les fichiers suivants restent en place
+paused = En pause après les fichiers en cours, reprendre avec : kill -USR2 { $pid }
+resumed = Reprise
+
+ingest-importing = Importation depuis '{ $dir }' ({ $id })
+ingest-would-import = { $count } fichiers seraient importés, { $known } déjà importés auparavant
+ingest-imported = { $count } fichiers importés, { $known } déjà importés auparavant, { $failed } en échec
+ingest-not-ejecting = Le périphérique n'est pas éjecté, car certains fichiers ont échoué.
+ingest-ejected = Périphérique éjecté, il peut être retiré.
+ingest-waiting-mount = En attente du montage de '{ $device }'...
+ingest-waiting-device = En attente d'une carte d'appareil photo ou d'une clé USB...
+
+daemon-dbus-failed = Échec de l'enregistrement sur D-Bus : { $error }
+daemon-no-schedule = '{ $dir }' n'a pas de planification, il n'est trié que sur demande.
+daemon-scheduled = Tri de { $dir } selon '{ $schedule }', prochain à { $next }
+daemon-sorting = Tri de { $dir }...
+daemon-failed = Le tri de { $dir } a échoué : { $status }
+daemon-start-failed = Impossible de lancer le tri de { $dir } : { $error }
+
+html-title = Index du répertoire
+html-heading = Index du répertoire : { $dir }
+html-generated = Index HTML généré dans { $path }
+json-generated = Index JSON généré dans { $path }
+html-category = Catégorie
+html-files = Fichiers
+html-size = Taille
+html-total = Total
+
+help-output-dir = Le répertoire où trier les fichiers ; à répéter pour répartir les catégories sur plusieurs disques
+help-notify = Envoyer une notification à la fin
+help-mv = Déplacer les fichiers au lieu de les copier
+help-blacklist = Extensions à exclure (séparées par des virgules, p. ex. 'txt,log,tmp')
+help-blacklist-file = Fichier contenant les extensions à exclure (une par ligne)
+help-threads = Nombre de threads pour le traitement en parallèle (par défaut : nombre de cœurs)
+help-max-depth = Profondeur maximale de parcours (0 = répertoire courant uniquement, par défaut : illimitée)
+help-config = Chemin d'un fichier de configuration contenant les catégories
+help-gen-html = Générer un index HTML après le tri
+help-serve = Servir le répertoire trié à la fin
+help-dry-run = Afficher ce qui serait placé où, sans toucher aux fichiers
+help-lang = Langue des messages et de l'aide, p. ex. 'fr' (par défaut : d'après LC_ALL, LC_MESSAGES ou LANG)
+
+about-init = Écrire un fichier de configuration de manière interactive, et éventuellement des unités systemd
+about-usage = Afficher la place que prendrait chaque catégorie et extension, sans trier
+about-analyze = Lister les plus gros fichiers avec le dossier où ils iraient, sans trier
+about-classify = Lire un fichier depuis stdin et afficher le dossier dans lequel il serait trié et sa destination
+about-diff = Comparer deux répertoires : fichiers présents dans un seul, fichiers dont le contenu diffère et évolution des totaux de chaque catégorie
+about-ingest = Attendre une carte d'appareil photo ou une clé USB et copier ses nouveaux fichiers dans `<catégorie>/<année>/<date>/`, en vérifiant chaque copie
+about-daemon = Rester actif et trier les répertoires `[[watch]]` de la configuration selon leur planning
+about-apply-renames = Placer les fichiers comme indiqué dans un plan de renommage écrit par --rename-plan, avec les noms et dossiers qui y ont été modifiés
+about-undo = Remettre en place les fichiers d'une exécution précédente, par défaut la plus récente
+about-simulate = Générer un répertoire de fichiers typiques et y tester la configuration actuelle
+about-bench = Mesurer le parcours, le classement et la copie, et suggérer un nombre de threads
+about-log = Lister les exécutions précédentes
@@ -0,0 +1,44 @@
+use {
+    actix_web::{
+        Error,
+        body::{BodySize, MessageBody},
+        dev::{ServiceRequest, ServiceResponse},
+        middleware::Next,
+    },
+    chrono::Local,
+    std::io::{self, Write},
+};
+
+/// Middleware that prints each request to stdout in the Common Log Format,
+/// e.g. `192.168.1.20 - - [15/Oct/2026:10:25:39 +0200] "GET /a.mp4 HTTP/1.1" 206 1048576`,
+/// so the output can be appended to a file and read by the usual tools.
+pub async fn log(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let client = req
+        .peer_addr()
+        .map_or_else(|| "-".to_string(), |addr| addr.ip().to_string());
+    let request = format!(
+        "{} {} {:?}",
+        req.method(),
+        req.uri()
+            .path_and_query()
+            .map_or(req.path(), |path| path.as_str()),
+        req.version()
+    );
+    let time = Local::now().format("%d/%b/%Y:%H:%M:%S %z");
+
+    let res = next.call(req).await?;
+    // Streamed bodies, like the change events, have no size up front.
+    let bytes = match res.response().body().size() {
+        BodySize::Sized(bytes) => bytes.to_string(),
+        BodySize::None | BodySize::Stream => "-".to_string(),
+    };
+    let _ = writeln!(
+        io::stdout().lock(),
+        r#"{client} - - [{time}] "{request}" {} {bytes}"#,
+        res.status().as_u16()
+    );
+    Ok(res)
+}
@@ -0,0 +1,63 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// How `--backup` preserves a destination before it is overwritten, after
+/// `cp --backup`.
+#[derive(Clone)]
+pub enum BackupMode {
+    /// `photo.jpg.~1~`, `photo.jpg.~2~`, ...
+    Numbered,
+    /// `photo.jpg<suffix>`, replacing an older backup
+    Suffix(String),
+    /// The same relative path under another directory
+    Dir(PathBuf),
+}
+
+pub fn parse(mode: &str) -> Result<BackupMode, String> {
+    match mode.split_once('=') {
+        None if mode == "numbered" => Ok(BackupMode::Numbered),
+        Some(("suffix", suffix)) if !suffix.is_empty() && !suffix.contains(['/', '\\']) => {
+            Ok(BackupMode::Suffix(suffix.to_string()))
+        }
+        Some(("dir", dir)) if !dir.is_empty() => Ok(BackupMode::Dir(PathBuf::from(dir))),
+        _ => Err(format!(
+            "invalid backup mode '{mode}', expected 'numbered', 'suffix=<SUFFIX>' or 'dir=<DIR>'"
+        )),
+    }
+}
+
+/// Moves the existing file or directory at `dest` out of the way, returning
+/// where it went. `out_dir` is the root `dest` is relative to for `Dir`.
+pub fn backup(dest: &Path, out_dir: &Path, mode: &BackupMode) -> io::Result<Option<PathBuf>> {
+    if fs::symlink_metadata(dest).is_err() {
+        return Ok(None);
+    }
+
+    let target = match mode {
+        BackupMode::Numbered => {
+            let name = dest.file_name().unwrap_or_default().to_string_lossy();
+            (1..)
+                .map(|n| dest.with_file_name(format!("{name}.~{n}~")))
+                .find(|candidate| fs::symlink_metadata(candidate).is_err())
+                .unwrap_or_else(|| dest.to_path_buf())
+        }
+        BackupMode::Suffix(suffix) => {
+            let mut name = dest.as_os_str().to_owned();
+            name.push(suffix);
+            PathBuf::from(name)
+        }
+        BackupMode::Dir(dir) => dir.join(dest.strip_prefix(out_dir).unwrap_or(dest)),
+    };
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if target.is_dir() {
+        fs::remove_dir_all(&target)?;
+    }
+    fs::rename(dest, &target)?;
+
+    Ok(Some(target))
+}
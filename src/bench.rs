@@ -0,0 +1,225 @@
+use {
+    crate::{
+        SorterConfig, categorize, collect_files, copy_file,
+        hashing::{self, HashAlgorithm},
+        progress::Progress,
+        simulate, tui,
+    },
+    rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator},
+    serde::Serialize,
+    std::{
+        error::Error,
+        fs,
+        io::{self, Write},
+        path::{Path, PathBuf},
+        process, thread,
+        time::Instant,
+    },
+    tracing::info,
+    walkdir::WalkDir,
+};
+
+#[derive(Serialize)]
+struct CopyResult {
+    threads: usize,
+    bytes_per_sec: f64,
+    files_per_sec: f64,
+}
+
+#[derive(Serialize)]
+struct Results {
+    files: usize,
+    bytes: u64,
+    scan_files_per_sec: f64,
+    categorize_files_per_sec: f64,
+    /// With the `--hash` algorithm
+    hash_bytes_per_sec: f64,
+    copy: Vec<CopyResult>,
+    recommended_threads: usize,
+    /// Options that apply to the run but aren't reflected in the numbers
+    not_measured: Vec<String>,
+}
+
+/// What `dirsort bench` measures, and with which of the run's options.
+pub struct Bench<'a> {
+    pub config: &'a SorterConfig,
+    pub screenshot_patterns: &'a [glob::Pattern],
+    /// Where the scratch directory goes, on the disk that would be sorted into
+    pub dir: &'a Path,
+    pub files: usize,
+    pub file_size: u64,
+    /// `--threads`, which is timed alongside the usual counts
+    pub threads: Option<usize>,
+    pub hash: HashAlgorithm,
+    pub mv: bool,
+    pub json: bool,
+}
+
+/// Measures scanning, categorizing, hashing and copying on generated files
+/// in a scratch directory under `bench.dir`, and prints the results or JSON.
+pub fn run(bench: &Bench) -> Result<(), Box<dyn Error>> {
+    let scratch = bench.dir.join(format!(".dirsort-bench-{}", process::id()));
+    let source = scratch.join("source");
+    fs::create_dir_all(&source)?;
+
+    let result = measure(bench, &scratch, &source);
+    fs::remove_dir_all(&scratch)?;
+    let results = result?;
+
+    let mut stdout = io::stdout().lock();
+    if bench.json {
+        serde_json::to_writer_pretty(&mut stdout, &results)?;
+        writeln!(stdout)?;
+        return Ok(());
+    }
+
+    writeln!(
+        stdout,
+        "{} files, {} in total\n",
+        results.files,
+        tui::human_bytes(results.bytes)
+    )?;
+    writeln!(
+        stdout,
+        "Scan:        {:>10.0} files/s",
+        results.scan_files_per_sec
+    )?;
+    writeln!(
+        stdout,
+        "Categorize:  {:>10.0} files/s",
+        results.categorize_files_per_sec
+    )?;
+    writeln!(
+        stdout,
+        "Hash:        {:>10}/s",
+        tui::human_bytes(results.hash_bytes_per_sec as u64)
+    )?;
+    for copy in &results.copy {
+        writeln!(
+            stdout,
+            "Copy, {:>3} threads: {:>10}/s  {:>8.0} files/s{}",
+            copy.threads,
+            tui::human_bytes(copy.bytes_per_sec as u64),
+            copy.files_per_sec,
+            if Some(copy.threads) == bench.threads {
+                "  (--threads)"
+            } else {
+                ""
+            }
+        )?;
+    }
+    writeln!(
+        stdout,
+        "\nRecommended: --threads {}",
+        results.recommended_threads
+    )?;
+    for option in &results.not_measured {
+        writeln!(stdout, "Not measured: {option}")?;
+    }
+    Ok(())
+}
+
+fn measure(bench: &Bench, scratch: &Path, source: &Path) -> Result<Results, Box<dyn Error>> {
+    let (count, file_size, config) = (bench.files, bench.file_size, bench.config);
+    info!("Generating {count} files...");
+    simulate::generate(source, count, simulate::Profile::DownloadsMix, 1)?;
+    let contents: Vec<u8> = (0..file_size).map(|i| (i * 31 % 251) as u8).collect();
+    for entry in WalkDir::new(source) {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            fs::write(entry.path(), &contents)?;
+        }
+    }
+
+    let scanning = Instant::now();
+    let scanned = collect_files(&[source.to_path_buf()], None, config, &Progress::Hidden);
+    let scan_time = scanning.elapsed();
+    let files: Vec<PathBuf> = scanned?
+        .0
+        .iter()
+        .map(|entry| entry.path().to_path_buf())
+        .collect();
+    let bytes: u64 = files
+        .iter()
+        .filter_map(|path| fs::metadata(path).ok())
+        .map(|metadata| metadata.len())
+        .sum();
+
+    info!("Categorizing...");
+    let categorizing = Instant::now();
+    for path in &files {
+        let ext = path.extension().and_then(|ext| ext.to_str());
+        categorize(path, ext, config, bench.screenshot_patterns);
+    }
+    let categorize_time = categorizing.elapsed();
+
+    info!("Hashing...");
+    let hashing = Instant::now();
+    for path in &files {
+        hashing::hash_file(path, bench.hash)?;
+    }
+    let hash_time = hashing.elapsed();
+
+    let cores = thread::available_parallelism().map_or(4, |cores| cores.get());
+    let mut counts: Vec<usize> = (0..)
+        .map(|power| 1 << power)
+        .take_while(|&n| n <= cores * 2)
+        .chain(bench.threads)
+        .collect();
+    counts.sort_unstable();
+    counts.dedup();
+    let mut copy = Vec::new();
+    for threads in counts {
+        info!("Copying with {threads} threads...");
+        let dest = scratch.join(format!("copy-{threads}"));
+        fs::create_dir_all(&dest)?;
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()?;
+        let copying = Instant::now();
+        pool.install(|| {
+            files
+                .par_iter()
+                .enumerate()
+                .try_for_each(|(index, path)| copy_file(path, &dest.join(format!("{index}"))))
+        })?;
+        let seconds = copying.elapsed().as_secs_f64();
+        fs::remove_dir_all(&dest)?;
+
+        copy.push(CopyResult {
+            threads,
+            bytes_per_sec: bytes as f64 / seconds,
+            files_per_sec: files.len() as f64 / seconds,
+        });
+    }
+
+    // The fewest threads within 5% of the fastest, more only add contention.
+    let fastest = copy
+        .iter()
+        .map(|result| result.bytes_per_sec)
+        .fold(0.0, f64::max);
+    let recommended = copy
+        .iter()
+        .find(|result| result.bytes_per_sec >= fastest * 0.95)
+        .map_or(cores, |result| result.threads);
+
+    // Everything here is copied within one disk.
+    let mut not_measured =
+        vec!["--device-threads, which only matters with files on several devices".to_string()];
+    if bench.mv {
+        not_measured
+            .push("--move, which renames within a filesystem and copies across them".to_string());
+    }
+
+    Ok(Results {
+        files: files.len(),
+        bytes,
+        scan_files_per_sec: files.len() as f64 / scan_time.as_secs_f64(),
+        categorize_files_per_sec: files.len() as f64 / categorize_time.as_secs_f64(),
+        hash_bytes_per_sec: bytes as f64 / hash_time.as_secs_f64(),
+        copy,
+        recommended_threads: recommended,
+        not_measured,
+    })
+}
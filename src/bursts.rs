@@ -0,0 +1,88 @@
+use {
+    crate::metadata,
+    std::{
+        collections::HashMap,
+        path::{Path, PathBuf},
+    },
+};
+
+/// How few shots still make a sequence.
+const MIN_SHOTS: usize = 3;
+
+/// Numbers may skip this many, for shots deleted in the camera.
+const MAX_NUMBER_GAP: u64 = 2;
+
+/// A numbered photo: `IMG_0042.jpg` is `IMG_` and 42, with 4 digits.
+struct Shot<'a> {
+    path: &'a Path,
+    number: u64,
+    digits: String,
+    taken: Option<chrono::NaiveDateTime>,
+}
+
+/// Finds bursts and numbered sequences among `images`: at least a few photos
+/// in one folder whose names only differ in a running number, each taken at
+/// most `max_gap_secs` after the one before. Returns the subfolder to keep
+/// each sequence's photos together in, e.g. 'IMG_0001-0087'.
+pub fn find(images: &[&Path], max_gap_secs: u64) -> HashMap<PathBuf, String> {
+    let mut groups: HashMap<(&Path, String, usize), Vec<Shot>> = HashMap::new();
+    for path in images {
+        let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        let prefix = stem.trim_end_matches(|c: char| c.is_ascii_digit());
+        let digits = &stem[prefix.len()..];
+        let Ok(number) = digits.parse::<u64>() else {
+            continue;
+        };
+        groups
+            .entry((
+                path.parent().unwrap_or(Path::new("")),
+                prefix.to_string(),
+                digits.len(),
+            ))
+            .or_default()
+            .push(Shot {
+                path,
+                number,
+                digits: digits.to_string(),
+                taken: metadata::file_date(path),
+            });
+    }
+
+    let mut sequences = HashMap::new();
+    for ((_, prefix, _), mut shots) in groups {
+        shots.sort_by_key(|shot| (shot.number, shot.taken));
+        let mut start = 0;
+        for end in 1..=shots.len() {
+            let breaks = end == shots.len() || {
+                let (previous, shot) = (&shots[end - 1], &shots[end]);
+                shot.number - previous.number > MAX_NUMBER_GAP
+                    || match (previous.taken, shot.taken) {
+                        (Some(previous), Some(taken)) => {
+                            (taken - previous).num_seconds().unsigned_abs() > max_gap_secs
+                        }
+                        _ => true,
+                    }
+            };
+            if !breaks {
+                continue;
+            }
+
+            let run = &shots[start..end];
+            let distinct = run
+                .windows(2)
+                .filter(|pair| pair[0].number != pair[1].number)
+                .count()
+                + 1;
+            if distinct >= MIN_SHOTS {
+                let folder = format!("{prefix}{}-{}", run[0].digits, run[run.len() - 1].digits);
+                for shot in run {
+                    sequences.insert(shot.path.to_path_buf(), folder.clone());
+                }
+            }
+            start = end;
+        }
+    }
+    sequences
+}
@@ -0,0 +1,157 @@
+use {
+    crate::{
+        hashing::{self, HashAlgorithm},
+        journal,
+    },
+    std::{
+        collections::{BTreeMap, HashMap},
+        fs::{self, File},
+        io::{self, BufRead, BufReader, BufWriter, Read, Write},
+        path::{Path, PathBuf},
+        sync::Mutex,
+    },
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ChecksumFormat {
+    /// 'SHA256SUMS', as checked by `sha256sum -c`
+    Sha256sum,
+    /// '<folder>.sfv' with CRC32s, as checked by cksfv, QuickSFV and others
+    Sfv,
+}
+
+impl ChecksumFormat {
+    fn file_name(self, folder: &Path) -> String {
+        match self {
+            ChecksumFormat::Sha256sum => "SHA256SUMS".to_string(),
+            ChecksumFormat::Sfv => format!(
+                "{}.sfv",
+                folder.file_name().unwrap_or_default().to_string_lossy()
+            ),
+        }
+    }
+
+    fn digest(self, path: &Path) -> io::Result<String> {
+        match self {
+            ChecksumFormat::Sha256sum => hashing::hash_file(path, HashAlgorithm::Sha256),
+            ChecksumFormat::Sfv => {
+                let mut file = BufReader::new(File::open(path)?);
+                let mut hasher = crc32fast::Hasher::new();
+                let mut buffer = [0; 64 * 1024];
+                loop {
+                    match file.read(&mut buffer)? {
+                        0 => break,
+                        read => hasher.update(&buffer[..read]),
+                    }
+                }
+                Ok(format!("{:08X}", hasher.finalize()))
+            }
+        }
+    }
+
+    fn line(self, name: &str, digest: &str) -> String {
+        match self {
+            ChecksumFormat::Sha256sum => format!("{digest}  {name}"),
+            ChecksumFormat::Sfv => format!("{name} {digest}"),
+        }
+    }
+
+    /// The name and digest on a line of an existing checksum file.
+    fn parse(self, line: &str) -> Option<(String, String)> {
+        match self {
+            ChecksumFormat::Sha256sum => {
+                let (digest, name) = line.split_once("  ")?;
+                Some((name.to_string(), digest.to_string()))
+            }
+            ChecksumFormat::Sfv if line.starts_with(';') => None,
+            ChecksumFormat::Sfv => {
+                let (name, digest) = line.rsplit_once(' ')?;
+                Some((name.to_string(), digest.to_string()))
+            }
+        }
+    }
+}
+
+/// Collects a checksum for every placed file with `--emit-checksums`, and
+/// writes them into a checksum file in each category folder at the end.
+pub struct Checksums {
+    format: ChecksumFormat,
+    /// Digests by category folder and path relative to it
+    folders: Mutex<HashMap<PathBuf, BTreeMap<String, String>>>,
+}
+
+impl Checksums {
+    pub fn new(format: ChecksumFormat) -> Self {
+        Self {
+            format,
+            folders: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Checksums `file`, placed somewhere under the category folder `folder`.
+    pub fn add(&self, folder: &Path, file: &Path) -> io::Result<()> {
+        let digest = self.format.digest(file)?;
+        let name = file
+            .strip_prefix(folder)
+            .unwrap_or(file)
+            .components()
+            .map(|part| part.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let mut folders = self.folders.lock().unwrap_or_else(|e| e.into_inner());
+        folders
+            .entry(folder.to_path_buf())
+            .or_default()
+            .insert(name, digest);
+        Ok(())
+    }
+
+    /// Follows the category folder `old` and the folders in it to `new`, for
+    /// folders renamed before `write`.
+    pub fn relocate(&self, old: &Path, new: &Path) {
+        let mut folders = self.folders.lock().unwrap_or_else(|e| e.into_inner());
+        let moved: Vec<PathBuf> = folders
+            .keys()
+            .filter(|folder| folder.starts_with(old))
+            .cloned()
+            .collect();
+        for folder in moved {
+            if let (Some(digests), Ok(rest)) = (folders.remove(&folder), folder.strip_prefix(old)) {
+                folders.insert(journal::relocated(new, rest), digests);
+            }
+        }
+    }
+
+    /// Writes the checksum files, keeping the entries earlier runs wrote for
+    /// files that are still there. Returns how many files were written.
+    pub fn write(&self) -> io::Result<usize> {
+        let folders = self.folders.lock().unwrap_or_else(|e| e.into_inner());
+        for (folder, digests) in folders.iter() {
+            let path = folder.join(self.format.file_name(folder));
+            let mut merged = match File::open(&path) {
+                Ok(file) => BufReader::new(file)
+                    .lines()
+                    .map_while(Result::ok)
+                    .filter_map(|line| self.format.parse(&line))
+                    .filter(|(name, _)| folder.join(name).is_file())
+                    .collect(),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+                Err(e) => return Err(e),
+            };
+            merged.extend(digests.clone());
+
+            let temp = path.with_extension("dirsort-tmp");
+            let mut file = BufWriter::new(File::create(&temp)?);
+            if self.format == ChecksumFormat::Sfv {
+                writeln!(file, "; Generated by dirsort")?;
+            }
+            for (name, digest) in &merged {
+                writeln!(file, "{}", self.format.line(name, digest))?;
+            }
+            file.flush()?;
+            drop(file);
+            fs::rename(&temp, &path)?;
+        }
+        Ok(folders.len())
+    }
+}
@@ -0,0 +1,114 @@
+use std::{
+    fs::File,
+    io::{self, Read, Write},
+    net::TcpStream,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+const DEFAULT_PORT: u16 = 3310;
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Where clamd listens: `clamd://host[:port]` or `clamd:///path/to/clamd.sock`.
+#[derive(Clone)]
+pub enum ClamdAddr {
+    Tcp(String),
+    #[cfg_attr(not(unix), allow(dead_code))]
+    Unix(PathBuf),
+}
+
+pub fn parse_url(url: &str) -> Result<ClamdAddr, String> {
+    let rest = url
+        .strip_prefix("clamd://")
+        .ok_or_else(|| format!("'{url}' is not a clamd:// URL"))?;
+
+    if rest.starts_with('/') {
+        if cfg!(unix) {
+            return Ok(ClamdAddr::Unix(PathBuf::from(rest)));
+        }
+        return Err("clamd sockets are only supported on Unix".to_string());
+    }
+
+    if rest.is_empty() {
+        return Err(format!("'{url}' has no host"));
+    }
+
+    Ok(ClamdAddr::Tcp(if rest.contains(':') {
+        rest.to_string()
+    } else {
+        format!("{rest}:{DEFAULT_PORT}")
+    }))
+}
+
+pub enum Verdict {
+    Clean,
+    Infected(String),
+}
+
+fn instream(mut stream: impl Read + Write, path: &Path) -> io::Result<Verdict> {
+    stream.write_all(b"zINSTREAM\0")?;
+
+    let mut file = File::open(path)?;
+    let mut chunk = vec![0; CHUNK_SIZE];
+    loop {
+        let read = file.read(&mut chunk)?;
+        if read == 0 {
+            break;
+        }
+        stream.write_all(&(read as u32).to_be_bytes())?;
+        stream.write_all(&chunk[..read])?;
+    }
+    stream.write_all(&[0; 4])?;
+
+    let mut reply = Vec::new();
+    stream.read_to_end(&mut reply)?;
+    let reply = String::from_utf8_lossy(&reply);
+    let reply = reply.trim_end_matches(['\0', '\n']);
+
+    // "stream: OK", "stream: <signature> FOUND" or "<message> ERROR".
+    match reply.strip_prefix("stream: ") {
+        Some("OK") => Ok(Verdict::Clean),
+        Some(found) if found.ends_with(" FOUND") => Ok(Verdict::Infected(
+            found.trim_end_matches(" FOUND").to_string(),
+        )),
+        _ => Err(io::Error::other(format!("clamd: {reply}"))),
+    }
+}
+
+/// Streams a file to clamd and returns its verdict.
+pub fn scan(addr: &ClamdAddr, path: &Path) -> io::Result<Verdict> {
+    match addr {
+        ClamdAddr::Tcp(host) => {
+            let stream = TcpStream::connect(host)?;
+            stream.set_read_timeout(Some(Duration::from_secs(300)))?;
+            instream(stream, path)
+        }
+        #[cfg(unix)]
+        ClamdAddr::Unix(socket) => instream(std::os::unix::net::UnixStream::connect(socket)?, path),
+        #[cfg(not(unix))]
+        ClamdAddr::Unix(_) => Err(io::Error::other("clamd sockets are only supported on Unix")),
+    }
+}
+
+/// Checks that clamd is reachable before any file is touched.
+pub fn ping(addr: &ClamdAddr) -> io::Result<()> {
+    fn exchange(mut stream: impl Read + Write) -> io::Result<()> {
+        stream.write_all(b"zPING\0")?;
+        let mut reply = Vec::new();
+        stream.read_to_end(&mut reply)?;
+
+        if reply.starts_with(b"PONG") {
+            Ok(())
+        } else {
+            Err(io::Error::other("clamd did not answer PING"))
+        }
+    }
+
+    match addr {
+        ClamdAddr::Tcp(host) => exchange(TcpStream::connect(host)?),
+        #[cfg(unix)]
+        ClamdAddr::Unix(socket) => exchange(std::os::unix::net::UnixStream::connect(socket)?),
+        #[cfg(not(unix))]
+        ClamdAddr::Unix(_) => Err(io::Error::other("clamd sockets are only supported on Unix")),
+    }
+}
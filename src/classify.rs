@@ -0,0 +1,107 @@
+use {
+    crate::rename::sanitize,
+    std::{
+        fs::{self, File},
+        io::{self, Read},
+        path::{Path, PathBuf},
+        process,
+    },
+};
+
+/// The name the stream is given when `--name` is left out.
+const DEFAULT_NAME: &str = "stdin";
+
+/// Stdin written to a temporary file, so it can be categorized like any
+/// other file. The file is removed again when this is dropped.
+pub struct Spooled {
+    dir: PathBuf,
+    path: PathBuf,
+}
+
+impl Spooled {
+    /// Reads all of stdin into a file named after `name`. Without an
+    /// extension, one is guessed from the first bytes.
+    pub fn from_stdin(name: Option<&str>) -> io::Result<Self> {
+        let dir = std::env::temp_dir().join(format!("dirsort-classify-{}", process::id()));
+        fs::create_dir_all(&dir)?;
+        let mut spooled = Self {
+            path: dir.join(file_name(name)),
+            dir,
+        };
+
+        io::copy(&mut io::stdin().lock(), &mut File::create(&spooled.path)?)?;
+
+        if spooled.path.extension().is_none()
+            && let Some(ext) = sniff(&spooled.path)
+        {
+            let path = spooled.path.with_extension(ext);
+            fs::rename(&spooled.path, &path)?;
+            spooled.path = path;
+        }
+        Ok(spooled)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for Spooled {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Only the last component of `name` is used, so `--name` can't write
+/// outside the temporary directory.
+fn file_name(name: Option<&str>) -> String {
+    name.and_then(|name| Path::new(name).file_name())
+        .map(|name| sanitize(&name.to_string_lossy()))
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| DEFAULT_NAME.to_string())
+}
+
+/// Magic bytes at an offset, and the extension they stand for. More
+/// specific signatures come before the ones they share a prefix with.
+const SIGNATURES: &[(usize, &[u8], &str)] = &[
+    (0, b"\x89PNG", "png"),
+    (0, b"\xFF\xD8\xFF", "jpg"),
+    (0, b"GIF8", "gif"),
+    (8, b"WEBP", "webp"),
+    (8, b"WAVE", "wav"),
+    (8, b"AVI ", "avi"),
+    (4, b"ftypheic", "heic"),
+    (4, b"ftypqt", "mov"),
+    (4, b"ftypM4A", "m4a"),
+    (4, b"ftyp", "mp4"),
+    (0, b"\x1A\x45\xDF\xA3", "mkv"),
+    (0, b"ID3", "mp3"),
+    (0, b"\xFF\xFB", "mp3"),
+    (0, b"fLaC", "flac"),
+    (0, b"OggS", "ogg"),
+    (0, b"%PDF", "pdf"),
+    (0, b"PK\x03\x04", "zip"),
+    (0, b"\x1F\x8B", "gz"),
+    (0, b"7z\xBC\xAF\x27\x1C", "7z"),
+    (0, b"Rar!", "rar"),
+    (0, b"\xFD7zXZ\x00", "xz"),
+    (0, b"\x28\xB5\x2F\xFD", "zst"),
+];
+
+/// Guesses an extension from the magic bytes at the start of the file.
+fn sniff(path: &Path) -> Option<&'static str> {
+    let mut header = [0; 16];
+    let read = File::open(path)
+        .and_then(|mut file| file.read(&mut header))
+        .ok()?;
+    let header = &header[..read];
+
+    SIGNATURES
+        .iter()
+        .find(|(offset, magic, _)| {
+            header
+                .get(*offset..)
+                .is_some_and(|rest| rest.starts_with(magic))
+        })
+        .map(|(_, _, ext)| *ext)
+}
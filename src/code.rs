@@ -0,0 +1,88 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Read},
+    path::Path,
+};
+
+/// Language name, file extensions, and shebang interpreters.
+const LANGUAGES: &[(&str, &[&str], &[&str])] = &[
+    ("Rust", &["rs"], &[]),
+    (
+        "Python",
+        &["py", "pyw", "pyi", "ipynb"],
+        &["python", "python2", "python3"],
+    ),
+    ("JavaScript", &["js", "mjs", "cjs", "jsx"], &["node"]),
+    (
+        "TypeScript",
+        &["ts", "mts", "cts", "tsx"],
+        &["deno", "bun", "ts-node"],
+    ),
+    ("C", &["c", "h"], &[]),
+    ("C++", &["cpp", "cc", "cxx", "hpp", "hh", "hxx"], &[]),
+    ("C#", &["cs", "csx"], &[]),
+    ("Go", &["go"], &[]),
+    ("Java", &["java"], &[]),
+    ("Kotlin", &["kt", "kts"], &[]),
+    ("Swift", &["swift"], &[]),
+    ("Ruby", &["rb"], &["ruby"]),
+    ("PHP", &["php"], &["php"]),
+    (
+        "Shell",
+        &["sh", "bash", "zsh", "fish", "ksh"],
+        &["sh", "bash", "zsh", "fish", "ksh", "dash"],
+    ),
+    ("PowerShell", &["ps1", "psm1", "psd1"], &["pwsh"]),
+    ("Lua", &["lua"], &["lua", "luajit"]),
+    ("Perl", &["pl", "pm"], &["perl"]),
+    ("Haskell", &["hs", "lhs"], &["runghc", "runhaskell"]),
+    ("Scala", &["scala", "sc"], &["scala"]),
+    ("Zig", &["zig"], &[]),
+    ("Nim", &["nim"], &[]),
+    ("Dart", &["dart"], &[]),
+    ("R", &["r"], &["Rscript"]),
+    ("SQL", &["sql"], &[]),
+];
+
+pub fn by_extension(ext: &str) -> Option<&'static str> {
+    let ext = ext.to_lowercase();
+
+    LANGUAGES
+        .iter()
+        .find(|(_, exts, _)| exts.contains(&ext.as_str()))
+        .map(|(language, _, _)| *language)
+}
+
+/// Reads `#!/usr/bin/env python3`-style first lines.
+fn by_shebang(path: &Path) -> Option<&'static str> {
+    let mut first_line = Vec::new();
+    BufReader::new(File::open(path).ok()?.take(256))
+        .read_until(b'\n', &mut first_line)
+        .ok()?;
+
+    let line = std::str::from_utf8(first_line.strip_prefix(b"#!")?).ok()?;
+    let mut words = line.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+
+    if program == "env" {
+        program = words.find(|word| !word.starts_with('-'))?;
+    }
+
+    // `python3.12` should match `python3`.
+    let program = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+
+    LANGUAGES
+        .iter()
+        .find(|(_, _, interpreters)| {
+            interpreters.iter().any(|interpreter| {
+                interpreter.trim_end_matches(|c: char| c.is_ascii_digit()) == program
+            })
+        })
+        .map(|(language, _, _)| *language)
+}
+
+/// Figures out the programming language of a source file, by extension
+/// first and by its shebang line otherwise.
+pub fn language(path: &Path, ext: Option<&str>) -> Option<&'static str> {
+    ext.and_then(by_extension).or_else(|| by_shebang(path))
+}
@@ -0,0 +1,104 @@
+use {
+    crate::plan::Action,
+    std::{
+        io::{self, BufRead, Write},
+        path::Path,
+        sync::{
+            Mutex,
+            atomic::{AtomicBool, AtomicU64, Ordering},
+        },
+    },
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ConfirmMode {
+    /// Ask before every file
+    All,
+    /// Ask only when the destination is already taken
+    Conflicts,
+}
+
+/// Asks before placing files with `--confirm`. Workers take turns at the
+/// prompt, and answering `a` or `q` settles every file that comes after.
+pub struct Confirmer {
+    mode: ConfirmMode,
+    prompt: Mutex<()>,
+    always: AtomicBool,
+    quit: AtomicBool,
+    pub declined: AtomicU64,
+}
+
+impl Confirmer {
+    pub fn new(mode: ConfirmMode) -> Self {
+        Self {
+            mode,
+            prompt: Mutex::new(()),
+            always: AtomicBool::new(false),
+            quit: AtomicBool::new(false),
+            declined: AtomicU64::new(0),
+        }
+    }
+
+    /// Whether `q` was answered, after which nothing else should be placed.
+    pub fn quit(&self) -> bool {
+        self.quit.load(Ordering::Relaxed)
+    }
+
+    /// Whether the file at `source` should be placed at `dest`.
+    pub fn allows(&self, verb: &str, source: &Path, dest: &Path, action: Action) -> bool {
+        if self.mode == ConfirmMode::Conflicts && action == Action::New {
+            return !self.quit();
+        }
+
+        let _turn = self.prompt.lock().unwrap_or_else(|e| e.into_inner());
+        if self.quit() {
+            return false;
+        }
+        if self.always.load(Ordering::Relaxed) {
+            return true;
+        }
+
+        let note = match action {
+            Action::New => "",
+            Action::Rename => " (renamed)",
+            Action::Overwrite => " (overwrites)",
+            Action::Skip => " (destination exists)",
+        };
+
+        loop {
+            print!(
+                "{verb} '{}' to '{}'{note}? [y/n/a/q] ",
+                source.display(),
+                dest.display()
+            );
+            if io::stdout().flush().is_err() {
+                self.quit.store(true, Ordering::Relaxed);
+                return false;
+            }
+
+            let mut answer = String::new();
+            // A closed stdin can't answer, so treat it like quitting.
+            if io::stdin().lock().read_line(&mut answer).unwrap_or(0) == 0 {
+                self.quit.store(true, Ordering::Relaxed);
+                return false;
+            }
+
+            match answer.trim().to_lowercase().as_str() {
+                "y" | "yes" => return true,
+                "n" | "no" => {
+                    self.declined.fetch_add(1, Ordering::Relaxed);
+                    return false;
+                }
+                "a" | "always" => {
+                    self.always.store(true, Ordering::Relaxed);
+                    return true;
+                }
+                "q" | "quit" => {
+                    self.quit.store(true, Ordering::Relaxed);
+                    return false;
+                }
+                _ => println!("Answer y(es), n(o), a(lways) or q(uit)."),
+            }
+        }
+    }
+}
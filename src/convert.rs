@@ -0,0 +1,99 @@
+use {
+    image::ImageFormat,
+    std::{
+        fmt, io,
+        path::{Path, PathBuf},
+        process::Command,
+    },
+};
+
+/// Where the files a conversion replaced are kept, under the folder the
+/// converted copy goes into.
+pub const ORIGINALS_DIR: &str = "Originals";
+
+/// Formats the `image` crate has no decoder for, read with `heif-convert`
+/// from libheif instead.
+const HEIF: &[&str] = &["heic", "heif"];
+
+/// A `--convert` pair, e.g. `heic:jpg`.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Conversion {
+    pub from: String,
+    pub to: String,
+}
+
+impl fmt::Display for Conversion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.from, self.to)
+    }
+}
+
+/// Parses one `--convert` pair, e.g. 'webp:png'.
+pub fn parse(pair: &str) -> Result<Conversion, String> {
+    let (from, to) = pair
+        .split_once(':')
+        .ok_or_else(|| format!("invalid conversion '{pair}', expected e.g. 'heic:jpg'"))?;
+    let from = from.trim().trim_start_matches('.').to_lowercase();
+    let to = to.trim().trim_start_matches('.').to_lowercase();
+    if from.is_empty() || from == to {
+        return Err(format!(
+            "invalid conversion '{pair}', expected e.g. 'heic:jpg'"
+        ));
+    }
+    if !ImageFormat::from_extension(&to).is_some_and(|format| format.writing_enabled()) {
+        return Err(format!("can't convert images to '{to}'"));
+    }
+    Ok(Conversion { from, to })
+}
+
+impl Conversion {
+    /// Whether the images can be read: by the `image` crate, or for HEIF
+    /// with `heif-convert` in PATH. `heif-convert` only writes JPEG and PNG.
+    pub fn available(&self) -> bool {
+        match HEIF.contains(&self.from.as_str()) {
+            true => matches!(self.to.as_str(), "jpg" | "jpeg" | "png") && heif_convert_available(),
+            false => ImageFormat::from_extension(&self.from)
+                .is_some_and(|format| format.reading_enabled()),
+        }
+    }
+
+    /// The file `source` is converted into inside `dir`, e.g.
+    /// `dir/photo.jpg` for `photo.HEIC`.
+    pub fn dest(&self, source: &Path, dir: &Path) -> PathBuf {
+        let stem = source.file_stem().unwrap_or_default().to_string_lossy();
+        dir.join(format!("{stem}.{}", self.to))
+    }
+
+    /// Writes `source` converted into `dest`.
+    pub fn run(&self, source: &Path, dest: &Path) -> io::Result<()> {
+        if HEIF.contains(&self.from.as_str()) {
+            let output = Command::new("heif-convert")
+                .arg("-q")
+                .arg("95")
+                .arg(source)
+                .arg(dest)
+                .output()?;
+            if !output.status.success() {
+                return Err(io::Error::other(format!(
+                    "heif-convert failed: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                )));
+            }
+            return Ok(());
+        }
+
+        let image = image::open(source).map_err(io::Error::other)?;
+        // JPEG has no alpha channel.
+        let image = match self.to.as_str() {
+            "jpg" | "jpeg" => image::DynamicImage::ImageRgb8(image.to_rgb8()),
+            _ => image,
+        };
+        image.save(dest).map_err(io::Error::other)
+    }
+}
+
+fn heif_convert_available() -> bool {
+    // `heif-convert` has no version flag and exits non-zero without
+    // arguments, so finding it at all is enough.
+    Command::new("heif-convert").arg("--help").output().is_ok()
+}
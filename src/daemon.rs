@@ -0,0 +1,222 @@
+use {
+    crate::{i18n::t, init, pause},
+    chrono::{DateTime, Local},
+    croner::Cron,
+    serde::{Deserialize, Serialize},
+    std::{
+        env,
+        error::Error,
+        path::{Path, PathBuf},
+        process,
+        str::FromStr,
+        sync::{Arc, Mutex, mpsc},
+        time::Instant,
+    },
+    tracing::{error, info, warn},
+};
+
+/// A directory `dirsort daemon` sorts on its own.
+#[derive(Serialize, Deserialize)]
+pub struct Watch {
+    /// Directory to sort, `~/` is expanded
+    pub path: String,
+    /// Cron expression for when to sort it, e.g. "0 3 * * *" for every night at 3
+    pub schedule: Option<String>,
+    /// Extra command line arguments for each run, e.g. ["--move", "-o", "/srv/sorted"]
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+struct Job<'a> {
+    watch: &'a Watch,
+    path: PathBuf,
+    cron: Cron,
+    next: DateTime<Local>,
+}
+
+/// What the daemon is doing, as reported over D-Bus.
+#[derive(Default)]
+pub struct Status {
+    /// The directory being sorted and since when
+    pub running: Option<(PathBuf, Instant)>,
+    /// Directories requested over D-Bus that haven't been sorted yet
+    pub queued: Vec<PathBuf>,
+}
+
+/// Sorts every `[[watch]]` directory whenever its schedule comes up, by
+/// running dirsort in it with the same config. On Linux it also takes
+/// requests over D-Bus as `org.dirsort.Sorter`. Runs until killed.
+pub fn run(
+    config: Option<&String>,
+    watches: &[Watch],
+    index_auto: bool,
+) -> Result<(), Box<dyn Error>> {
+    let home = init::home_dir();
+    let now = Local::now();
+    let expand = |path: &str| match &home {
+        Some(home) => init::expand_home(path, home),
+        None => PathBuf::from(path),
+    };
+
+    let mut jobs = Vec::new();
+    for watch in watches {
+        let Some(schedule) = &watch.schedule else {
+            continue;
+        };
+        let cron = Cron::from_str(schedule)
+            .map_err(|e| format!("invalid schedule '{schedule}' for '{}': {e}", watch.path))?;
+        let next = cron.find_next_occurrence(&now, false)?;
+        jobs.push(Job {
+            watch,
+            path: expand(&watch.path),
+            cron,
+            next,
+        });
+    }
+
+    let status = Arc::new(Mutex::new(Status::default()));
+    let (sender, requests) = mpsc::channel();
+    #[cfg(target_os = "linux")]
+    let bus = match crate::dbus::serve(
+        watches.iter().map(|watch| expand(&watch.path)).collect(),
+        sender.clone(),
+        status.clone(),
+    ) {
+        Ok(bus) => Some(bus),
+        Err(e) => {
+            warn!("{}", t!("daemon-dbus-failed", error = e.to_string()));
+            None
+        }
+    };
+    #[cfg(not(target_os = "linux"))]
+    let bus: Option<()> = {
+        // Nothing sends requests here, but the channel has to stay open.
+        let _ = &sender;
+        None
+    };
+
+    if jobs.is_empty() && bus.is_none() {
+        return Err("no [[watch]] entries with a schedule in the config".into());
+    }
+    for watch in watches.iter().filter(|watch| watch.schedule.is_none()) {
+        warn!("{}", t!("daemon-no-schedule", dir = watch.path.as_str()));
+    }
+
+    let exe = env::current_exe()?;
+    // Runs start in the watched directory, so a relative config would break.
+    let config = config.map(|config| env::current_dir().map(|dir| dir.join(config)));
+    let config = config.transpose()?;
+    for job in &jobs {
+        info!(
+            "{}",
+            t!(
+                "daemon-scheduled",
+                dir = job.path.display().to_string(),
+                schedule = job.cron.pattern.to_string(),
+                next = job.next.format("%Y-%m-%d %H:%M").to_string()
+            )
+        );
+    }
+
+    loop {
+        let due = jobs.iter().map(|job| job.next).min();
+        let wait = due.map(|due| (due - Local::now()).to_std().unwrap_or_default());
+        let requested = match wait {
+            Some(wait) => requests.recv_timeout(wait).ok(),
+            // `sender` is still around, so this only returns with a request.
+            None => requests.recv().ok(),
+        };
+
+        let (path, args) = match requested {
+            Some(path) => {
+                status
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .queued
+                    .retain(|queued| *queued != path);
+                let args = watches
+                    .iter()
+                    .find(|watch| expand(&watch.path) == path)
+                    .map_or(&[][..], |watch| &watch.args[..]);
+                (path, args)
+            }
+            None => {
+                let Some(job) = jobs.iter_mut().min_by_key(|job| job.next) else {
+                    continue;
+                };
+                if job.next > Local::now() {
+                    continue;
+                }
+                // Runs that took longer than the schedule skip what they overlapped.
+                job.next = job.cron.find_next_occurrence(&Local::now(), false)?;
+                (job.path.clone(), &job.watch.args[..])
+            }
+        };
+
+        // A paused daemon holds back runs until it is resumed.
+        pause::wait();
+
+        status.lock().unwrap_or_else(|e| e.into_inner()).running =
+            Some((path.clone(), Instant::now()));
+        #[cfg(target_os = "linux")]
+        if let Some(bus) = &bus {
+            let _ = bus.started(&path);
+        }
+
+        let success = sort(&exe, config.as_deref(), &path, args, index_auto);
+
+        status.lock().unwrap_or_else(|e| e.into_inner()).running = None;
+        #[cfg(target_os = "linux")]
+        if let Some(bus) = &bus {
+            let _ = bus.finished(&path, success);
+        }
+    }
+}
+
+/// Runs dirsort in `path` and waits for it, forwarding pause signals.
+fn sort(exe: &Path, config: Option<&Path>, path: &Path, args: &[String], index_auto: bool) -> bool {
+    info!("{}", t!("daemon-sorting", dir = path.display().to_string()));
+    let mut command = process::Command::new(exe);
+    // Nobody is there to answer the estimate's prompt.
+    command.arg("--yes");
+    if let Some(config) = config {
+        command.arg("--config").arg(config);
+    }
+    if index_auto {
+        command.arg("--index-auto");
+    }
+    match command
+        .args(args)
+        .current_dir(path)
+        .spawn()
+        .and_then(|mut child| {
+            pause::forward_to(Some(child.id()));
+            let status = child.wait();
+            pause::forward_to(None);
+            status
+        }) {
+        Ok(status) if status.success() => true,
+        Ok(status) => {
+            error!(
+                "{}",
+                t!(
+                    "daemon-failed",
+                    dir = path.display().to_string(),
+                    status = status.to_string()
+                )
+            );
+            false
+        }
+        Err(e) => {
+            error!(
+                "{}",
+                t!(
+                    "daemon-start-failed",
+                    dir = path.display().to_string(),
+                    error = e.to_string()
+                )
+            );
+            false
+        }
+    }
+}
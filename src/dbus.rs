@@ -0,0 +1,120 @@
+use {
+    crate::{daemon::Status, pause},
+    std::{
+        path::{Path, PathBuf},
+        sync::{Arc, Mutex, mpsc::Sender},
+    },
+    zbus::{blocking::Connection, fdo, object_server::SignalEmitter},
+};
+
+const NAME: &str = "org.dirsort.Sorter";
+const PATH: &str = "/org/dirsort/Sorter";
+
+/// `dirsort daemon` on the session bus.
+struct Sorter {
+    watched: Vec<PathBuf>,
+    requests: Sender<PathBuf>,
+    status: Arc<Mutex<Status>>,
+}
+
+#[zbus::interface(name = "org.dirsort.Sorter")]
+impl Sorter {
+    /// Queues a sort of `path`, or of every watched directory if it is empty.
+    fn sort(&self, path: String) -> fdo::Result<()> {
+        let paths = if path.is_empty() {
+            self.watched.clone()
+        } else {
+            let path = PathBuf::from(path);
+            if !path.is_absolute() || !path.is_dir() {
+                return Err(fdo::Error::InvalidArgs(format!(
+                    "'{}' is not an absolute path to a directory",
+                    path.display()
+                )));
+            }
+            vec![path]
+        };
+
+        let mut status = self.status.lock().unwrap_or_else(|e| e.into_inner());
+        for path in paths {
+            status.queued.push(path.clone());
+            self.requests
+                .send(path)
+                .map_err(|e| fdo::Error::Failed(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// The directory being sorted (empty when idle), for how many seconds,
+    /// and the directories queued after it.
+    fn status(&self) -> (String, u64, Vec<String>) {
+        let status = self.status.lock().unwrap_or_else(|e| e.into_inner());
+        let (running, elapsed) = status.running.as_ref().map_or_else(
+            || (String::new(), 0),
+            |(path, started)| (path.display().to_string(), started.elapsed().as_secs()),
+        );
+        let queued = status
+            .queued
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        (running, elapsed, queued)
+    }
+
+    /// Pauses the run in progress after the files being transferred, and
+    /// holds back the next ones.
+    fn pause(&self) {
+        pause::set_paused(true);
+    }
+
+    /// Resumes after `Pause`.
+    fn resume(&self) {
+        pause::set_paused(false);
+    }
+
+    #[zbus(signal)]
+    async fn started(emitter: &SignalEmitter<'_>, path: &str) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn finished(emitter: &SignalEmitter<'_>, path: &str, success: bool) -> zbus::Result<()>;
+}
+
+/// The daemon's connection to the session bus, kept open while it runs.
+pub struct Bus(Connection);
+
+/// Registers `org.dirsort.Sorter` on the session bus. Requested sorts are
+/// sent to `requests`.
+pub fn serve(
+    watched: Vec<PathBuf>,
+    requests: Sender<PathBuf>,
+    status: Arc<Mutex<Status>>,
+) -> zbus::Result<Bus> {
+    let sorter = Sorter {
+        watched,
+        requests,
+        status,
+    };
+    let connection = zbus::blocking::connection::Builder::session()?
+        .name(NAME)?
+        .serve_at(PATH, sorter)?
+        .build()?;
+    Ok(Bus(connection))
+}
+
+impl Bus {
+    pub fn started(&self, path: &Path) -> zbus::Result<()> {
+        let sorter = self.0.object_server().interface::<_, Sorter>(PATH)?;
+        zbus::block_on(Sorter::started(
+            sorter.signal_emitter(),
+            &path.display().to_string(),
+        ))
+    }
+
+    pub fn finished(&self, path: &Path, success: bool) -> zbus::Result<()> {
+        let sorter = self.0.object_server().interface::<_, Sorter>(PATH)?;
+        zbus::block_on(Sorter::finished(
+            sorter.signal_emitter(),
+            &path.display().to_string(),
+            success,
+        ))
+    }
+}
@@ -0,0 +1,159 @@
+use {
+    crate::{
+        SorterConfig, categorize,
+        hashing::{self, HashAlgorithm},
+        tui::human_bytes,
+    },
+    rayon::iter::{IntoParallelIterator, ParallelIterator},
+    std::{
+        collections::{BTreeMap, BTreeSet},
+        io::{self, Write},
+        path::{Path, PathBuf},
+    },
+    walkdir::WalkDir,
+};
+
+/// Files and bytes in one category of a directory.
+#[derive(Default, Clone, Copy)]
+struct Totals {
+    files: u64,
+    bytes: u64,
+}
+
+/// Every file under a directory, by its path relative to it.
+struct Listing {
+    files: BTreeMap<PathBuf, u64>,
+    categories: BTreeMap<String, Totals>,
+}
+
+impl Listing {
+    fn scan(dir: &Path, config: &SorterConfig, screenshot_patterns: &[glob::Pattern]) -> Self {
+        let mut listing = Listing {
+            files: BTreeMap::new(),
+            categories: BTreeMap::new(),
+        };
+        for entry in WalkDir::new(dir).into_iter().filter_map(Result::ok) {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if !metadata.is_file() {
+                continue;
+            }
+            let path = entry.path();
+            let ext = path.extension().and_then(|ext| ext.to_str());
+            let category = categorize(path, ext, config, screenshot_patterns)
+                .unwrap_or_else(|| config.fallback.clone());
+            let totals = listing.categories.entry(category).or_default();
+            totals.files += 1;
+            totals.bytes += metadata.len();
+
+            let relative = path.strip_prefix(dir).unwrap_or(path).to_path_buf();
+            listing.files.insert(relative, metadata.len());
+        }
+        listing
+    }
+}
+
+/// `dirsort diff`: prints the files only one of `a` and `b` has, the files
+/// both have under the same path but with different contents, and how each
+/// category's totals change from `a` to `b`.
+pub fn run(
+    a: &Path,
+    b: &Path,
+    config: &SorterConfig,
+    screenshot_patterns: &[glob::Pattern],
+    algorithm: HashAlgorithm,
+) -> io::Result<()> {
+    for dir in [a, b] {
+        if !dir.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("'{}' is not a directory", dir.display()),
+            ));
+        }
+    }
+    let left = Listing::scan(a, config, screenshot_patterns);
+    let right = Listing::scan(b, config, screenshot_patterns);
+
+    let only_left: Vec<&PathBuf> = left
+        .files
+        .keys()
+        .filter(|path| !right.files.contains_key(*path))
+        .collect();
+    let only_right: Vec<&PathBuf> = right
+        .files
+        .keys()
+        .filter(|path| !left.files.contains_key(*path))
+        .collect();
+
+    let shared: Vec<(&PathBuf, u64, u64)> = left
+        .files
+        .iter()
+        .filter_map(|(path, size)| Some((path, *size, *right.files.get(path)?)))
+        .collect();
+    let mut changed: Vec<&PathBuf> = shared
+        .into_par_iter()
+        .filter(|(path, left_size, right_size)| {
+            left_size != right_size || {
+                let left_hash = hashing::hash_file(&a.join(path), algorithm);
+                let right_hash = hashing::hash_file(&b.join(path), algorithm);
+                // A file that can't be read can't be shown to be the same.
+                !matches!((left_hash, right_hash), (Ok(l), Ok(r)) if l == r)
+            }
+        })
+        .map(|(path, _, _)| path)
+        .collect();
+    changed.sort();
+
+    let mut stdout = io::stdout().lock();
+    list(&mut stdout, &format!("Only in {}", a.display()), &only_left)?;
+    list(
+        &mut stdout,
+        &format!("Only in {}", b.display()),
+        &only_right,
+    )?;
+    list(&mut stdout, "Different contents", &changed)?;
+
+    let categories: BTreeSet<&String> = left
+        .categories
+        .keys()
+        .chain(right.categories.keys())
+        .collect();
+    let name_width = categories
+        .iter()
+        .map(|name| name.len())
+        .max()
+        .unwrap_or_default();
+    writeln!(stdout, "Categories:")?;
+    for category in categories {
+        let before = left.categories.get(category).copied().unwrap_or_default();
+        let after = right.categories.get(category).copied().unwrap_or_default();
+        writeln!(
+            stdout,
+            "  {category:<name_width$}  {:>6} → {:<6} files ({:>+6})  {:>10} → {:<10} ({})",
+            before.files,
+            after.files,
+            after.files as i64 - before.files as i64,
+            human_bytes(before.bytes),
+            human_bytes(after.bytes),
+            signed_bytes(before.bytes, after.bytes)
+        )?;
+    }
+    Ok(())
+}
+
+fn list(out: &mut impl Write, heading: &str, paths: &[&PathBuf]) -> io::Result<()> {
+    writeln!(out, "{heading} ({}):", paths.len())?;
+    for path in paths {
+        writeln!(out, "  {}", path.display())?;
+    }
+    writeln!(out)
+}
+
+fn signed_bytes(before: u64, after: u64) -> String {
+    match after.cmp(&before) {
+        std::cmp::Ordering::Less => format!("-{}", human_bytes(before - after)),
+        std::cmp::Ordering::Equal => "±0".to_string(),
+        std::cmp::Ordering::Greater => format!("+{}", human_bytes(after - before)),
+    }
+}
@@ -0,0 +1,155 @@
+use {
+    lopdf::{Document, Object},
+    std::{fs::File, io::Read, path::Path},
+    zip::ZipArchive,
+};
+
+/// Plain-text files `keywords` rules search, besides PDFs.
+const TEXT_EXTENSIONS: &[&str] = &["txt", "md", "csv", "log", "html", "xml", "json", "rtf"];
+
+/// Only the start of a text file is searched, which is where invoice numbers
+/// and letterheads are.
+const TEXT_LIMIT: u64 = 1024 * 1024;
+
+/// Likewise, only the first pages of a PDF.
+const PDF_PAGES: u32 = 10;
+
+#[derive(Default)]
+pub struct DocMeta {
+    pub title: Option<String>,
+    pub author: Option<String>,
+}
+
+/// Decodes a PDF text string, which is either UTF-16BE with a byte order
+/// mark or (close enough to) Latin-1.
+fn decode_pdf_string(bytes: &[u8]) -> String {
+    match bytes.strip_prefix(&[0xFE, 0xFF]) {
+        Some(utf16) => String::from_utf16_lossy(
+            &utf16
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect::<Vec<_>>(),
+        ),
+        None => bytes.iter().map(|&b| char::from(b)).collect(),
+    }
+}
+
+fn non_empty(value: String) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// Reads the title and author from a PDF's document information dictionary.
+pub fn pdf_meta(path: &Path) -> Option<DocMeta> {
+    let document = Document::load(path).ok()?;
+    let info = match document.trailer.get(b"Info").ok()? {
+        Object::Reference(id) => document.get_object(*id).ok()?,
+        object => object,
+    };
+    let info = info.as_dict().ok()?;
+    let field = |key: &[u8]| {
+        info.get(key)
+            .ok()
+            .and_then(|value| value.as_str().ok())
+            .map(decode_pdf_string)
+            .and_then(non_empty)
+    };
+
+    Some(DocMeta {
+        title: field(b"Title"),
+        author: field(b"Author"),
+    })
+}
+
+/// Finds the start of the first `<tag>` or `<tag ...>`, without mistaking
+/// `<rootfiles>` for `<rootfile>`.
+fn find_element(xml: &str, tag: &str) -> Option<usize> {
+    let needle = format!("<{tag}");
+    let mut offset = 0;
+
+    loop {
+        let found = offset + xml[offset..].find(&needle)?;
+        let next = xml[found + needle.len()..].chars().next();
+
+        if next.is_some_and(|c| c == '>' || c == '/' || c.is_whitespace()) {
+            return Some(found);
+        }
+
+        offset = found + needle.len();
+    }
+}
+
+/// Returns the text of the first `<tag ...>text</tag>` element.
+fn xml_text(xml: &str, tag: &str) -> Option<String> {
+    let open = find_element(xml, tag)?;
+    let start = open + xml[open..].find('>')? + 1;
+    let end = start + xml[start..].find(&format!("</{tag}>"))?;
+
+    let text = xml[start..end]
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&");
+
+    non_empty(text)
+}
+
+/// Returns the value of `attr="..."` on the first `<tag ...>` element.
+fn xml_attr(xml: &str, tag: &str, attr: &str) -> Option<String> {
+    let open = find_element(xml, tag)?;
+    let element = &xml[open..open + xml[open..].find('>')?];
+    let start = element.find(&format!("{attr}=\""))? + attr.len() + 2;
+    let end = start + element[start..].find('"')?;
+
+    Some(element[start..end].to_string())
+}
+
+fn read_zip_entry(archive: &mut ZipArchive<File>, name: &str) -> Option<String> {
+    let mut content = String::new();
+    archive
+        .by_name(name)
+        .ok()?
+        .read_to_string(&mut content)
+        .ok()?;
+
+    Some(content)
+}
+
+/// Reads the title and first creator from an EPUB's OPF package document.
+pub fn epub_meta(path: &Path) -> Option<DocMeta> {
+    let mut archive = ZipArchive::new(File::open(path).ok()?).ok()?;
+    let container = read_zip_entry(&mut archive, "META-INF/container.xml")?;
+    let opf_path = xml_attr(&container, "rootfile", "full-path")?;
+    let opf = read_zip_entry(&mut archive, &opf_path)?;
+
+    Some(DocMeta {
+        title: xml_text(&opf, "dc:title"),
+        author: xml_text(&opf, "dc:creator"),
+    })
+}
+
+/// The text of a PDF's text layer or of a plain-text file, lowercased, for
+/// `keywords` rules. Scanned PDFs without a text layer have none.
+pub fn text(path: &Path, ext: &str) -> Option<String> {
+    let text = if ext == "pdf" {
+        let document = Document::load(path).ok()?;
+        let pages: Vec<u32> = document
+            .get_pages()
+            .into_keys()
+            .take(PDF_PAGES as usize)
+            .collect();
+        document.extract_text(&pages).ok()?
+    } else if TEXT_EXTENSIONS.contains(&ext) {
+        let mut bytes = Vec::new();
+        File::open(path)
+            .ok()?
+            .take(TEXT_LIMIT)
+            .read_to_end(&mut bytes)
+            .ok()?;
+        String::from_utf8_lossy(&bytes).into_owned()
+    } else {
+        return None;
+    };
+    Some(text.to_lowercase())
+}
@@ -0,0 +1,101 @@
+use std::{fs, path::Path, thread, time::Duration};
+
+/// Where Chrome and Firefox record the URL a file was downloaded from on Linux.
+#[cfg(all(unix, not(target_os = "macos")))]
+const ORIGIN_URL_ATTR: &str = "user.xdg.origin.url";
+
+/// The download URL and the page it was linked from, as a binary plist.
+#[cfg(target_os = "macos")]
+const WHERE_FROMS_ATTR: &str = "com.apple.metadata:kMDItemWhereFroms";
+
+/// How long to watch a recently modified file for changes.
+const POLL: Duration = Duration::from_secs(1);
+
+/// Whether `path` was modified within `settle` and is still changing, like a
+/// download that is being written to.
+pub fn still_growing(path: &Path, settle: Duration) -> bool {
+    let Ok(before) = fs::metadata(path) else {
+        return false;
+    };
+
+    // An mtime in the future can't be trusted to mean "settled" either.
+    let recent = before
+        .modified()
+        .is_ok_and(|modified| modified.elapsed().map_or(true, |age| age < settle));
+    if !recent {
+        return false;
+    }
+
+    thread::sleep(POLL.min(settle));
+
+    fs::metadata(path).is_ok_and(|after| {
+        after.len() != before.len() || after.modified().ok() != before.modified().ok()
+    })
+}
+
+/// The URL a browser recorded downloading `path` from, if it did.
+#[cfg(target_os = "macos")]
+pub fn origin_url(path: &Path) -> Option<String> {
+    let raw = xattr::get(path, WHERE_FROMS_ATTR).ok()??;
+    plist::from_bytes::<Vec<String>>(&raw)
+        .ok()?
+        .into_iter()
+        .find(|url| !url.is_empty())
+}
+
+/// The URL a browser recorded downloading `path` from, if it did.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn origin_url(path: &Path) -> Option<String> {
+    let raw = xattr::get(path, ORIGIN_URL_ATTR).ok()??;
+    String::from_utf8(raw).ok().filter(|url| !url.is_empty())
+}
+
+/// The URL a browser recorded downloading `path` from, if it did, from the
+/// `Zone.Identifier` stream Windows attaches to downloads.
+#[cfg(windows)]
+pub fn origin_url(path: &Path) -> Option<String> {
+    let mut stream = path.as_os_str().to_os_string();
+    stream.push(":Zone.Identifier");
+    let zone = fs::read_to_string(stream).ok()?;
+    zone.lines()
+        .find_map(|line| line.strip_prefix("HostUrl="))
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty())
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn origin_url(_path: &Path) -> Option<String> {
+    None
+}
+
+/// `url` without credentials, its query string or fragment, which can hold
+/// session tokens and signed download links.
+pub fn redact(url: &str) -> String {
+    let url = url.split(['?', '#']).next().unwrap_or(url);
+    match url.split_once("://") {
+        Some((scheme, rest)) => {
+            let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+            let host = authority
+                .rsplit_once('@')
+                .map_or(authority, |(_, host)| host);
+            format!("{scheme}://{host}{path}")
+        }
+        None => url.to_string(),
+    }
+}
+
+/// The host of `url`, e.g. 'drive.google.com' for
+/// 'https://drive.google.com/uc?id=...'.
+pub fn domain(url: &str) -> Option<String> {
+    let url = url.strip_prefix("blob:").unwrap_or(url);
+    let (_, rest) = url.split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    let host = match host.strip_prefix('[') {
+        Some(ipv6) => ipv6.split(']').next()?,
+        None => host.split(':').next()?,
+    };
+    Some(host.to_lowercase()).filter(|host| !host.is_empty())
+}
@@ -0,0 +1,164 @@
+use std::{
+    cmp::Reverse,
+    collections::BTreeMap,
+    error::Error,
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+/// Everything that can stop dirsort, by the stage it happened in.
+#[derive(Debug, thiserror::Error)]
+pub enum DirsortError {
+    /// The config, blacklist or command line options are unusable
+    #[error("{context}: {source}")]
+    Config {
+        context: String,
+        #[source]
+        source: Box<dyn Error + Send + Sync>,
+    },
+    /// The directory to sort couldn't be read
+    #[error("failed to scan '{}': {source}", path.display())]
+    Scan {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    /// A file or directory couldn't be placed
+    #[error(transparent)]
+    Transfer(#[from] SortError),
+    /// The web server couldn't start
+    #[error("failed to serve on {addr}: {source}")]
+    Serve {
+        addr: String,
+        #[source]
+        source: io::Error,
+    },
+}
+
+impl DirsortError {
+    pub fn config(
+        context: impl Into<String>,
+        source: impl Into<Box<dyn Error + Send + Sync>>,
+    ) -> Self {
+        DirsortError::Config {
+            context: context.into(),
+            source: source.into(),
+        }
+    }
+}
+
+/// A file or directory that could not be placed.
+#[derive(Debug)]
+pub enum SortError {
+    /// Reading, writing, moving or copying failed
+    Io {
+        source: PathBuf,
+        dest: Option<PathBuf>,
+        error: io::Error,
+    },
+    /// Anything else, like an unreadable tag or an unreachable clamd
+    Other {
+        source: PathBuf,
+        dest: Option<PathBuf>,
+        message: String,
+    },
+}
+
+impl SortError {
+    pub fn new(source: &Path, dest: Option<PathBuf>, error: Box<dyn Error + Send + Sync>) -> Self {
+        let source = source.to_path_buf();
+        match error.downcast::<io::Error>() {
+            Ok(error) => SortError::Io {
+                source,
+                dest,
+                error: *error,
+            },
+            Err(error) => SortError::Other {
+                source,
+                dest,
+                message: error.to_string(),
+            },
+        }
+    }
+
+    /// The file or directory that failed, and where it was going.
+    pub fn paths(&self) -> (&Path, Option<&Path>) {
+        match self {
+            SortError::Io { source, dest, .. } | SortError::Other { source, dest, .. } => {
+                (source, dest.as_deref())
+            }
+        }
+    }
+
+    /// The underlying error, without the paths.
+    pub fn message(&self) -> String {
+        match self {
+            SortError::Io { error, .. } => error.to_string(),
+            SortError::Other { message, .. } => message.clone(),
+        }
+    }
+
+    /// What went wrong, for grouping errors in the summary.
+    pub fn kind(&self) -> &'static str {
+        let SortError::Io { error, .. } = self else {
+            return "other";
+        };
+        match error.kind() {
+            io::ErrorKind::NotFound => "not found",
+            io::ErrorKind::PermissionDenied => "permission denied",
+            io::ErrorKind::AlreadyExists => "already exists",
+            io::ErrorKind::StorageFull | io::ErrorKind::QuotaExceeded => "out of space",
+            io::ErrorKind::ReadOnlyFilesystem => "read-only filesystem",
+            io::ErrorKind::InvalidFilename => "invalid file name",
+            io::ErrorKind::ResourceBusy => "busy",
+            _ => "I/O error",
+        }
+    }
+}
+
+impl fmt::Display for SortError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (source, dest, error): (_, _, &dyn fmt::Display) = match self {
+            SortError::Io {
+                source,
+                dest,
+                error,
+            } => (source, dest, error),
+            SortError::Other {
+                source,
+                dest,
+                message,
+            } => (source, dest, message),
+        };
+        match dest {
+            Some(dest) => write!(
+                f,
+                "Failed to place '{}' at '{}': {error}",
+                source.display(),
+                dest.display()
+            ),
+            None => write!(f, "Failed to process '{}': {error}", source.display()),
+        }
+    }
+}
+
+impl Error for SortError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SortError::Io { error, .. } => Some(error),
+            SortError::Other { .. } => None,
+        }
+    }
+}
+
+/// How many of `errors` there are of each kind, most common first.
+pub fn summarize(errors: &[SortError]) -> Vec<(&'static str, usize)> {
+    let mut counts = BTreeMap::new();
+    for error in errors {
+        *counts.entry(error.kind()).or_insert(0) += 1;
+    }
+
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by_key(|(_, count)| Reverse(*count));
+    counts
+}
@@ -0,0 +1,134 @@
+use {
+    crate::{tui::human_bytes, volumes},
+    std::{
+        collections::{BTreeMap, HashSet},
+        fs::{self, File},
+        io::{self, BufRead, Read, Write},
+        path::{Path, PathBuf},
+        time::{Duration, Instant},
+    },
+};
+
+/// How much of a file the throughput probe copies.
+const PROBE_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Less than this copies too quickly to tell the speed from.
+const MIN_PROBE_BYTES: u64 = 1024 * 1024;
+
+/// What a run is about to do, worked out from the scan before anything is
+/// placed.
+#[derive(Default)]
+pub struct Estimate {
+    /// Files and bytes per category folder
+    pub categories: BTreeMap<String, (u64, u64)>,
+    pub new_dirs: HashSet<PathBuf>,
+    /// Files whose destination is taken, or claimed by another file
+    pub conflicts: u64,
+    /// Bytes that have to be copied rather than renamed
+    pub copy_bytes: u64,
+    /// Bytes per second measured by `probe`
+    pub rate: Option<u64>,
+}
+
+impl Estimate {
+    /// Adds a file of `size` bytes that goes into `folder` under `root`.
+    pub fn add(
+        &mut self,
+        folder: &str,
+        root: &Path,
+        name: &str,
+        size: u64,
+        claimed: &mut HashSet<PathBuf>,
+    ) {
+        let (files, bytes) = self.categories.entry(folder.to_string()).or_default();
+        *files += 1;
+        *bytes += size;
+
+        let dir = root.join(folder);
+        if !dir.is_dir() {
+            self.new_dirs.insert(dir.clone());
+        }
+        let dest = dir.join(name);
+        if !claimed.insert(dest.clone()) || dest.exists() {
+            self.conflicts += 1;
+        }
+    }
+
+    /// How long copying takes at the measured rate, or at
+    /// `volumes::ASSUMED_COPY_RATE` without one.
+    pub fn duration(&self) -> Duration {
+        let rate = self.rate.unwrap_or(volumes::ASSUMED_COPY_RATE).max(1);
+        Duration::from_secs(self.copy_bytes / rate)
+    }
+
+    /// Prints the estimate. Folders and conflicts are by category and file
+    /// name only, so they leave out rename templates, subfolders and sidecars.
+    pub fn print(&self) {
+        eprintln!("Estimate (by category and file name, before renames, subfolders and sidecars):");
+        for (folder, (files, bytes)) in &self.categories {
+            eprintln!("  {folder}: {files} files, {}", human_bytes(*bytes));
+        }
+        eprintln!("  New folders: {}", self.new_dirs.len());
+        eprintln!("  Conflicts: {}", self.conflicts);
+        let seconds = self.duration().as_secs();
+        match self.copy_bytes {
+            0 => eprintln!("  Time: moments, every file is only renamed"),
+            bytes => eprintln!(
+                "  Time: about {}m {}s to copy {} at {}/s{}",
+                seconds / 60,
+                seconds % 60,
+                human_bytes(bytes),
+                human_bytes(self.rate.unwrap_or(volumes::ASSUMED_COPY_RATE)),
+                if self.rate.is_some() {
+                    ""
+                } else {
+                    " (assumed)"
+                }
+            ),
+        }
+    }
+}
+
+/// Measures how fast `sample` copies into `dir` by writing the start of it
+/// into a scratch file there. Returns bytes per second, or `None` if the
+/// sample is too small to tell.
+pub fn probe(sample: &Path, dir: &Path) -> Option<u64> {
+    let scratch = dir.join(".dirsort-probe");
+    let started = Instant::now();
+    let copied = (|| -> io::Result<u64> {
+        let mut source = File::open(sample)?.take(PROBE_BYTES);
+        let mut dest = File::create(&scratch)?;
+        let copied = io::copy(&mut source, &mut dest)?;
+        dest.sync_all()?;
+        Ok(copied)
+    })();
+    let elapsed = started.elapsed();
+    let _ = fs::remove_file(&scratch);
+
+    match copied {
+        Ok(bytes) if bytes >= MIN_PROBE_BYTES => {
+            Some((bytes as f64 / elapsed.as_secs_f64().max(1e-6)) as u64)
+        }
+        _ => None,
+    }
+}
+
+/// The closest folder to `path` that exists, to probe and compare
+/// filesystems against before the output directory is created.
+pub fn existing_ancestor(path: &Path) -> &Path {
+    path.ancestors()
+        .find(|dir| dir.is_dir())
+        .unwrap_or(Path::new("."))
+}
+
+/// Asks whether to go ahead. Closed input counts as no.
+pub fn confirm() -> bool {
+    eprint!("Continue? [Y/n] ");
+    let _ = io::stderr().flush();
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer).unwrap_or(0) == 0 {
+        eprintln!();
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "" | "y" | "yes")
+}
@@ -0,0 +1,21 @@
+use std::{fs::File, io::Read, path::Path};
+
+/// Recognizes native executables by their magic bytes rather than their
+/// extension: Windows PE (`MZ`), ELF, and thin or universal Mach-O.
+pub fn is_executable(path: &Path) -> bool {
+    let mut header = [0; 8];
+    let Ok(read) = File::open(path).and_then(|mut file| file.read(&mut header)) else {
+        return false;
+    };
+    let header = &header[..read];
+
+    match header {
+        [b'M', b'Z', ..] => true,
+        [0x7F, b'E', b'L', b'F', ..] => true,
+        [0xFE, 0xED, 0xFA, 0xCE | 0xCF, ..] | [0xCE | 0xCF, 0xFA, 0xED, 0xFE, ..] => true,
+        // Universal binaries share their magic with Java class files, which
+        // have a class file version where the architecture count would be.
+        [0xCA, 0xFE, 0xBA, 0xBE, a, b, c, d] => u32::from_be_bytes([*a, *b, *c, *d]) < 40,
+        _ => false,
+    }
+}
@@ -0,0 +1,52 @@
+use std::{
+    io::{self, Result},
+    path::Path,
+};
+
+const USER_TAGS_ATTR: &str = "com.apple.metadata:_kMDItemUserTags";
+const QUARANTINE_ATTR: &str = "com.apple.quarantine";
+
+/// Picks a Finder label colour for a category.
+///
+/// Finder stores tags as `"<name>\n<colour>"`, where the colour is an index
+/// into its label palette (1 = gray, 2 = green, 3 = purple, 4 = blue,
+/// 5 = yellow, 6 = red, 7 = orange).
+fn tag_color(category: &str) -> u8 {
+    match category {
+        "Images" => 2,
+        "Videos" => 3,
+        "Documents" => 4,
+        "Audio" => 5,
+        "Archives" => 1,
+        other => other.bytes().fold(0u8, |acc, b| acc.wrapping_add(b)) % 7 + 1,
+    }
+}
+
+pub fn tag_with_category(path: &Path, category: &str) -> Result<()> {
+    let mut tags: Vec<String> = match xattr::get(path, USER_TAGS_ATTR)? {
+        Some(raw) => plist::from_bytes(&raw).unwrap_or_default(),
+        None => Vec::new(),
+    };
+
+    if tags
+        .iter()
+        .any(|tag| tag.split('\n').next() == Some(category))
+    {
+        return Ok(());
+    }
+
+    tags.push(format!("{category}\n{}", tag_color(category)));
+
+    let mut encoded = Vec::new();
+    plist::to_writer_binary(&mut encoded, &tags).map_err(io::Error::other)?;
+
+    xattr::set(path, USER_TAGS_ATTR, &encoded)
+}
+
+pub fn strip_quarantine(path: &Path) -> Result<()> {
+    if xattr::get(path, QUARANTINE_ATTR)?.is_some() {
+        xattr::remove(path, QUARANTINE_ATTR)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use {
+    std::{
+        collections::HashMap,
+        fs,
+        path::{Path, PathBuf},
+        sync::Mutex,
+    },
+    tracing::warn,
+    walkdir::WalkDir,
+};
+
+/// Turns category keys into the names of their folders on disk, from
+/// `[folders]` and `folder_name`. A `{count}` in a name is kept up to date
+/// with how many files the folder holds.
+pub struct FolderNames {
+    templates: HashMap<String, String>,
+    default: String,
+    /// Folders with a `{count}` that this run used, by root and category
+    counted: Mutex<HashMap<(PathBuf, String), String>>,
+}
+
+impl FolderNames {
+    pub fn new(templates: HashMap<String, String>, default: String) -> Self {
+        Self {
+            templates,
+            default,
+            counted: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn name(&self, category: &str) -> String {
+        self.templates
+            .get(category)
+            .unwrap_or(&self.default)
+            .replace("{category}", category)
+    }
+
+    /// The path under `root` that `subfolder`, starting with a category, is
+    /// placed at.
+    pub fn resolve(&self, root: &Path, subfolder: &str) -> String {
+        let (category, rest) = subfolder.split_once('/').unwrap_or((subfolder, ""));
+        let folder = self.folder(root, category);
+        if rest.is_empty() {
+            folder
+        } else {
+            format!("{folder}/{rest}")
+        }
+    }
+
+    fn folder(&self, root: &Path, category: &str) -> String {
+        let name = self.name(category);
+        if !name.contains("{count}") {
+            return name;
+        }
+
+        let mut counted = self.counted.lock().unwrap_or_else(|e| e.into_inner());
+        counted
+            .entry((root.to_path_buf(), category.to_string()))
+            .or_insert_with(|| {
+                existing(root, &name).unwrap_or_else(|| name.replace("{count}", "0"))
+            })
+            .clone()
+    }
+
+    /// Renames the folders with a `{count}` to how many files they hold now,
+    /// returning the old and new path of each renamed one.
+    pub fn recount(&self) -> Vec<(PathBuf, PathBuf)> {
+        let counted = self.counted.lock().unwrap_or_else(|e| e.into_inner());
+        let mut renamed = Vec::new();
+        for ((root, category), folder) in counted.iter() {
+            let old = root.join(folder);
+            let count = WalkDir::new(&old)
+                .into_iter()
+                .filter_map(Result::ok)
+                .filter(|entry| entry.file_type().is_file())
+                .count();
+            let new = root.join(self.name(category).replace("{count}", &count.to_string()));
+            if new == old || new.exists() || !old.is_dir() {
+                continue;
+            }
+
+            match fs::rename(&old, &new) {
+                Ok(()) => renamed.push((old, new)),
+                Err(e) => warn!(
+                    "Failed to rename '{}' to '{}': {e}",
+                    old.display(),
+                    new.display()
+                ),
+            }
+        }
+        renamed
+    }
+}
+
+/// The folder in `root` that `name` refers to with any count in place of
+/// `{count}`, so later runs keep using it.
+fn existing(root: &Path, name: &str) -> Option<String> {
+    let (prefix, suffix) = name.split_once("{count}")?;
+    fs::read_dir(root)
+        .ok()?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_dir()))
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .find(|folder| {
+            folder
+                .strip_prefix(prefix)
+                .and_then(|rest| rest.strip_suffix(suffix))
+                .is_some_and(|count| !count.is_empty() && count.bytes().all(|b| b.is_ascii_digit()))
+        })
+}
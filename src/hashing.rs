@@ -0,0 +1,47 @@
+use {
+    memmap2::Mmap,
+    sha2::{Digest, Sha256},
+    std::{fs::File, io, path::Path},
+};
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum HashAlgorithm {
+    /// BLAKE3, fast and cryptographically secure
+    #[default]
+    Blake3,
+    /// XXH3 (128-bit), fastest but not cryptographic
+    Xxh3,
+    /// SHA-256, for when audits require it
+    Sha256,
+}
+
+/// Hashes a file, returning the lowercase hex digest.
+///
+/// Files are memory-mapped rather than read through a buffer, and BLAKE3
+/// additionally splits large files across the rayon pool.
+pub fn hash_file(path: &Path, algorithm: HashAlgorithm) -> io::Result<String> {
+    if algorithm == HashAlgorithm::Blake3 {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update_mmap_rayon(path)?;
+        return Ok(hasher.finalize().to_hex().to_string());
+    }
+
+    let file = File::open(path)?;
+
+    // Mapping an empty file fails on some platforms.
+    let map;
+    let bytes: &[u8] = if file.metadata()?.len() == 0 {
+        &[]
+    } else {
+        // SAFETY: the mapping is only read, and dirsort doesn't write to
+        // files while hashing them. Another process truncating the file
+        // concurrently is the usual mmap caveat, same as for `blake3`.
+        map = unsafe { Mmap::map(&file)? };
+        &map
+    };
+
+    Ok(match algorithm {
+        HashAlgorithm::Xxh3 => format!("{:032x}", xxhash_rust::xxh3::xxh3_128(bytes)),
+        _ => format!("{:x}", Sha256::digest(bytes)),
+    })
+}
@@ -0,0 +1,146 @@
+use {
+    crate::init,
+    serde::{Deserialize, Serialize},
+    std::{
+        env,
+        fs::{self, OpenOptions},
+        io::{self, BufRead, BufReader, Write},
+        path::PathBuf,
+    },
+};
+
+/// What a run did, one line of `history.jsonl` in the state directory.
+#[derive(Serialize, Deserialize)]
+pub struct Run {
+    /// Start time as `YYYYMMDD-HHMMSS`, with `-2`, `-3`, ... after it for
+    /// runs that started in the same second
+    pub id: String,
+    /// Start time in RFC 3339
+    pub started: String,
+    pub duration_secs: f64,
+    /// Directory that was sorted
+    pub dir: PathBuf,
+    /// Command line arguments, without the program name
+    pub args: Vec<String>,
+    pub found: u64,
+    pub processed: u64,
+    pub skipped: u64,
+    pub uncategorized: u64,
+    pub conflicts: u64,
+    #[serde(default)]
+    pub errors: Vec<String>,
+    /// The `--report` written for this run
+    pub report: Option<PathBuf>,
+    /// Where the run's placements were journaled for `dirsort undo`
+    #[serde(default)]
+    pub journal: Option<PathBuf>,
+}
+
+/// `$XDG_STATE_HOME/dirsort`, usually `~/.local/state/dirsort`.
+pub fn state_dir() -> Option<PathBuf> {
+    env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| init::home_dir().map(|home| home.join(".local").join("state")))
+        .map(|dir| dir.join("dirsort"))
+}
+
+fn history_path() -> io::Result<PathBuf> {
+    state_dir()
+        .map(|dir| dir.join("history.jsonl"))
+        .ok_or_else(|| io::Error::other("could not determine the state directory"))
+}
+
+pub fn record(run: &Run) -> io::Result<()> {
+    let path = history_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(run)?)
+}
+
+pub fn load() -> io::Result<Vec<Run>> {
+    let file = match fs::File::open(history_path()?) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    // Lines from a run that was killed mid-write are skipped.
+    Ok(BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect())
+}
+
+/// Lists past runs, most recent last.
+pub fn list() -> io::Result<()> {
+    let runs = load()?;
+    if runs.is_empty() {
+        println!("No runs recorded yet.");
+        return Ok(());
+    }
+
+    let mut stdout = io::stdout().lock();
+    for run in &runs {
+        writeln!(
+            stdout,
+            "{}  {:>8.1}s  {:>13} files  {:>4} errors  {}",
+            run.id,
+            run.duration_secs,
+            format!("{}/{}", run.processed, run.found),
+            run.errors.len(),
+            run.dir.display()
+        )?;
+    }
+    Ok(())
+}
+
+/// The run with the id `id`, or else the latest run whose id starts with it.
+pub fn matching<'a>(runs: &'a [Run], id: &str) -> Option<&'a Run> {
+    runs.iter()
+        .rev()
+        .find(|run| run.id == id)
+        .or_else(|| runs.iter().rev().find(|run| run.id.starts_with(id)))
+}
+
+/// Prints everything recorded about the run `matching` finds for `id`.
+pub fn show(id: &str) -> io::Result<()> {
+    let runs = load()?;
+    let Some(run) = matching(&runs, id) else {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no run matching '{id}'"),
+        ));
+    };
+
+    let mut stdout = io::stdout().lock();
+    writeln!(stdout, "Run {}", run.id)?;
+    writeln!(stdout, "  Started:       {}", run.started)?;
+    writeln!(stdout, "  Duration:      {:.1}s", run.duration_secs)?;
+    writeln!(stdout, "  Directory:     {}", run.dir.display())?;
+    writeln!(stdout, "  Arguments:     {}", run.args.join(" "))?;
+    writeln!(stdout, "  Files found:   {}", run.found)?;
+    writeln!(stdout, "  Processed:     {}", run.processed)?;
+    writeln!(stdout, "  Blacklisted:   {}", run.skipped)?;
+    writeln!(stdout, "  Uncategorized: {}", run.uncategorized)?;
+    writeln!(stdout, "  Conflicts:     {}", run.conflicts)?;
+    if let Some(report) = &run.report {
+        writeln!(stdout, "  Report:        {}", report.display())?;
+    }
+    match &run.journal {
+        Some(journal) if journal.exists() => {
+            writeln!(stdout, "  Journal:       {}", journal.display())?
+        }
+        Some(_) => writeln!(stdout, "  Journal:       (undone)")?,
+        None => {}
+    }
+    writeln!(stdout, "  Errors:        {}", run.errors.len())?;
+    for error in &run.errors {
+        writeln!(stdout, "    {error}")?;
+    }
+    Ok(())
+}
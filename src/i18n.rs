@@ -0,0 +1,158 @@
+use {
+    fluent_bundle::{FluentArgs, FluentResource, FluentValue, concurrent::FluentBundle},
+    std::{env, sync::OnceLock},
+    unic_langid::LanguageIdentifier,
+};
+
+/// Bundled translations. English is complete and used for anything a locale
+/// leaves out; the others also translate the `--help` output.
+const LOCALES: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.ftl")),
+    ("de", include_str!("../locales/de.ftl")),
+    ("es", include_str!("../locales/es.ftl")),
+    ("fr", include_str!("../locales/fr.ftl")),
+];
+
+/// The selected locale first, then English.
+static BUNDLES: OnceLock<Vec<FluentBundle<FluentResource>>> = OnceLock::new();
+
+/// Formats message `id` in the selected language, e.g. `t!("summary-total", count = 3)`.
+macro_rules! t {
+    ($id:literal) => {
+        $crate::i18n::tr($id, &[])
+    };
+    ($id:literal, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::tr(
+            $id,
+            &[$((stringify!($name), fluent_bundle::FluentValue::from($value))),+],
+        )
+    };
+}
+pub(crate) use t;
+
+/// The language asked for with `--lang`, which has to be known before the
+/// arguments are parsed so `--help` can be translated.
+pub fn requested_lang() -> Option<String> {
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            break;
+        }
+        if let Some(lang) = arg.strip_prefix("--lang=") {
+            return Some(lang.to_string());
+        }
+        if arg == "--lang" {
+            return args.next();
+        }
+    }
+    None
+}
+
+/// Selects the locale from `lang`, or else from `LC_ALL`, `LC_MESSAGES` or
+/// `LANG`. Falls back to English for anything that isn't bundled.
+pub fn init(lang: Option<&str>) {
+    let _ = BUNDLES.set(load(lang));
+}
+
+fn load(lang: Option<&str>) -> Vec<FluentBundle<FluentResource>> {
+    let requested = lang.map(str::to_string).or_else(|| {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .into_iter()
+            .filter_map(|var| env::var(var).ok())
+            .find(|value| !value.is_empty())
+    });
+    // `de_DE.UTF-8` -> `de-DE`
+    let language = requested
+        .as_deref()
+        .map(|lang| {
+            lang.split(['.', '@'])
+                .next()
+                .unwrap_or(lang)
+                .replace('_', "-")
+        })
+        .and_then(|lang| lang.parse::<LanguageIdentifier>().ok())
+        .map(|id| id.language.as_str().to_string());
+
+    let mut bundles = Vec::new();
+    for (code, source) in LOCALES {
+        if language.as_deref() == Some(code) && *code != "en" {
+            bundles.insert(0, bundle(code, source));
+        } else if *code == "en" {
+            bundles.push(bundle(code, source));
+        }
+    }
+    bundles
+}
+
+fn bundle(code: &str, source: &str) -> FluentBundle<FluentResource> {
+    let id: LanguageIdentifier = code.parse().unwrap_or_default();
+    let mut bundle = FluentBundle::new_concurrent(vec![id]);
+    // Unicode isolation marks show up as garbage in most terminals.
+    bundle.set_use_isolating(false);
+    // The bundled files are known to parse; a broken one only loses messages.
+    let resource = FluentResource::try_new(source.to_string()).unwrap_or_else(|(res, _)| res);
+    let _ = bundle.add_resource(resource);
+    bundle
+}
+
+pub fn tr(id: &str, args: &[(&str, FluentValue)]) -> String {
+    let bundles = BUNDLES.get_or_init(|| load(None));
+    let mut fluent_args = FluentArgs::new();
+    for (name, value) in args {
+        fluent_args.set(*name, value.clone());
+    }
+
+    for bundle in bundles {
+        if let Some(pattern) = bundle.get_message(id).and_then(|message| message.value()) {
+            let mut errors = Vec::new();
+            return bundle
+                .format_pattern(pattern, Some(&fluent_args), &mut errors)
+                .into_owned();
+        }
+    }
+    id.to_string()
+}
+
+/// Message `id` from the selected locale only, without the English fallback.
+fn translated(id: &str) -> Option<String> {
+    let bundles = BUNDLES.get_or_init(|| load(None));
+    // With English selected there is only the one bundle, and the help text
+    // already comes from the doc comments.
+    if bundles.len() < 2 {
+        return None;
+    }
+    let bundle = &bundles[0];
+    let pattern = bundle.get_message(id)?.value()?;
+    let mut errors = Vec::new();
+    Some(
+        bundle
+            .format_pattern(pattern, None, &mut errors)
+            .into_owned(),
+    )
+}
+
+/// Replaces the help of every argument and subcommand that has a `help-<arg>`
+/// or `about-<subcommand>` message in the selected locale.
+pub fn localize(mut command: clap::Command) -> clap::Command {
+    let ids: Vec<String> = command
+        .get_arguments()
+        .map(|arg| arg.get_id().to_string())
+        .collect();
+    for id in ids {
+        if let Some(help) = translated(&format!("help-{}", id.replace('_', "-"))) {
+            command = command.mut_arg(id, |arg| arg.help(help.clone()).long_help(help));
+        }
+    }
+
+    let names: Vec<String> = command
+        .get_subcommands()
+        .map(|sub| sub.get_name().to_string())
+        .collect();
+    for name in names {
+        if let Some(about) = translated(&format!("about-{name}")) {
+            command = command.mut_subcommand(name, |sub| sub.about(about));
+        }
+    }
+
+    command
+}
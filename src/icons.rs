@@ -0,0 +1,123 @@
+use {
+    crate::code,
+    serde::{Deserialize, Serialize},
+    std::{collections::HashMap, path::Path, sync::OnceLock},
+};
+
+/// The `[icons]` table: what the HTML index shows in front of folders and
+/// files. Icons are HTML, so an emoji or an inline `<svg>` both work.
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct IconConfig {
+    /// Instead of 📁 for folders
+    folder: Option<String>,
+    /// Instead of 📄 for files nothing else matches
+    file: Option<String>,
+    /// By category folder, e.g. `Images/RAW`, for the folder and the files
+    /// in it
+    #[serde(default)]
+    categories: HashMap<String, String>,
+    /// By extension, taking precedence over the category
+    #[serde(default)]
+    extensions: HashMap<String, String>,
+}
+
+/// Icons by extension when the config has none for a file.
+const KINDS: &[(&str, &[&str])] = &[
+    (
+        "🖼️",
+        &[
+            "jpg", "jpeg", "png", "gif", "webp", "heic", "heif", "bmp", "tiff", "tif", "svg",
+            "ico", "avif", "raw", "dng", "cr2", "cr3", "nef", "arw", "orf", "rw2", "raf",
+        ],
+    ),
+    (
+        "🎬",
+        &[
+            "mp4", "m4v", "mkv", "mov", "webm", "ogv", "avi", "wmv", "flv",
+        ],
+    ),
+    (
+        "🎵",
+        &[
+            "mp3", "m4a", "aac", "flac", "wav", "ogg", "oga", "opus", "weba", "wma",
+        ],
+    ),
+    (
+        "📝",
+        &[
+            "pdf", "doc", "docx", "odt", "rtf", "txt", "md", "epub", "xls", "xlsx", "ods", "ppt",
+            "pptx", "odp", "csv",
+        ],
+    ),
+    (
+        "📦",
+        &[
+            "zip", "tar", "gz", "tgz", "bz2", "xz", "zst", "7z", "rar", "iso",
+        ],
+    ),
+    ("✉️", &["eml", "mbox", "msg"]),
+    (
+        "⚙️",
+        &[
+            "exe", "msi", "dll", "appimage", "deb", "rpm", "dmg", "pkg", "apk",
+        ],
+    ),
+];
+
+/// What source files show when the config has no icon for them.
+const CODE: &str = "💻";
+
+static ICONS: OnceLock<IconConfig> = OnceLock::new();
+
+/// Uses `config` for every index generated from now on.
+pub fn configure(config: IconConfig) {
+    ICONS.get_or_init(|| config);
+}
+
+fn config() -> &'static IconConfig {
+    ICONS.get_or_init(IconConfig::default)
+}
+
+/// The configured icon of the category folder `relative`, or of its closest
+/// parent folder that has one.
+fn category_icon(relative: &Path) -> Option<&'static str> {
+    let config = config();
+    relative.ancestors().find_map(|dir| {
+        let key = dir
+            .to_string_lossy()
+            .replace(std::path::MAIN_SEPARATOR, "/");
+        config.categories.get(&key).map(String::as_str)
+    })
+}
+
+/// The icon of the folder at `relative`, under the indexed directory.
+pub fn folder(relative: &Path) -> &'static str {
+    let config = config();
+    category_icon(relative)
+        .or(config.folder.as_deref())
+        .unwrap_or("📁")
+}
+
+/// The icon of the file at `relative`, under the indexed directory: by its
+/// extension, then by its category, then by what kind of file the
+/// extension says it is.
+pub fn file(relative: &Path) -> &'static str {
+    let config = config();
+    let ext = relative
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase());
+    let ext = ext.as_deref();
+
+    ext.and_then(|ext| config.extensions.get(ext).map(String::as_str))
+        .or_else(|| relative.parent().and_then(category_icon))
+        .or_else(|| {
+            let ext = ext?;
+            KINDS
+                .iter()
+                .find(|(_, exts)| exts.contains(&ext))
+                .map(|(icon, _)| *icon)
+                .or_else(|| code::by_extension(ext).map(|_| CODE))
+        })
+        .or(config.file.as_deref())
+        .unwrap_or("📄")
+}
@@ -0,0 +1,400 @@
+use {
+    crate::{QUARANTINE_DIR, i18n::t, icons, live, media, overview, tui::human_bytes},
+    chrono::{DateTime, Local},
+    serde::Serialize,
+    std::{
+        ffi::OsStr,
+        fmt::Write as _,
+        fs::{self, File},
+        io::{self, Read, Write},
+        path::Path,
+        sync::OnceLock,
+    },
+    tracing::info,
+    walkdir::WalkDir,
+};
+
+/// What `dirsort index` writes, and what the tree leaves out.
+pub const INDEX_FILES: [&str; 2] = ["index.html", "index.json"];
+
+/// The files a run writes into the root of the output directory.
+const RUN_FILES: [&str; 2] = ["dirsort-report.csv", "near-duplicates.txt"];
+
+/// Whether `name`, directly in the output directory, is left out of the
+/// index and the category totals: the quarantine, hidden folders like the
+/// staging area, and what dirsort writes there itself.
+pub fn is_hidden_at_root(name: &OsStr) -> bool {
+    let name = name.to_string_lossy();
+    name.eq_ignore_ascii_case(QUARANTINE_DIR)
+        || name.starts_with('.')
+        || INDEX_FILES.contains(&name.as_ref())
+        || RUN_FILES.contains(&name.as_ref())
+}
+
+/// The colors of the generated index, with `--index-theme`.
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Theme {
+    /// Dark text on white
+    Light,
+    /// Light text on a dark background
+    Dark,
+    /// Light or dark, following the viewer's system setting
+    Auto,
+}
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// Uses `theme` for every index generated from now on.
+pub fn set_theme(theme: Theme) {
+    THEME.get_or_init(|| theme);
+}
+
+const LIGHT: &str = "--background: #fff; --text: #000; --heading: #333; --link: #0066cc; --dir: #009933; --muted: #777; --rule: #ddd; color-scheme: light;";
+
+const DARK: &str = "--background: #1e1e1e; --text: #ddd; --heading: #eee; --link: #6ab0ff; --dir: #5fd38d; --muted: #999; --rule: #444; color-scheme: dark;";
+
+/// The colors for `theme`, and a print style that is always light and
+/// leaves out what only works on screen.
+fn theme_css(theme: Theme) -> String {
+    let colors = match theme {
+        Theme::Light => format!(":root {{ {LIGHT} }}"),
+        Theme::Dark => format!(":root {{ {DARK} }}"),
+        Theme::Auto => format!(
+            ":root {{ {LIGHT} }}
+        @media (prefers-color-scheme: dark) {{ :root {{ {DARK} }} }}"
+        ),
+    };
+    format!(
+        "{colors}
+        @media print {{
+            :root {{ {LIGHT} }}
+            body {{ margin: 0; font-size: 11pt; }}
+            #breadcrumbs, .play {{ display: none; }}
+            a {{ color: var(--text); }}
+            li, footer {{ break-inside: avoid; }}
+        }}"
+    )
+}
+
+/// Keeps the breadcrumbs on the folder opened last, and opens the folder in
+/// the URL's fragment, so `index.html#Images/2024` links straight to it.
+const TREE_SCRIPT: &str = r##"
+        const crumbs = document.getElementById("breadcrumbs");
+        const root = crumbs.firstElementChild.cloneNode(true);
+        const show = (path) => {
+            crumbs.replaceChildren(root.cloneNode(true));
+            let prefix = "";
+            for (const part of path ? path.split("/") : []) {
+                prefix += (prefix ? "/" : "") + part;
+                const link = document.createElement("a");
+                link.href = "#" + encodeURIComponent(prefix);
+                link.textContent = part;
+                crumbs.append(" / ", link);
+            }
+        };
+        const open = (path) => {
+            const target = path && document.getElementById(path);
+            if (!target) {
+                document.querySelectorAll("details[open]").forEach((details) => details.open = false);
+                return show("");
+            }
+            target.querySelectorAll("details[open]").forEach((details) => details.open = false);
+            for (let details = target; details; details = details.parentElement.closest("details")) {
+                details.open = true;
+            }
+            target.scrollIntoView();
+            show(path);
+        };
+        document.querySelectorAll("details").forEach((details) => details.addEventListener("toggle", () => {
+            // Only the innermost open folder moves the breadcrumbs.
+            if (details.open && !details.querySelector("details[open]")) {
+                show(details.id);
+            } else if (!details.open) {
+                show(details.parentElement.closest("details")?.id ?? "");
+            }
+        }));
+        // Closed folders would print as just their name.
+        let closed = [];
+        window.addEventListener("beforeprint", () => {
+            closed = [...document.querySelectorAll("details:not([open])")];
+            closed.forEach((details) => details.open = true);
+        });
+        window.addEventListener("afterprint", () => closed.forEach((details) => details.open = false));
+        const fragment = () => decodeURIComponent(location.hash.slice(1));
+        window.addEventListener("hashchange", () => open(fragment()));
+        if (location.hash) {
+            open(fragment());
+        }
+"##;
+
+/// Escapes `text` for use in HTML text and attribute values.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Writes `index.html` into `output_dir`: a tree of its folders, collapsed
+/// so that large trees stay quick to open, with breadcrumbs to the folder
+/// opened last and the totals of each category in the footer.
+pub fn gen_html_index(output_dir: &Path) -> io::Result<()> {
+    let index_path = output_dir.join("index.html");
+    let mut tree = String::new();
+    write_tree(&mut tree, output_dir, output_dir, 2)?;
+
+    let mut file = File::create(&index_path)?;
+    let root_name = output_dir.file_name().map_or_else(
+        || output_dir.display().to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    );
+    write!(
+        file,
+        "<!DOCTYPE html>
+<html>
+<head>
+    <meta charset=\"utf-8\">
+    <meta name=\"generator\" content=\"dirsort\">
+    <title>{}</title>
+    <style>
+        {}
+        body {{ font-family: Arial, sans-serif; margin: 20px; background: var(--background); color: var(--text); }}
+        h1 {{ color: var(--heading); }}
+        ul {{ list-style-type: none; padding: 0; }}
+        li {{ margin: 5px 0; }}
+        details > ul {{ padding-left: 20px; }}
+        summary {{ cursor: pointer; }}
+        a {{ color: var(--link); text-decoration: none; }}
+        a:hover {{ text-decoration: underline; }}
+        #breadcrumbs {{ margin-bottom: 15px; }}
+        .dir {{ font-weight: bold; color: var(--dir); }}
+        .icon svg {{ width: 1em; height: 1em; vertical-align: -0.125em; }}
+        .count {{ color: var(--muted); }}
+        .play {{ margin-left: 8px; }}
+        footer {{ margin-top: 20px; border-top: 1px solid var(--rule); padding-top: 10px; }}
+        footer td, footer th {{ padding: 2px 12px 2px 0; text-align: left; }}
+        footer .number {{ text-align: right; }}
+        footer .total td {{ font-weight: bold; border-top: 1px solid var(--rule); }}
+    </style>
+</head>
+<body>
+    <h1>{}</h1>
+    <nav id=\"breadcrumbs\"><a href=\"#\">{}</a></nav>
+    <ul>
+{tree}    </ul>
+",
+        t!("html-title"),
+        theme_css(*THEME.get().unwrap_or(&Theme::Auto)),
+        escape(&t!("html-heading", dir = output_dir.display().to_string())),
+        escape(&root_name),
+    )?;
+
+    let categories = overview::collect(output_dir);
+    if !categories.is_empty() {
+        writeln!(
+            file,
+            r#"    <footer>
+        <table>
+            <tr><th>{}</th><th class="number">{}</th><th class="number">{}</th></tr>"#,
+            t!("html-category"),
+            t!("html-files"),
+            t!("html-size")
+        )?;
+        for category in &categories {
+            writeln!(
+                file,
+                r#"            <tr><td>{}</td><td class="number">{}</td><td class="number">{}</td></tr>"#,
+                escape(&category.name),
+                category.files,
+                human_bytes(category.bytes)
+            )?;
+        }
+        writeln!(
+            file,
+            r#"            <tr class="total"><td>{}</td><td class="number">{}</td><td class="number">{}</td></tr>
+        </table>
+    </footer>"#,
+            t!("html-total"),
+            categories
+                .iter()
+                .map(|category| category.files)
+                .sum::<u64>(),
+            human_bytes(categories.iter().map(|category| category.bytes).sum())
+        )?;
+    }
+
+    writeln!(
+        file,
+        r#"    <script>{TREE_SCRIPT}
+        // Reloads when `dirsort serve --watch` sees a change.
+        if (location.protocol.startsWith("http")) {{
+            const events = new EventSource("{}");
+            events.onmessage = () => location.reload();
+            events.onerror = () => events.close();
+        }}
+    </script>
+</body>
+</html>"#,
+        live::EVENTS_PATH
+    )?;
+
+    info!(
+        "{}",
+        t!("html-generated", path = index_path.display().to_string())
+    );
+
+    Ok(())
+}
+
+/// Whether the `index.html` in `dir` was generated by dirsort, rather than
+/// being part of what is in there.
+fn is_generated(dir: &Path) -> bool {
+    let mut head = String::new();
+    File::open(dir.join(INDEX_FILES[0]))
+        .and_then(|file| file.take(512).read_to_string(&mut head))
+        .is_ok_and(|_| head.contains(r#"<meta name="generator" content="dirsort">"#))
+}
+
+/// Regenerates the index in `dir` if dirsort generated the one there, so it
+/// stays current across runs, or generates a new one with `create`. An
+/// `index.json` next to it is regenerated as well.
+pub fn refresh(dir: &Path, create: bool) -> io::Result<()> {
+    if !create && !is_generated(dir) {
+        return Ok(());
+    }
+    gen_html_index(dir)?;
+    if dir.join(INDEX_FILES[1]).is_file() {
+        gen_json_index(dir)?;
+    }
+    Ok(())
+}
+
+/// Appends the entries of `dir` to `html` as list items, folders first, each
+/// folder a `<details>` holding its own entries. Returns how many files are
+/// under `dir`.
+fn write_tree(html: &mut String, root: &Path, dir: &Path, depth: usize) -> io::Result<u64> {
+    let indent = "    ".repeat(depth);
+    let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    entries.retain(|entry| dir != root || !is_hidden_at_root(&entry.file_name()));
+    let is_dir = |entry: &fs::DirEntry| entry.file_type().is_ok_and(|kind| kind.is_dir());
+    entries.sort_by(|a, b| {
+        is_dir(b)
+            .cmp(&is_dir(a))
+            .then_with(|| a.file_name().cmp(&b.file_name()))
+    });
+
+    let mut files = 0;
+    for entry in entries {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        let url_path = relative
+            .to_string_lossy()
+            .replace(std::path::MAIN_SEPARATOR, "/");
+
+        if is_dir(&entry) {
+            let mut children = String::new();
+            let count = write_tree(&mut children, root, &path, depth + 2)?;
+            files += count;
+            writeln!(
+                html,
+                r#"{indent}<li><details id="{}">
+{indent}    <summary><span class="dir"><span class="icon">{}</span> {}/</span> <span class="count">({count})</span></summary>
+{indent}    <ul>
+{children}{indent}    </ul>
+{indent}</details></li>"#,
+                escape(&url_path),
+                icons::folder(relative),
+                escape(&name)
+            )
+            .map_err(io::Error::other)?;
+        } else {
+            files += 1;
+            let abs_path = path.canonicalize()?;
+            // Players only work when the index is served.
+            let play = match media::media_type(&url_path) {
+                Some(_) => format!(
+                    r#" <a class="play" href="{}/{}">▶</a>"#,
+                    media::PLAY_PATH,
+                    media::encode_path(&url_path)
+                ),
+                None => String::new(),
+            };
+            writeln!(
+                html,
+                r#"{indent}<li><a href="file://{}" target="_blank"><span class="icon">{}</span>  {}</a>{play}</li>"#,
+                escape(&abs_path.display().to_string()),
+                icons::file(relative),
+                escape(&name)
+            )
+            .map_err(io::Error::other)?;
+        }
+    }
+    Ok(files)
+}
+
+/// A file in `index.json`.
+#[derive(Serialize)]
+struct IndexedFile {
+    /// Relative to the indexed directory, with `/` between folders
+    path: String,
+    size: u64,
+    modified: Option<String>,
+}
+
+#[derive(Serialize)]
+struct JsonIndex {
+    dir: String,
+    generated: String,
+    categories: Vec<overview::CategoryStats>,
+    files: Vec<IndexedFile>,
+}
+
+/// Writes `index.json` into `dir`: every file with its size and when it was
+/// last modified, sorted by path, and the totals of each category.
+pub fn gen_json_index(dir: &Path) -> io::Result<()> {
+    let index_path = dir.join(INDEX_FILES[1]);
+    let mut files = Vec::new();
+    for entry in WalkDir::new(dir)
+        .min_depth(1)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| entry.depth() != 1 || !is_hidden_at_root(entry.file_name()))
+    {
+        let entry = entry?;
+        if entry.file_type().is_dir() {
+            continue;
+        }
+        let metadata = entry.metadata()?;
+        files.push(IndexedFile {
+            path: entry
+                .path()
+                .strip_prefix(dir)
+                .unwrap_or(entry.path())
+                .to_string_lossy()
+                .replace(std::path::MAIN_SEPARATOR, "/"),
+            size: metadata.len(),
+            modified: metadata
+                .modified()
+                .ok()
+                .map(|modified| DateTime::<Local>::from(modified).to_rfc3339()),
+        });
+    }
+
+    let index = JsonIndex {
+        dir: dir.display().to_string(),
+        generated: Local::now().to_rfc3339(),
+        categories: overview::collect(dir),
+        files,
+    };
+    let mut file = File::create(&index_path)?;
+    serde_json::to_writer_pretty(&mut file, &index)?;
+    writeln!(file)?;
+
+    info!(
+        "{}",
+        t!("json-generated", path = index_path.display().to_string())
+    );
+
+    Ok(())
+}
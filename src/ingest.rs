@@ -0,0 +1,396 @@
+use {
+    crate::{
+        SorterConfig, categorize, copy_file,
+        hashing::{self, HashAlgorithm},
+        history,
+        i18n::t,
+        is_blacklisted, metadata,
+    },
+    serde::{Deserialize, Serialize},
+    std::{
+        collections::{BTreeSet, HashMap, HashSet},
+        fs, io,
+        path::{Path, PathBuf},
+        process, thread,
+        time::{Duration, UNIX_EPOCH},
+    },
+    tracing::{error, info, warn},
+    walkdir::WalkDir,
+};
+
+/// How often to look for the device while waiting for it.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How many imported files are recorded before `ingested.json` is saved, so
+/// a card pulled out halfway resumes close to where it stopped.
+const SAVE_EVERY: u32 = 50;
+
+/// Where removable media get mounted.
+const MOUNT_ROOTS: &[&str] = &["/media", "/run/media", "/Volumes", "/mnt"];
+
+/// Everything `dirsort ingest` needs from the main options and config.
+pub struct Ingest<'a> {
+    pub config: &'a SorterConfig,
+    pub screenshot_patterns: &'a [glob::Pattern],
+    pub blacklist: &'a HashSet<String>,
+    pub out_dir: &'a Path,
+    pub hash: HashAlgorithm,
+    /// Only list what would be imported
+    pub dry_run: bool,
+}
+
+/// Files already imported from each device, by device id, so a card that is
+/// inserted again only has its new files copied. Stored in `ingested.json`
+/// under the state directory.
+#[derive(Default, Serialize, Deserialize)]
+struct Imported(HashMap<String, BTreeSet<String>>);
+
+impl Imported {
+    fn path() -> io::Result<PathBuf> {
+        history::state_dir()
+            .map(|dir| dir.join("ingested.json"))
+            .ok_or_else(|| io::Error::other("could not determine the state directory"))
+    }
+
+    fn load() -> io::Result<Self> {
+        match fs::read(Self::path()?) {
+            Ok(content) => Ok(serde_json::from_slice(&content)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let path = Self::path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let temp = path.with_extension("json.tmp");
+        fs::write(&temp, serde_json::to_vec(self)?)?;
+        fs::rename(temp, path)
+    }
+}
+
+/// `dirsort ingest`: waits for the device (`device`, or else the next one
+/// that is mounted), copies the files that weren't imported from it before
+/// into `<category>/<year>/<date>/` by their capture date, verifies each copy
+/// against the original, and ejects the device if asked to.
+pub fn run(ingest: &Ingest, device: Option<&Path>, eject: bool) -> io::Result<()> {
+    let mount = match device {
+        Some(device) => wait_for(device),
+        None => wait_for_new(),
+    };
+    let id = device_id(&mount);
+    info!(
+        "{}",
+        t!(
+            "ingest-importing",
+            dir = mount.display().to_string(),
+            id = id.as_str()
+        )
+    );
+
+    let mut imported = Imported::load()?;
+    let mut copied = 0;
+    let mut known = 0;
+    let mut failed = 0;
+    let mut unsaved = 0;
+    let result = (|| -> io::Result<()> {
+        for entry in WalkDir::new(&mount).into_iter().filter_map(Result::ok) {
+            let path = entry.path();
+            if !entry.file_type().is_file()
+                || is_blacklisted(path, ingest.blacklist)
+                || is_hidden(path.strip_prefix(&mount).unwrap_or(path))
+            {
+                continue;
+            }
+            let key = import_key(&mount, path)?;
+            if imported.0.get(&id).is_some_and(|keys| keys.contains(&key)) {
+                known += 1;
+                continue;
+            }
+
+            if ingest.dry_run {
+                match destination(ingest, path) {
+                    Ok(dir) => info!("Would import {} -> {}", path.display(), dir.display()),
+                    Err(e) => warn!("Would fail to import '{}': {e}", path.display()),
+                }
+                copied += 1;
+                continue;
+            }
+
+            match import(ingest, path) {
+                Ok(dest) => {
+                    info!("{} -> {}", path.display(), dest.display());
+                    imported.0.entry(id.clone()).or_default().insert(key);
+                    copied += 1;
+                    unsaved += 1;
+                    if unsaved >= SAVE_EVERY {
+                        imported.save()?;
+                        unsaved = 0;
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to import '{}': {e}", path.display());
+                    failed += 1;
+                }
+            }
+        }
+        Ok(())
+    })();
+    // Saved on errors too, so the files that made it aren't copied again.
+    if unsaved > 0 {
+        imported.save()?;
+    }
+    result?;
+
+    if ingest.dry_run {
+        info!(
+            "{}",
+            t!("ingest-would-import", count = copied, known = known)
+        );
+        return Ok(());
+    }
+    info!(
+        "{}",
+        t!(
+            "ingest-imported",
+            count = copied,
+            known = known,
+            failed = failed
+        )
+    );
+
+    if eject {
+        if failed > 0 {
+            warn!("{}", t!("ingest-not-ejecting"));
+        } else {
+            self::eject(&mount)?;
+            info!("{}", t!("ingest-ejected"));
+        }
+    }
+    Ok(())
+}
+
+/// The folder `path` is imported into: `<category>/<year>/<date>/`.
+fn destination(ingest: &Ingest, path: &Path) -> io::Result<PathBuf> {
+    let ext = path.extension().and_then(|ext| ext.to_str());
+    let category = categorize(path, ext, ingest.config, ingest.screenshot_patterns)
+        .unwrap_or_else(|| ingest.config.fallback.clone());
+    let date = metadata::file_date(path)
+        .ok_or_else(|| io::Error::other("could not determine the file's date"))?;
+    Ok(ingest
+        .out_dir
+        .join(category)
+        .join(date.format("%Y").to_string())
+        .join(date.format("%Y-%m-%d").to_string()))
+}
+
+/// Copies `path` into the output directory and checks the copy against it.
+fn import(ingest: &Ingest, path: &Path) -> io::Result<PathBuf> {
+    let ext = path.extension().and_then(|ext| ext.to_str());
+    let dir = destination(ingest, path)?;
+    fs::create_dir_all(&dir)?;
+
+    let source_hash = hashing::hash_file(path, ingest.hash)?;
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::other("file has no name"))?;
+    let mut dest = dir.join(name);
+    let mut counter = 1;
+    while dest.exists() {
+        // The same file from an earlier card or a re-formatted one.
+        if hashing::hash_file(&dest, ingest.hash)? == source_hash {
+            return Ok(dest);
+        }
+        let stem = Path::new(name)
+            .file_stem()
+            .unwrap_or(name)
+            .to_string_lossy();
+        dest = dir.join(match ext {
+            Some(ext) => format!("{stem} ({counter}).{ext}"),
+            None => format!("{stem} ({counter})"),
+        });
+        counter += 1;
+    }
+
+    copy_file(path, &dest)?;
+    if hashing::hash_file(&dest, ingest.hash)? != source_hash {
+        let _ = fs::remove_file(&dest);
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "the copy doesn't match the original, the card or the disk may be failing",
+        ));
+    }
+    Ok(dest)
+}
+
+/// What identifies a file on the device across insertions: its path, size
+/// and modification time.
+fn import_key(mount: &Path, path: &Path) -> io::Result<String> {
+    let metadata = path.metadata()?;
+    let modified = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let relative = path.strip_prefix(mount).unwrap_or(path);
+    Ok(format!(
+        "{}:{}:{modified}",
+        relative.display(),
+        metadata.len()
+    ))
+}
+
+/// Dotfiles and folders, like `.Trashes` or `.Spotlight-V100`, aren't the user's.
+fn is_hidden(relative: &Path) -> bool {
+    relative
+        .components()
+        .any(|part| part.as_os_str().to_string_lossy().starts_with('.'))
+}
+
+fn wait_for(device: &Path) -> PathBuf {
+    let mut waiting = false;
+    while !is_mount_point(device) {
+        if !waiting {
+            info!(
+                "{}",
+                t!(
+                    "ingest-waiting-mount",
+                    device = device.display().to_string()
+                )
+            );
+            waiting = true;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    device.to_path_buf()
+}
+
+fn wait_for_new() -> PathBuf {
+    let before = mounted();
+    info!("{}", t!("ingest-waiting-device"));
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        if let Some(mount) = mounted().into_iter().find(|mount| !before.contains(mount)) {
+            return mount;
+        }
+    }
+}
+
+/// The removable media that are currently mounted.
+fn mounted() -> HashSet<PathBuf> {
+    let user = std::env::var("USER").unwrap_or_default();
+    MOUNT_ROOTS
+        .iter()
+        .flat_map(|root| [PathBuf::from(root), Path::new(root).join(&user)])
+        .filter_map(|root| fs::read_dir(root).ok())
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| is_mount_point(path))
+        .collect()
+}
+
+#[cfg(unix)]
+fn is_mount_point(path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (path.metadata(), path.join("..").metadata()) {
+        (Ok(dir), Ok(parent)) => dir.is_dir() && dir.dev() != parent.dev(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn is_mount_point(path: &Path) -> bool {
+    path.is_dir()
+}
+
+/// The filesystem's UUID where it can be found, which stays the same
+/// wherever the device is mounted; its mount point's name otherwise.
+fn device_id(mount: &Path) -> String {
+    filesystem_uuid(mount).unwrap_or_else(|| {
+        mount.file_name().map_or_else(
+            || mount.display().to_string(),
+            |name| name.to_string_lossy().to_string(),
+        )
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn filesystem_uuid(mount: &Path) -> Option<String> {
+    let device = block_device(mount)?;
+    fs::read_dir("/dev/disk/by-uuid")
+        .ok()?
+        .filter_map(Result::ok)
+        .find(|entry| fs::canonicalize(entry.path()).ok().as_deref() == Some(device.as_path()))
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn filesystem_uuid(_mount: &Path) -> Option<String> {
+    None
+}
+
+/// The block device mounted at `mount`, from `/proc/mounts`.
+#[cfg(target_os = "linux")]
+fn block_device(mount: &Path) -> Option<PathBuf> {
+    let mount = fs::canonicalize(mount).ok()?;
+    let mounts = fs::read_to_string("/proc/mounts").ok()?;
+    mounts.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let device = fields.next()?;
+        // Spaces in mount points are escaped as '\040'.
+        let point = fields.next()?.replace("\\040", " ");
+        (Path::new(&point) == mount)
+            .then(|| fs::canonicalize(device).ok())
+            .flatten()
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn eject(mount: &Path) -> io::Result<()> {
+    match block_device(mount) {
+        Some(device) => {
+            run_tool(
+                process::Command::new("udisksctl")
+                    .arg("unmount")
+                    .arg("-b")
+                    .arg(&device),
+            )?;
+            run_tool(
+                process::Command::new("udisksctl")
+                    .arg("power-off")
+                    .arg("-b")
+                    .arg(&device),
+            )
+        }
+        None => run_tool(process::Command::new("eject").arg(mount)),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn eject(mount: &Path) -> io::Result<()> {
+    run_tool(process::Command::new("diskutil").arg("eject").arg(mount))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn eject(_mount: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "ejecting devices is not supported on this platform",
+    ))
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn run_tool(command: &mut process::Command) -> io::Result<()> {
+    let output = command.output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "failed to eject: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,241 @@
+use {
+    crate::{ConflictStrategy, DEFAULT_CATEGORY_CONFIG},
+    clap::ValueEnum,
+    std::{
+        env,
+        error::Error,
+        fs,
+        io::{self, BufRead, Write},
+        path::{Path, PathBuf},
+    },
+    toml::{Table, Value},
+    tracing::{info, warn},
+};
+
+/// Walks through the common choices and writes a config file, plus systemd
+/// units that re-sort each source when it changes (Linux only).
+pub fn run() -> Result<(), Box<dyn Error>> {
+    let home = home_dir().ok_or("could not determine the home directory")?;
+
+    println!("This will write a dirsort config for you. Press enter to accept the [default].\n");
+
+    let mut sources = Vec::new();
+    for name in ["Downloads", "Desktop"] {
+        let dir = home.join(name);
+        if dir.is_dir() && confirm(&format!("Sort {}?", dir.display()), true)? {
+            sources.push(dir);
+        }
+    }
+    for dir in list(&ask("Other folders to sort (comma-separated)", "")?) {
+        let dir = expand_home(&dir, &home);
+        if dir.is_dir() {
+            sources.push(dir);
+        } else {
+            warn!("'{}' is not a directory, skipping.", dir.display());
+        }
+    }
+
+    let mut config: Table = toml::from_str(DEFAULT_CATEGORY_CONFIG)?;
+    let categories = config
+        .get_mut("categories")
+        .and_then(Value::as_table_mut)
+        .ok_or("default config has no categories")?;
+
+    let defaults = categories.keys().cloned().collect::<Vec<_>>().join(", ");
+    let keep = list(&ask("Categories to use", &defaults)?);
+    categories.retain(|name, _| keep.iter().any(|kept| kept == name));
+
+    loop {
+        let extra = ask("Add a category as 'Name=ext,ext' (empty to finish)", "")?;
+        let Some((name, exts)) = extra.split_once('=') else {
+            if !extra.is_empty() {
+                warn!("Expected 'Name=ext,ext'.");
+                continue;
+            }
+            break;
+        };
+        let exts = list(exts)
+            .into_iter()
+            .map(|ext| Value::String(ext.trim_start_matches('.').to_lowercase()))
+            .collect();
+        categories.insert(name.trim().to_string(), Value::Array(exts));
+    }
+
+    let output_dir = expand_home(&ask("Sort into", "~/Sorted")?, &home);
+    let mv = confirm("Move files instead of copying them?", false)?;
+    let on_conflict = loop {
+        let answer = ask(
+            "When a destination already exists: overwrite, skip or rename?",
+            "rename",
+        )?;
+        match ConflictStrategy::from_str(&answer, true) {
+            Ok(strategy) => break strategy,
+            Err(_) => warn!("Expected 'overwrite', 'skip' or 'rename'."),
+        }
+    };
+
+    let config_path = config_dir(&home).join("dirsort").join("config.toml");
+    if config_path.exists()
+        && !confirm(
+            &format!("{} exists, overwrite it?", config_path.display()),
+            false,
+        )?
+    {
+        return Err("not overwriting the existing config".into());
+    }
+    fs::create_dir_all(config_path.parent().unwrap_or(&home))?;
+    fs::write(&config_path, toml::to_string(&config)?)?;
+    info!("Wrote {}", config_path.display());
+
+    let exe = env::current_exe()?;
+    let mut command = vec![
+        exe.display().to_string(),
+        "--config".to_string(),
+        config_path.display().to_string(),
+        "--output-dir".to_string(),
+        output_dir.display().to_string(),
+        "--on-conflict".to_string(),
+        on_conflict
+            .to_possible_value()
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default(),
+    ];
+    if mv {
+        command.push("--move".to_string());
+    }
+
+    if cfg!(target_os = "linux")
+        && !sources.is_empty()
+        && confirm(
+            "Install systemd user units that sort these folders whenever they change?",
+            false,
+        )?
+    {
+        let unit_dir = config_dir(&home).join("systemd").join("user");
+        fs::create_dir_all(&unit_dir)?;
+
+        for source in &sources {
+            let name = unit_name(source);
+            fs::write(
+                unit_dir.join(format!("{name}.service")),
+                service_unit(source, &command),
+            )?;
+            fs::write(unit_dir.join(format!("{name}.path")), path_unit(source))?;
+            info!("Installed {name}, enable it with: systemctl --user enable --now {name}.path");
+        }
+        return Ok(());
+    }
+
+    println!("\nTo sort, run this from each folder:\n");
+    println!(
+        "  {}",
+        command
+            .iter()
+            .map(|arg| quote(arg))
+            .collect::<Vec<_>>()
+            .join(" ")
+    );
+    for source in &sources {
+        println!("    in {}", source.display());
+    }
+
+    Ok(())
+}
+
+fn ask(question: &str, default: &str) -> io::Result<String> {
+    if default.is_empty() {
+        print!("{question}: ");
+    } else {
+        print!("{question} [{default}]: ");
+    }
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer)? == 0 {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "input closed"));
+    }
+
+    let answer = answer.trim();
+    Ok(if answer.is_empty() { default } else { answer }.to_string())
+}
+
+fn confirm(question: &str, default: bool) -> io::Result<bool> {
+    let answer = ask(question, if default { "Y/n" } else { "y/N" })?;
+    Ok(match answer.to_lowercase().as_str() {
+        "y" | "yes" => true,
+        "n" | "no" => false,
+        _ => default,
+    })
+}
+
+fn list(answer: &str) -> Vec<String> {
+    answer
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+pub fn home_dir() -> Option<PathBuf> {
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+}
+
+fn config_dir(home: &Path) -> PathBuf {
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .unwrap_or_else(|| home.join(".config"))
+}
+
+pub fn expand_home(path: &str, home: &Path) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => home.join(rest),
+        None if path == "~" => home.to_path_buf(),
+        None => PathBuf::from(path),
+    }
+}
+
+fn unit_name(source: &Path) -> String {
+    let name: String = source
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    format!("dirsort-{name}")
+}
+
+fn quote(arg: &str) -> String {
+    if arg.contains(|c: char| c.is_whitespace() || c == '"') {
+        format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        arg.to_string()
+    }
+}
+
+fn service_unit(source: &Path, command: &[String]) -> String {
+    format!(
+        "[Unit]\nDescription=Sort {source} with dirsort\n\n\
+         [Service]\nType=oneshot\nWorkingDirectory={source}\nExecStart={command}\n",
+        source = source.display(),
+        command = command
+            .iter()
+            .map(|arg| quote(arg))
+            .collect::<Vec<_>>()
+            .join(" "),
+    )
+}
+
+fn path_unit(source: &Path) -> String {
+    format!(
+        "[Unit]\nDescription=Watch {source} for new files to sort\n\n\
+         [Path]\nPathChanged={source}\n\n\
+         [Install]\nWantedBy=default.target\n",
+        source = source.display(),
+    )
+}
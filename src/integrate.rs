@@ -0,0 +1,302 @@
+use {
+    crate::init,
+    std::{env, error::Error, fs, path::Path, process::Command},
+    tracing::info,
+};
+
+const MENU_ITEM: &str = "Sort this folder with dirsort";
+
+/// Explorer's context menu on folders, and on the background inside one.
+const EXPLORER_KEYS: &[&str] = &[
+    r"HKCU\Software\Classes\Directory\shell\dirsort",
+    r"HKCU\Software\Classes\Directory\Background\shell\dirsort",
+];
+
+const WORKFLOW: &str = "Sort with dirsort.workflow";
+
+/// Adds "Sort this folder with dirsort" to Explorer's context menu. `args`
+/// are the options the menu item runs dirsort with.
+pub fn explorer(args: &[String], remove: bool) -> Result<(), Box<dyn Error>> {
+    if !cfg!(windows) {
+        return Err("the Explorer integration is only available on Windows".into());
+    }
+
+    for key in EXPLORER_KEYS {
+        if remove {
+            // Missing keys are fine, the menu item is gone either way.
+            let _ = reg(&["delete", key, "/f"]);
+            continue;
+        }
+
+        let exe = env::current_exe()?.display().to_string();
+        // `/k` keeps the window open, so the summary can be read.
+        let command = format!(
+            r#"cmd.exe /k cd /d "%V" && {}"#,
+            std::iter::once(&exe)
+                .chain(args)
+                .map(|arg| windows_quote(arg))
+                .collect::<Vec<_>>()
+                .join(" ")
+        );
+        reg(&["add", key, "/ve", "/d", MENU_ITEM, "/f"])?;
+        reg(&["add", key, "/v", "Icon", "/d", &exe, "/f"])?;
+        reg(&[
+            "add",
+            &format!(r"{key}\command"),
+            "/ve",
+            "/d",
+            &command,
+            "/f",
+        ])?;
+    }
+
+    info!(
+        "{}",
+        if remove {
+            "Removed the Explorer context menu item"
+        } else {
+            "Added \"Sort this folder with dirsort\" to the Explorer context menu"
+        }
+    );
+    Ok(())
+}
+
+fn reg(args: &[&str]) -> Result<(), Box<dyn Error>> {
+    let output = Command::new("reg.exe").args(args).output()?;
+    if !output.status.success() {
+        return Err(format!(
+            "reg {} failed: {}",
+            args[0],
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+fn windows_quote(arg: &str) -> String {
+    if arg.contains(' ') {
+        format!("\"{arg}\"")
+    } else {
+        arg.to_string()
+    }
+}
+
+/// Installs a Quick Action that shows up as "Sort this folder with dirsort"
+/// when right-clicking folders in Finder. `args` are the options it runs
+/// dirsort with; output goes to `~/Library/Logs/dirsort.log`.
+pub fn finder(args: &[String], remove: bool) -> Result<(), Box<dyn Error>> {
+    if !cfg!(target_os = "macos") {
+        return Err("the Finder integration is only available on macOS".into());
+    }
+
+    let home = init::home_dir().ok_or("could not determine the home directory")?;
+    let workflow = home.join("Library").join("Services").join(WORKFLOW);
+    if remove {
+        if workflow.exists() {
+            fs::remove_dir_all(&workflow)?;
+        }
+        refresh_services();
+        info!("Removed the Finder Quick Action");
+        return Ok(());
+    }
+
+    let exe = env::current_exe()?;
+    let contents = workflow.join("Contents");
+    fs::create_dir_all(&contents)?;
+    fs::write(contents.join("Info.plist"), INFO_PLIST)?;
+    fs::write(
+        contents.join("document.wflow"),
+        document_wflow(&shell_script(&exe, args, &home)),
+    )?;
+    refresh_services();
+
+    info!(
+        "Installed the Quick Action at '{}', find it under Quick Actions when right-clicking a folder",
+        workflow.display()
+    );
+    Ok(())
+}
+
+/// Makes Finder pick up the changed Quick Action without logging out.
+fn refresh_services() {
+    let _ = Command::new("/System/Library/CoreServices/pbs")
+        .arg("-update")
+        .status();
+}
+
+fn shell_script(exe: &Path, args: &[String], home: &Path) -> String {
+    let command = std::iter::once(exe.display().to_string())
+        .chain(args.iter().cloned())
+        // Without a terminal, the notification is the only sign it finished.
+        .chain(["--notify".to_string()])
+        .map(|arg| sh_quote(&arg))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let log = home.join("Library").join("Logs").join("dirsort.log");
+    format!(
+        "for dir in \"$@\"; do\n    cd \"$dir\" && {command} >> {log} 2>&1\ndone\n",
+        log = sh_quote(&log.display().to_string())
+    )
+}
+
+fn sh_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+const INFO_PLIST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>NSServices</key>
+	<array>
+		<dict>
+			<key>NSMenuItem</key>
+			<dict>
+				<key>default</key>
+				<string>Sort this folder with dirsort</string>
+			</dict>
+			<key>NSMessage</key>
+			<string>runWorkflowAsService</string>
+			<key>NSRequiredContext</key>
+			<dict>
+				<key>NSApplicationIdentifier</key>
+				<string>com.apple.finder</string>
+			</dict>
+			<key>NSSendFileTypes</key>
+			<array>
+				<string>public.folder</string>
+			</array>
+		</dict>
+	</array>
+</dict>
+</plist>
+"#;
+
+/// A workflow with a single "Run Shell Script" action that gets the selected
+/// folders as arguments.
+fn document_wflow(script: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>AMApplicationBuild</key>
+	<string>523</string>
+	<key>AMApplicationVersion</key>
+	<string>2.10</string>
+	<key>AMDocumentVersion</key>
+	<string>2</string>
+	<key>actions</key>
+	<array>
+		<dict>
+			<key>action</key>
+			<dict>
+				<key>AMAccepts</key>
+				<dict>
+					<key>Container</key>
+					<string>List</string>
+					<key>Optional</key>
+					<true/>
+					<key>Types</key>
+					<array>
+						<string>com.apple.cocoa.string</string>
+					</array>
+				</dict>
+				<key>AMActionVersion</key>
+				<string>2.0.3</string>
+				<key>AMParameterProperties</key>
+				<dict>
+					<key>COMMAND_STRING</key>
+					<dict/>
+					<key>inputMethod</key>
+					<dict/>
+					<key>shell</key>
+					<dict/>
+					<key>source</key>
+					<dict/>
+				</dict>
+				<key>AMProvides</key>
+				<dict>
+					<key>Container</key>
+					<string>List</string>
+					<key>Types</key>
+					<array>
+						<string>com.apple.cocoa.string</string>
+					</array>
+				</dict>
+				<key>ActionBundlePath</key>
+				<string>/System/Library/Automator/Run Shell Script.action</string>
+				<key>ActionName</key>
+				<string>Run Shell Script</string>
+				<key>ActionParameters</key>
+				<dict>
+					<key>COMMAND_STRING</key>
+					<string>{script}</string>
+					<key>CheckedForUserDefaultShell</key>
+					<true/>
+					<key>inputMethod</key>
+					<integer>1</integer>
+					<key>shell</key>
+					<string>/bin/sh</string>
+					<key>source</key>
+					<string></string>
+				</dict>
+				<key>BundleIdentifier</key>
+				<string>com.apple.RunShellScript</string>
+				<key>CFBundleVersion</key>
+				<string>2.0.3</string>
+				<key>CanShowSelectedItemsWhenRun</key>
+				<false/>
+				<key>CanShowWhenRun</key>
+				<true/>
+				<key>Class Name</key>
+				<string>RunShellScriptAction</string>
+				<key>InputUUID</key>
+				<string>6A1F2E4C-2C67-4F1B-9B53-5E0D2D3F1A01</string>
+				<key>OutputUUID</key>
+				<string>6A1F2E4C-2C67-4F1B-9B53-5E0D2D3F1A02</string>
+				<key>UUID</key>
+				<string>6A1F2E4C-2C67-4F1B-9B53-5E0D2D3F1A03</string>
+				<key>isViewVisible</key>
+				<integer>1</integer>
+			</dict>
+		</dict>
+	</array>
+	<key>connectors</key>
+	<dict/>
+	<key>workflowMetaData</key>
+	<dict>
+		<key>applicationBundleID</key>
+		<string>com.apple.finder</string>
+		<key>inputTypeIdentifier</key>
+		<string>com.apple.Automator.fileSystemObject.folder</string>
+		<key>outputTypeIdentifier</key>
+		<string>com.apple.Automator.nothing</string>
+		<key>presentationMode</key>
+		<integer>15</integer>
+		<key>processesInput</key>
+		<false/>
+		<key>serviceApplicationBundleID</key>
+		<string>com.apple.finder</string>
+		<key>serviceInputTypeIdentifier</key>
+		<string>com.apple.Automator.fileSystemObject.folder</string>
+		<key>serviceOutputTypeIdentifier</key>
+		<string>com.apple.Automator.nothing</string>
+		<key>serviceProcessesInput</key>
+		<false/>
+		<key>workflowTypeIdentifier</key>
+		<string>com.apple.Automator.servicesMenu</string>
+	</dict>
+</dict>
+</plist>
+"#,
+        script = xml_escape(script)
+    )
+}
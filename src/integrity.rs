@@ -0,0 +1,92 @@
+use {
+    crate::copy_file,
+    rayon::prelude::*,
+    std::{
+        fmt, fs, io,
+        path::{Path, PathBuf},
+    },
+};
+
+/// A file placed during the run, with the size its source had.
+pub struct Placed {
+    pub source: PathBuf,
+    pub dest: PathBuf,
+    /// For `--checksums`, to hash the file again after a repair
+    pub category_dir: PathBuf,
+    pub size: u64,
+    pub moved: bool,
+}
+
+/// What is wrong with a placed file.
+pub enum Problem {
+    Missing,
+    /// Empty although its source wasn't
+    Empty,
+    /// Of a different size than its source
+    Truncated {
+        found: u64,
+    },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Problem::Missing => write!(f, "missing"),
+            Problem::Empty => write!(f, "zero bytes"),
+            Problem::Truncated { found } => write!(f, "{found} bytes"),
+        }
+    }
+}
+
+pub struct Damaged {
+    pub placed: Placed,
+    pub problem: Problem,
+}
+
+/// Checks every placed file against the size its source had, finding the
+/// ones that went missing, came out empty or were cut short, as a full disk
+/// or a flaky mount can leave them.
+pub fn sweep(placed: Vec<Placed>) -> Vec<Damaged> {
+    let mut damaged: Vec<Damaged> = placed
+        .into_par_iter()
+        .filter_map(|placed| {
+            let problem = match fs::metadata(&placed.dest) {
+                Err(_) => Problem::Missing,
+                Ok(metadata) if metadata.len() == placed.size => return None,
+                Ok(metadata) if metadata.len() == 0 => Problem::Empty,
+                Ok(metadata) => Problem::Truncated {
+                    found: metadata.len(),
+                },
+            };
+            Some(Damaged { placed, problem })
+        })
+        .collect();
+    damaged.sort_by(|a, b| a.placed.dest.cmp(&b.placed.dest));
+    damaged
+}
+
+/// `--repair`: copies the source over the damaged file again, as long as
+/// it's still there and unchanged. Files that were moved have no source left.
+pub fn repair(damaged: &Damaged) -> io::Result<()> {
+    let Placed {
+        source, dest, size, ..
+    } = &damaged.placed;
+    let unchanged = |path: &Path| fs::metadata(path).is_ok_and(|m| m.len() == *size);
+    if !unchanged(source) {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            match damaged.placed.moved {
+                true => "the source was moved, there's nothing to copy from",
+                false => "the source is gone or has changed",
+            },
+        ));
+    }
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    copy_file(source, dest)?;
+    match unchanged(dest) {
+        true => Ok(()),
+        false => Err(io::Error::other("the copy came out damaged again")),
+    }
+}
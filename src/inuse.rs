@@ -0,0 +1,117 @@
+use std::path::Path;
+#[cfg(target_os = "linux")]
+use std::{collections::HashSet, fs, os::unix::fs::MetadataExt, process};
+
+/// What to do with a file another process has open when it is about to be moved.
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum InUsePolicy {
+    /// Try it again once everything else has been placed, then skip it
+    Defer,
+    /// Leave it where it is
+    Skip,
+    /// Move it anyway
+    Ignore,
+}
+
+/// Files that other processes had open when the run started. Only Linux
+/// exposes this (through `/proc`); elsewhere only locks are checked.
+#[derive(Default)]
+pub struct OpenFiles {
+    #[cfg(target_os = "linux")]
+    ids: HashSet<(u64, u64)>,
+}
+
+impl OpenFiles {
+    #[cfg(target_os = "linux")]
+    pub fn snapshot() -> Self {
+        let own_pid = process::id().to_string();
+        let mut ids = HashSet::new();
+
+        let Ok(processes) = fs::read_dir("/proc") else {
+            return Self { ids };
+        };
+        for process in processes.flatten() {
+            if process.file_name() == own_pid.as_str() {
+                continue;
+            }
+            // Other users' processes aren't readable without privileges.
+            let Ok(fds) = fs::read_dir(process.path().join("fd")) else {
+                continue;
+            };
+            for fd in fds.flatten() {
+                if let Ok(metadata) = fs::metadata(fd.path())
+                    && metadata.is_file()
+                {
+                    ids.insert((metadata.dev(), metadata.ino()));
+                }
+            }
+        }
+
+        Self { ids }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn snapshot() -> Self {
+        Self::default()
+    }
+
+    /// Whether `path` is open in another process or locked.
+    pub fn in_use(&self, path: &Path) -> bool {
+        #[cfg(target_os = "linux")]
+        if let Ok(metadata) = fs::metadata(path)
+            && self.ids.contains(&(metadata.dev(), metadata.ino()))
+        {
+            return true;
+        }
+
+        locked(path)
+    }
+}
+
+/// Probes for `flock` and `fcntl` locks held by other processes.
+#[cfg(unix)]
+fn locked(path: &Path) -> bool {
+    use nix::{
+        fcntl::{FcntlArg, Flock, FlockArg, fcntl},
+        libc,
+    };
+
+    let Ok(file) = std::fs::File::open(path) else {
+        return false;
+    };
+
+    // SAFETY: `flock` is plain old data; all zeroes describes the whole file.
+    let mut lock: libc::flock = unsafe { std::mem::zeroed() };
+    lock.l_type = libc::F_WRLCK as _;
+    if fcntl(&file, FcntlArg::F_GETLK(&mut lock)).is_ok()
+        && lock.l_type as libc::c_int != libc::F_UNLCK
+    {
+        return true;
+    }
+
+    // The probe lock is released again when it is dropped.
+    Flock::lock(file, FlockArg::LockExclusiveNonblock).is_err()
+}
+
+/// Opens the file without sharing, which fails while another process has it
+/// open without `FILE_SHARE_*` flags allowing it.
+#[cfg(windows)]
+fn locked(path: &Path) -> bool {
+    use std::{fs::OpenOptions, os::windows::fs::OpenOptionsExt};
+
+    const ERROR_SHARING_VIOLATION: i32 = 32;
+    const ERROR_LOCK_VIOLATION: i32 = 33;
+
+    match OpenOptions::new().read(true).share_mode(0).open(path) {
+        Ok(_) => false,
+        Err(e) => matches!(
+            e.raw_os_error(),
+            Some(ERROR_SHARING_VIOLATION | ERROR_LOCK_VIOLATION)
+        ),
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn locked(_path: &Path) -> bool {
+    false
+}
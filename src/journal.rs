@@ -0,0 +1,344 @@
+use {
+    crate::{history, move_file},
+    chrono::{DateTime, Duration, Local, NaiveDateTime, TimeZone},
+    serde::{Deserialize, Serialize},
+    std::{
+        fs::{self, File, OpenOptions},
+        io::{self, BufRead, BufReader, BufWriter, Write},
+        path::{self, Path, PathBuf},
+        sync::Mutex,
+    },
+    tracing::{error, info, warn},
+};
+
+/// One placed file or directory.
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    source: PathBuf,
+    dest: PathBuf,
+    moved: bool,
+    /// Where `--backup` put the destination this replaced
+    #[serde(skip_serializing_if = "Option::is_none")]
+    backup: Option<PathBuf>,
+    /// The label of the `--source` the file came from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    origin: Option<String>,
+    /// Folders the run created for it, deepest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    created: Vec<PathBuf>,
+    /// Made by dirsort from `source`, like `--convert` output, so undo
+    /// removes it whatever became of `source`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    made: bool,
+}
+
+/// Records every placement of a run in `runs/<id>.jsonl` under the state
+/// directory, so `dirsort undo` can put things back. Each line is flushed as
+/// it is written, so a run that is killed can still be undone.
+pub struct Journal {
+    id: String,
+    path: PathBuf,
+    file: Mutex<BufWriter<File>>,
+}
+
+impl Journal {
+    /// Starts the journal of a run started at `started`. Its id is the time
+    /// to the second, with `-2`, `-3`, ... after it when runs start in the
+    /// same second.
+    pub fn create(started: &DateTime<Local>) -> io::Result<Self> {
+        let stamp = started.format("%Y%m%d-%H%M%S").to_string();
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let id = match attempt {
+                1 => stamp.clone(),
+                n => format!("{stamp}-{n}"),
+            };
+            let path = journal_path(&id)?;
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => {
+                    return Ok(Self {
+                        id,
+                        file: Mutex::new(BufWriter::new(file)),
+                        path,
+                    });
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// The run's id, for its history entry and `dirsort undo`.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn record(
+        &self,
+        source: &Path,
+        dest: &Path,
+        moved: bool,
+        backup: Option<PathBuf>,
+        origin: Option<&str>,
+        created: Vec<PathBuf>,
+    ) -> io::Result<()> {
+        // Undo may run from anywhere, so relative paths won't do.
+        let entry = Entry {
+            source: path::absolute(source)?,
+            dest: path::absolute(dest)?,
+            moved,
+            backup,
+            origin: origin.map(str::to_string),
+            created: created
+                .iter()
+                .map(path::absolute)
+                .collect::<io::Result<_>>()?,
+            made: false,
+        };
+        self.write(&entry)
+    }
+
+    /// Records `dest`, which dirsort made from `source` rather than placed.
+    pub fn record_made(&self, source: &Path, dest: &Path, created: Vec<PathBuf>) -> io::Result<()> {
+        let entry = Entry {
+            source: path::absolute(source)?,
+            dest: path::absolute(dest)?,
+            moved: false,
+            backup: None,
+            origin: None,
+            created: created
+                .iter()
+                .map(path::absolute)
+                .collect::<io::Result<_>>()?,
+            made: true,
+        };
+        self.write(&entry)
+    }
+
+    fn write(&self, entry: &Entry) -> io::Result<()> {
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        writeln!(file, "{}", serde_json::to_string(entry)?)?;
+        file.flush()
+    }
+}
+
+/// The folders `create_dir_all(dir)` is about to create, deepest first, so
+/// undo removes only those and never a folder that was there before.
+pub fn missing_dirs(dir: &Path) -> Vec<PathBuf> {
+    dir.ancestors()
+        .take_while(|dir| !dir.as_os_str().is_empty() && !dir.exists())
+        .map(Path::to_path_buf)
+        .collect()
+}
+
+fn journal_path(run_id: &str) -> io::Result<PathBuf> {
+    history::state_dir()
+        .map(|dir| dir.join("runs").join(format!("{run_id}.jsonl")))
+        .ok_or_else(|| io::Error::other("could not determine the state directory"))
+}
+
+static REWRITING: Mutex<()> = Mutex::new(());
+
+/// Points journal entries that placed a file at or under `old` to `new`, or
+/// drops them when it was deleted, so undoing those runs follows later
+/// changes made through the server or to folder names.
+pub fn relocate(old: &Path, new: Option<&Path>) -> io::Result<()> {
+    let Some(dir) = history::state_dir().map(|dir| dir.join("runs")) else {
+        return Ok(());
+    };
+    let old = path::absolute(old)?;
+    let new = new.map(path::absolute).transpose()?;
+    let journals = match fs::read_dir(&dir) {
+        Ok(journals) => journals,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+
+    // Requests are served in parallel, and each rewrites whole journals.
+    let _rewriting = REWRITING.lock().unwrap_or_else(|e| e.into_inner());
+    for journal in journals {
+        let path = journal?.path();
+        if path.extension().is_none_or(|ext| ext != "jsonl") {
+            continue;
+        }
+        let mut entries: Vec<Entry> = BufReader::new(File::open(&path)?)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str(&line).ok())
+            .collect();
+        if !entries.iter().any(|entry| entry.dest.starts_with(&old)) {
+            continue;
+        }
+
+        match &new {
+            Some(new) => entries.iter_mut().for_each(|entry| {
+                if let Ok(rest) = entry.dest.strip_prefix(&old) {
+                    entry.dest = relocated(new, rest);
+                }
+            }),
+            None => entries.retain(|entry| !entry.dest.starts_with(&old)),
+        }
+        // Written aside and renamed over it, so a crash can't cut the
+        // journal short.
+        let temp = path.with_extension("jsonl.tmp");
+        let mut file = BufWriter::new(File::create(&temp)?);
+        for entry in &entries {
+            writeln!(file, "{}", serde_json::to_string(entry)?)?;
+        }
+        file.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        fs::rename(&temp, &path)?;
+    }
+    Ok(())
+}
+
+/// `new` joined with `rest`, without the trailing separator joining an empty
+/// path would add.
+pub fn relocated(new: &Path, rest: &Path) -> PathBuf {
+    if rest.as_os_str().is_empty() {
+        new.to_path_buf()
+    } else {
+        new.join(rest)
+    }
+}
+
+/// Parses `--since`: "2 hours ago", "30m", "1 day", or a local time like
+/// "2024-05-01 14:00".
+pub fn parse_since(since: &str) -> Result<DateTime<Local>, String> {
+    let since = since.trim();
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S"] {
+        if let Ok(time) = NaiveDateTime::parse_from_str(since, format)
+            && let Some(time) = Local.from_local_datetime(&time).earliest()
+        {
+            return Ok(time);
+        }
+    }
+
+    let ago = since.strip_suffix("ago").unwrap_or(since).trim();
+    let split = ago.find(|c: char| !c.is_ascii_digit()).unwrap_or(ago.len());
+    let (count, unit) = ago.split_at(split);
+    let count: i64 = count
+        .parse()
+        .map_err(|_| format!("invalid time '{since}', expected e.g. '2 hours ago'"))?;
+    let duration = match unit.trim().trim_end_matches('s') {
+        "" | "sec" | "second" => Duration::seconds(count),
+        "m" | "min" | "minute" => Duration::minutes(count),
+        "h" | "hour" => Duration::hours(count),
+        "d" | "day" => Duration::days(count),
+        "w" | "week" => Duration::weeks(count),
+        unit => return Err(format!("unknown time unit '{unit}' in '{since}'")),
+    };
+    Ok(Local::now() - duration)
+}
+
+/// Reverses the run matching `run_id`, every run since `since`, or else the
+/// most recent run, newest first.
+pub fn undo(run_id: Option<&str>, since: Option<DateTime<Local>>) -> io::Result<()> {
+    let runs = history::load()?;
+    let mut selected: Vec<&history::Run> = match (run_id, since) {
+        (Some(id), _) => history::matching(&runs, id).into_iter().collect(),
+        (None, Some(since)) => runs
+            .iter()
+            .filter(|run| {
+                DateTime::parse_from_rfc3339(&run.started).is_ok_and(|started| started >= since)
+            })
+            .collect(),
+        (None, None) => runs.last().into_iter().collect(),
+    };
+    selected.sort_by_key(|run| std::cmp::Reverse(DateTime::parse_from_rfc3339(&run.started).ok()));
+
+    if selected.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "no matching runs"));
+    }
+
+    for run in selected {
+        let path = journal_path(&run.id)?;
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                info!("Run {} has nothing to undo", run.id);
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        let entries: Vec<Entry> = BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str(&line).ok())
+            .collect();
+
+        info!("Undoing run {} ({} files)...", run.id, entries.len());
+        let mut failed = 0;
+        for entry in entries.iter().rev() {
+            if let Err(e) = revert(entry) {
+                failed += 1;
+                error!("Failed to undo '{}': {e}", entry.dest.display());
+            }
+        }
+
+        if failed == 0 {
+            fs::remove_file(&path)?;
+        } else {
+            warn!(
+                "{failed} files of run {} could not be put back, run undo again once fixed",
+                run.id
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn revert(entry: &Entry) -> io::Result<()> {
+    if fs::symlink_metadata(&entry.dest).is_err() {
+        // Already put back by an earlier, interrupted undo.
+        if !entry.moved || fs::symlink_metadata(&entry.source).is_ok() {
+            return Ok(());
+        }
+        return Err(io::Error::new(io::ErrorKind::NotFound, "no longer there"));
+    }
+
+    if entry.made {
+        fs::remove_file(&entry.dest)?;
+    } else if entry.moved {
+        if fs::symlink_metadata(&entry.source).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("'{}' exists again", entry.source.display()),
+            ));
+        }
+        if let Some(parent) = entry.source.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        move_file(&entry.dest, &entry.source)?;
+    } else if fs::symlink_metadata(&entry.source).is_err() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "the original is gone, keeping the copy",
+        ));
+    } else if entry.dest.is_dir() {
+        fs::remove_dir_all(&entry.dest)?;
+    } else {
+        fs::remove_file(&entry.dest)?;
+    }
+
+    if let Some(backup) = &entry.backup {
+        fs::rename(backup, &entry.dest)?;
+    } else {
+        // Folders the run created are removed again once they are empty.
+        for dir in &entry.created {
+            if fs::remove_dir(dir).is_err() {
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
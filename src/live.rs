@@ -0,0 +1,125 @@
+use {
+    crate::index,
+    actix_web::{
+        HttpResponse,
+        body::{BodySize, MessageBody},
+        web::{self, Bytes},
+    },
+    std::{
+        collections::hash_map::DefaultHasher,
+        convert::Infallible,
+        hash::{Hash, Hasher},
+        path::{Path, PathBuf},
+        pin::Pin,
+        sync::Mutex,
+        task::{Context, Poll},
+        thread,
+        time::Duration,
+    },
+    tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
+    tracing::warn,
+    walkdir::WalkDir,
+};
+
+/// Where connected index pages listen for changes.
+pub const EVENTS_PATH: &str = "/_dirsort/events";
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Polls between keep-alive comments, which also drop disconnected browsers.
+const KEEPALIVE_POLLS: u32 = 15;
+
+/// Browsers connected to [`EVENTS_PATH`].
+#[derive(Default)]
+pub struct Clients(Mutex<Vec<UnboundedSender<Bytes>>>);
+
+impl Clients {
+    fn broadcast(&self, message: &'static str) {
+        let mut clients = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        clients.retain(|client| client.send(Bytes::from_static(message.as_bytes())).is_ok());
+    }
+}
+
+/// A server-sent event stream fed by [`watch`].
+struct Events(UnboundedReceiver<Bytes>);
+
+impl MessageBody for Events {
+    type Error = Infallible;
+
+    fn size(&self) -> BodySize {
+        BodySize::Stream
+    }
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        self.0.poll_recv(cx).map(|message| message.map(Ok))
+    }
+}
+
+pub async fn events(clients: web::Data<Clients>) -> HttpResponse {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    clients
+        .0
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(sender);
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        // Compressed events would sit in the encoder's buffer.
+        .insert_header(("Content-Encoding", "identity"))
+        .body(Events(receiver))
+}
+
+/// Polls `dir` in the background. On a change its index, if dirsort
+/// generated one there, is regenerated and connected browsers are told to
+/// reload.
+pub fn watch(dir: PathBuf, clients: web::Data<Clients>) {
+    thread::spawn(move || {
+        let mut last = fingerprint(&dir);
+        let mut polls = 0;
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            polls += 1;
+
+            let current = fingerprint(&dir);
+            if current != last {
+                last = current;
+                if let Err(e) = index::refresh(&dir, false) {
+                    warn!("Failed to regenerate the index: {e}");
+                }
+                clients.broadcast("data: changed\n\n");
+            } else if polls % KEEPALIVE_POLLS == 0 {
+                clients.broadcast(": ping\n\n");
+            }
+        }
+    });
+}
+
+/// Hashes the name, size and modification time of everything under `dir`,
+/// except the index that is regenerated on a change.
+fn fingerprint(dir: &Path) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for entry in WalkDir::new(dir)
+        .min_depth(1)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(Result::ok)
+    {
+        if entry.depth() == 1
+            && index::INDEX_FILES
+                .iter()
+                .any(|name| entry.file_name() == *name)
+        {
+            continue;
+        }
+        entry.path().hash(&mut hasher);
+        if let Ok(metadata) = entry.metadata() {
+            metadata.len().hash(&mut hasher);
+            metadata.modified().ok().hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
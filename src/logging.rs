@@ -0,0 +1,211 @@
+use {
+    crate::progress,
+    serde_json::{Map, Value, json},
+    std::{
+        fmt,
+        io::{self, IsTerminal, Write},
+    },
+    tracing::{
+        Event, Level, Subscriber,
+        field::{Field, Visit},
+    },
+    tracing_subscriber::{
+        Layer,
+        field::RecordFields,
+        filter::Targets,
+        fmt::{
+            FmtContext, FormatEvent, FormatFields, FormattedFields,
+            format::{FmtSpan, Writer},
+        },
+        layer::SubscriberExt,
+        registry::LookupSpan,
+        util::SubscriberInitExt,
+    },
+};
+
+/// How log lines are written.
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum LogFormat {
+    /// The level and message, for reading along in a terminal
+    Pretty,
+    /// Timestamped, with the file or folder each message is about in front
+    Compact,
+    /// One JSON object per line, for log collectors
+    Json,
+}
+
+/// Sets up logging to stderr in `format`. Which messages are shown follows
+/// `RUST_LOG` in the usual `target=level` form, e.g. `dirsort=debug` to
+/// also log how long each file and folder took; by default only dirsort's
+/// own messages from info up are shown.
+pub fn init(format: LogFormat) {
+    let filter = std::env::var("RUST_LOG")
+        .ok()
+        .filter(|spec| !spec.trim().is_empty())
+        .and_then(|spec| match spec.parse::<Targets>() {
+            Ok(filter) => Some(filter),
+            Err(e) => {
+                eprintln!("Ignoring RUST_LOG '{spec}': {e}");
+                None
+            }
+        })
+        .unwrap_or_else(|| Targets::new().with_target("dirsort", Level::INFO));
+
+    // Colored only in a terminal, and never with NO_COLOR set.
+    let ansi = std::io::stderr().is_terminal()
+        && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty());
+    let layer = tracing_subscriber::fmt::layer()
+        .with_writer(|| Line(Vec::new()))
+        .with_ansi(ansi)
+        .with_span_events(FmtSpan::CLOSE);
+    let layer = match format {
+        // Without timestamps, but still with how long each span took.
+        LogFormat::Pretty => layer
+            .event_format(
+                tracing_subscriber::fmt::format()
+                    .without_time()
+                    .with_target(false),
+            )
+            .boxed(),
+        LogFormat::Compact => layer.compact().with_target(false).boxed(),
+        LogFormat::Json => layer
+            .fmt_fields(JsonFields)
+            .event_format(JsonEvent)
+            .with_ansi(false)
+            .boxed(),
+    };
+    tracing_subscriber::registry()
+        .with(layer.with_filter(filter))
+        .init();
+}
+
+/// One log line, written to stderr in one go once it is complete, above the
+/// progress bars if they are shown.
+struct Line(Vec<u8>);
+
+impl Write for Line {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for Line {
+    fn drop(&mut self) {
+        progress::suspend(|| {
+            let _ = io::stderr().write_all(&self.0);
+        });
+    }
+}
+
+/// Collects the fields of an event or span into a JSON object.
+#[derive(Default)]
+struct JsonVisitor(Map<String, Value>);
+
+impl Visit for JsonVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), json!(format!("{value:?}")));
+    }
+}
+
+/// Keeps span fields as JSON, so `JsonEvent` can nest them.
+struct JsonFields;
+
+impl<'writer> FormatFields<'writer> for JsonFields {
+    fn format_fields<R: RecordFields>(
+        &self,
+        mut writer: Writer<'writer>,
+        fields: R,
+    ) -> fmt::Result {
+        let mut visitor = JsonVisitor::default();
+        fields.record(&mut visitor);
+        write!(writer, "{}", Value::Object(visitor.0))
+    }
+
+    // Fields recorded later, like a file's category, are merged in.
+    fn add_fields(
+        &self,
+        current: &'writer mut FormattedFields<Self>,
+        fields: &tracing::span::Record<'_>,
+    ) -> fmt::Result {
+        let mut visitor = JsonVisitor(serde_json::from_str(&current.fields).unwrap_or_default());
+        fields.record(&mut visitor);
+        current.fields = Value::Object(visitor.0).to_string();
+        Ok(())
+    }
+}
+
+/// One JSON object per event: its time, level, message and fields, and the
+/// spans it happened in from the outermost one.
+struct JsonEvent;
+
+impl<S, N> FormatEvent<S, N> for JsonEvent
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let mut visitor = JsonVisitor::default();
+        event.record(&mut visitor);
+        let mut line = Map::new();
+        line.insert(
+            "timestamp".to_string(),
+            json!(chrono::Local::now().to_rfc3339()),
+        );
+        line.insert(
+            "level".to_string(),
+            json!(event.metadata().level().as_str()),
+        );
+        line.insert("target".to_string(), json!(event.metadata().target()));
+        line.extend(visitor.0);
+
+        let spans: Vec<Value> = ctx
+            .event_scope()
+            .into_iter()
+            .flat_map(|scope| scope.from_root())
+            .map(|span| {
+                let mut fields: Map<String, Value> = span
+                    .extensions()
+                    .get::<FormattedFields<N>>()
+                    .and_then(|fields| serde_json::from_str(&fields.fields).ok())
+                    .unwrap_or_default();
+                fields.insert("name".to_string(), json!(span.name()));
+                Value::Object(fields)
+            })
+            .collect();
+        if !spans.is_empty() {
+            line.insert("spans".to_string(), Value::Array(spans));
+        }
+        writeln!(writer, "{}", Value::Object(line))
+    }
+}
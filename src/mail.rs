@@ -0,0 +1,118 @@
+use {
+    crate::rename::sanitize,
+    mail_parser::{MessageParser, MimeHeaders, mailbox::mbox::MessageIterator},
+    std::{
+        collections::HashMap,
+        fs::{self, File},
+        io::{self, BufReader},
+        path::{Path, PathBuf},
+        process,
+    },
+};
+
+/// Whether `ext` is a mail file `--extract-attachments` opens.
+pub fn is_mail(ext: &str) -> bool {
+    ext.eq_ignore_ascii_case("eml") || ext.eq_ignore_ascii_case("mbox")
+}
+
+/// A scratch directory the attachments are extracted into, so they can be
+/// sorted like any other file. It is removed again when dropped.
+pub struct Attachments {
+    dir: PathBuf,
+    messages: usize,
+    /// The mail file each extracted attachment came from
+    sources: HashMap<PathBuf, PathBuf>,
+}
+
+impl Attachments {
+    pub fn new() -> io::Result<Self> {
+        let dir = scratch_dir();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            messages: 0,
+            sources: HashMap::new(),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The mail file each extracted attachment came from, by the path it
+    /// was extracted to.
+    pub fn take_sources(&mut self) -> HashMap<PathBuf, PathBuf> {
+        std::mem::take(&mut self.sources)
+    }
+
+    /// Extracts the attachments of the message in an `.eml` file, or of
+    /// every message in an mbox. Returns how many there were.
+    pub fn extract(&mut self, path: &Path) -> io::Result<usize> {
+        let is_mbox = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("mbox"));
+        if !is_mbox {
+            return self.extract_message(path, &fs::read(path)?);
+        }
+
+        let mut extracted = 0;
+        for message in MessageIterator::new(BufReader::new(File::open(path)?)) {
+            extracted += self.extract_message(path, message?.contents())?;
+        }
+        Ok(extracted)
+    }
+
+    fn extract_message(&mut self, path: &Path, raw: &[u8]) -> io::Result<usize> {
+        let message = MessageParser::default()
+            .parse(raw)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "not a valid message"))?;
+        // Each message gets its own folder, as attachments are often named
+        // alike, e.g. 'image001.png'.
+        self.messages += 1;
+        let dir = self.dir.join(self.messages.to_string());
+
+        let mut extracted = 0;
+        for (index, part) in message.attachments().enumerate() {
+            let name = match (part.attachment_name(), part.message()) {
+                (Some(name), _) => sanitize(name),
+                // A forwarded message is kept as one.
+                (None, Some(_)) => format!("message-{}.eml", index + 1),
+                (None, None) => format!(
+                    "attachment-{}.{}",
+                    index + 1,
+                    part.content_type()
+                        .and_then(|content_type| content_type.subtype())
+                        .map_or_else(|| "bin".to_string(), sanitize)
+                ),
+            };
+            if name.is_empty() {
+                continue;
+            }
+            fs::create_dir_all(&dir)?;
+            let mut dest = dir.join(&name);
+            if dest.exists() {
+                dest = dir.join(format!("{} {name}", index + 1));
+            }
+            fs::write(&dest, part.contents())?;
+            self.sources.insert(dest, path.to_path_buf());
+            extracted += 1;
+        }
+        Ok(extracted)
+    }
+}
+
+impl Drop for Attachments {
+    fn drop(&mut self) {
+        discard();
+    }
+}
+
+fn scratch_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("dirsort-attachments-{}", process::id()))
+}
+
+/// Removes what was extracted, for ways out of a run that exit the process
+/// before `Attachments` is dropped.
+pub fn discard() {
+    let _ = fs::remove_dir_all(scratch_dir());
+}
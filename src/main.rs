@@ -1,54 +1,296 @@
 use {
     actix_files::Files,
-    actix_web::{App, HttpServer},
-    clap::Parser,
+    actix_web::{
+        App, HttpServer,
+        middleware::{Compress, Condition, DefaultHeaders, from_fn},
+        web,
+    },
+    backup::BackupMode,
+    checksums::{ChecksumFormat, Checksums},
+    chrono::{DateTime, Local},
+    clamav::{ClamdAddr, Verdict},
+    clap::{ArgAction, CommandFactory, FromArgMatches, Subcommand, parser::ValueSource},
     clap_markdown::help_markdown,
-    indicatif::ProgressBar,
+    confirm::{ConfirmMode, Confirmer},
+    convert::Conversion,
+    errors::{DirsortError, SortError},
+    estimate::Estimate,
+    folders::FolderNames,
+    hashing::HashAlgorithm,
+    i18n::t,
+    index::gen_html_index,
+    inuse::{InUsePolicy, OpenFiles},
+    journal::Journal,
+    logging::LogFormat,
     notify_rust::{Notification, Timeout},
-    prettylogger::Logger,
-    rayon::iter::{IntoParallelRefIterator, ParallelIterator},
+    plan::{Action, Plan},
+    priority::IoPriority,
+    progress::{Progress, ProgressMode},
+    quotas::{Admission, Quotas},
+    rayon::iter::{IntoParallelRefIterator, ParallelBridge, ParallelIterator},
+    rename::{NameFields, RenameTemplate, sanitize},
+    report::{Report, ReportFormat, Row, Status},
+    roots::Roots,
     serde::{Deserialize, Serialize},
+    snapshot::Snapshot,
+    sources::{OriginTag, Source},
     std::{
+        cell::OnceCell,
+        cmp::Reverse,
         collections::{HashMap, HashSet},
-        error::{self, Error},
+        error,
         fs::{self, File, create_dir_all, remove_file, rename},
         hash::RandomState,
-        io::{Result, Write},
+        io::{self, IsTerminal, Result, Write},
+        net::SocketAddr,
         path::{Path, PathBuf},
         process,
         sync::{
-            Arc, LazyLock, Mutex,
-            atomic::{AtomicU64, Ordering},
+            Arc, Mutex,
+            atomic::{AtomicBool, AtomicU64, Ordering},
         },
+        thread,
+        time::{Duration, Instant, SystemTime},
     },
+    torrents::Seeding,
+    tracing::{debug_span, error, field, info, warn},
+    transaction::{Staged, Transaction},
+    usage::UsageFormat,
     walkdir::WalkDir,
 };
 
+mod access_log;
+mod backup;
+mod bench;
+mod bursts;
+mod checksums;
+mod clamav;
+mod classify;
+mod code;
+mod confirm;
+mod convert;
+mod daemon;
+#[cfg(target_os = "linux")]
+mod dbus;
+mod diff;
+mod documents;
+mod downloads;
+mod errors;
+mod estimate;
+mod executables;
+#[cfg(target_os = "macos")]
+mod finder;
+mod folders;
+mod hashing;
+mod history;
+mod i18n;
+mod icons;
+mod index;
+mod ingest;
+mod init;
+mod integrate;
+mod integrity;
+mod inuse;
+mod journal;
+mod live;
+mod logging;
+mod mail;
+mod manage;
+mod mdns;
+mod media;
+mod metadata;
+mod mqtt;
+mod overview;
+mod par2;
+mod pause;
+mod perceptual;
+#[cfg(unix)]
+mod perms;
+mod places;
+mod plan;
+mod priority;
+mod progress;
+mod qr;
+mod quotas;
+mod readonly;
+mod rename;
+mod renames;
+mod report;
+mod roots;
+mod rules;
+mod screenshots;
+mod script;
+mod simulate;
+mod snapshot;
+mod sources;
+mod torrents;
+mod transaction;
+mod tui;
+mod usage;
+mod video;
+mod volumes;
+#[cfg(target_os = "linux")]
+mod xattrs;
+
 const DEFAULT_CATEGORY_CONFIG: &str = r#"
+fallback = "unknown"
+
+[uncategorized]
+mode = "by-extension"
+
 [categories]
-Images = ["gif", "ico", "jpeg", "jpg", "jpg~", "png", "png~", "webp"]
+Images.extensions = ["gif", "heic", "heif", "ico", "jpeg", "jpg", "jpg~", "png", "png~", "webp"]
+Images.RAW = ["raw", "dng", "cr2", "cr3", "nef", "arw", "orf", "rw2", "raf"]
 Videos = ["mp4", "mkv", "ogv", "webm"]
 Documents = ["pdf", "docx", "doc", "txt", "md"]
 Audio = ["mp3", "wav", "flac", "ogg"]
 Archives = ["zip", "tar", "gz", "rar"]
+
+[aliases]
+jpeg = "jpg"
+jpe = "jpg"
+tif = "tiff"
+mpeg4 = "mp4"
+htm = "html"
+yml = "yaml"
+
+[live_photos]
+stills = ["heic", "heif", "jpg", "jpeg"]
+videos = ["mov", "mp4"]
+max_video_size = "20MB"
+
+[raw_pairs]
+policy = "together"
+raw = ["raw", "dng", "cr2", "cr3", "nef", "arw", "orf", "rw2", "raf"]
+jpeg = ["jpg", "jpeg"]
+previews_folder = "Previews"
+
+[screenshots]
+folder = "Screenshots"
+patterns = ["Screenshot*", "Screen Shot *", "Screen Recording *", "Capture d*écran*", "Bildschirmfoto*", "スクリーンショット*", "Снимок экрана*"]
+check_metadata = true
+check_dimensions = true
+
+[executables]
+folder = "Executables"
+extensions = ["exe", "msi", "dll", "com", "scr", "appimage", "deb", "rpm", "dmg", "pkg", "apk"]
+detect_magic = true
+
+[code]
+folder = "Code"
+
+[incomplete]
+extensions = ["part", "crdownload", "download", "partial", "opdownload", "!ut", "aria2", "tmp"]
+settle_seconds = 5
+action = "skip"
+folder = "Pending"
+
+[projects]
+markers = [".git", "Cargo.toml", "package.json", "pyproject.toml", "go.mod"]
+bundles = ["app", "framework", "bundle"]
+action = "skip"
+folder = "Projects"
+
+[[sidecars]]
+extensions = ["srt", "ass", "ssa", "sub", "vtt"]
+follows = ["mp4", "mkv", "ogv", "webm"]
+
+[[sidecars]]
+extensions = ["xmp"]
+follows = ["jpg", "jpeg"]
 "#;
 
-static LOGGER_INTERFACE: LazyLock<Logger> = LazyLock::new(Logger::default);
+/// Where `--dedup-perceptual` puts the images it considers duplicates, one
+/// folder per kept image.
+const NEAR_DUPLICATES_DIR: &str = "Review/Near duplicates";
+
+/// Appended to a destination's name while it is being copied.
+const TEMP_SUFFIX: &str = ".dirsort-tmp";
+
+/// Where files that shouldn't be opened casually are placed.
+const QUARANTINE_DIR: &str = "Quarantine";
+
+/// Whether `relative`, under the directory `root` being served, is in the
+/// quarantine. The name is compared in any case, since the filesystem may
+/// not tell them apart, and symlinks are followed to where they lead.
+fn in_quarantine(root: &Path, relative: &Path) -> bool {
+    let named = relative.components().next().is_some_and(|first| {
+        first
+            .as_os_str()
+            .to_string_lossy()
+            .eq_ignore_ascii_case(QUARANTINE_DIR)
+    });
+    // What doesn't exist yet is checked by the folder it would be made in.
+    let real = root
+        .join(relative)
+        .ancestors()
+        .find(|dir| dir.exists())
+        .and_then(|dir| dir.canonicalize().ok());
+    named
+        || match (real, root.join(QUARANTINE_DIR).canonicalize()) {
+            (Some(real), Ok(quarantine)) => real.starts_with(quarantine),
+            _ => false,
+        }
+}
+
+/// What `planned_folder` reports for uncategorized files that are skipped.
+const LEFT_IN_PLACE: &str = "(left in place)";
 
 #[derive(clap::Parser)]
 struct Cli {
-    /// The directory to sort the files into
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// The directory to sort the files into; repeat to spread categories across several disks
     #[arg(short, long)]
-    output_dir: Option<String>,
+    output_dir: Vec<String>,
+
+    /// Sort the files in this directory instead of the current one; repeat to merge several into one output, e.g. 'laptop=/mnt/laptop'
+    ///
+    /// Each file's source is recorded in the journal and the report by its label, which defaults to the directory's name.
+    #[arg(long = "source", value_name = "[LABEL=]DIR", value_parser = sources::parse)]
+    sources: Vec<Source>,
+
+    /// Mark where each placed file came from, by the label of its `--source`
+    #[arg(long, value_enum)]
+    tag_origin: Option<OriginTag>,
 
     /// Send a notification when finished
     #[arg(short, long)]
     notify: bool,
 
+    /// Publish run start, finish and error events to an MQTT broker, e.g. 'tcp://broker:1883'
+    #[arg(long, value_name = "URL")]
+    mqtt: Option<String>,
+
+    /// Topic to publish the `--mqtt` events to
+    #[arg(long, default_value = "dirsort/events")]
+    mqtt_topic: String,
+
     /// Move files instead of copying them
     #[arg(short, long = "move")]
     mv: bool,
 
+    /// Never modify the source tree, for media that must stay pristine: files are copied even with --move, and output directories inside it are refused
+    #[arg(long)]
+    assert_no_writes: bool,
+
+    /// Sort into a new timestamped folder under the output directory on every run, e.g. 'sorted/2024-06-01T12-00/', hardlinking files unchanged since the previous one
+    #[arg(long)]
+    snapshot: bool,
+
+    /// Copy every file into a hidden '.dirsort-staging/' folder in the output directory first, and only move them into place and remove the sources once all of them made it
+    ///
+    /// If any file fails, the staged copies are removed again and nothing is placed or moved. Files that were replaced are put back, from their --backup or from staging.
+    #[arg(long)]
+    transactional: bool,
+
+    /// Copy files the integrity check after the run finds missing, empty or cut short over again from their sources
+    ///
+    /// Moved files have no source left, so they are only listed.
+    #[arg(long)]
+    repair: bool,
+
     /// Extensions to exclude from sorting (comma-separated, e.g., 'txt,log,tmp')
     #[arg(short, long)]
     blacklist: Option<String>,
@@ -61,10 +303,38 @@ struct Cli {
     #[arg(short = 'j', long = "threads")]
     threads: Option<usize>,
 
+    /// Workers per device when the files are spread over several devices, so a slow disk doesn't hold up the others
+    #[arg(long = "device-threads", default_value_t = 4)]
+    device_threads: usize,
+
+    /// Lower the CPU priority by this much, as with nice(1) (-20 to 19)
+    #[arg(long, value_parser = clap::value_parser!(i32).range(-20..=19), allow_negative_numbers = true)]
+    nice: Option<i32>,
+
+    /// Lower the disk priority; on macOS and Windows this runs the whole process in background mode
+    #[arg(long)]
+    ionice: Option<IoPriority>,
+
     /// Maximum depth to recurse into directories (0 = current directory only, default: unlimited)
     #[arg(short = 'd', long = "max-depth")]
     max_depth: Option<usize>,
 
+    /// Order to process files in (default: as they are found)
+    #[arg(long, value_enum)]
+    order: Option<ProcessOrder>,
+
+    /// Only move this many files per run, oldest first or in --order, leaving the rest for later runs
+    ///
+    /// Files that are left in place anyway, like blacklisted, seeding, in-use or incomplete files, don't count.
+    #[arg(long, value_name = "FILES", requires = "mv")]
+    limit: Option<usize>,
+
+    /// Only move this many bytes of files per run, oldest first or in --order, e.g. '10GB'
+    ///
+    /// A file larger than the limit is still moved when it is the first in line, so a run always gets somewhere.
+    #[arg(long, value_name = "SIZE", value_parser = parse_byte_size, requires = "mv")]
+    limit_bytes: Option<ByteSize>,
+
     /// Path to a config file containing extension categories
     #[arg(short = 'c', long = "config")]
     config: Option<String>,
@@ -73,459 +343,4400 @@ struct Cli {
     #[arg(short = 'i', long = "index")]
     gen_html: bool,
 
+    /// Colors of the generated index; it always prints in black on white
+    #[arg(long, value_enum, default_value_t = index::Theme::Auto)]
+    index_theme: index::Theme,
+
+    /// Keep the index current without --index: it is generated on every run and by `serve`, also in the runs `daemon` starts
+    ///
+    /// An index dirsort generated in the output directory is regenerated after every run anyway, so it doesn't go stale when --index is left out.
+    #[arg(long)]
+    index_auto: bool,
+
     /// Serves the resulting sorted directory
     #[arg(short, long)]
     serve: bool,
 
-    #[arg(short, long)]
-    verbose: bool,
+    /// Address to serve on; use '0.0.0.0:6969' to let other devices connect
+    #[arg(long, default_value = "127.0.0.1:6969")]
+    bind: SocketAddr,
 
-    #[arg(short, long, hide = true)]
-    gen_docs: bool,
-}
+    /// Announce the server over mDNS so other devices can find it, optionally under this name (default: 'dirsort on <host>')
+    #[arg(long, value_name = "NAME", num_args = 0..=1, require_equals = true)]
+    mdns: Option<Option<String>>,
 
-#[derive(Serialize, Deserialize)]
-struct SorterConfig {
-    categories: HashMap<String, Vec<String>>,
-}
+    /// Let web apps on other origins fetch from the server, e.g. '--cors=https://example.com'; any origin if none is given
+    #[arg(long, value_name = "ORIGIN", num_args = 0..=1, require_equals = true, default_missing_value = "*")]
+    cors: Option<String>,
 
-fn move_file(from: &Path, to: &Path) -> Result<()> {
-    rename(from, to)
-}
-
-fn gen_html_index(output_dir: &Path) -> Result<()> {
-    let index_path = output_dir.join("index.html");
-    let mut file = File::create(&index_path)?;
-
-    let html = format!(
-        "<!DOCTYPE html>
-<html>
-<head>
-    <title>Directory Index</title>
-    <style>
-        body {{ font-family: Arial, sans-serif; margin: 20px; }}
-        h1 {{ color: #333; }}
-        ul {{ list-style-type: none; padding: 0; }}
-        li {{ margin: 5px 0; }}
-        a {{ color: #0066cc; text-decoration: none; }}
-        a:hover {{ text-decoration: underline; }}
-        .dir {{ font-weight: bold; color: #009933; }}
-    </style>
-</head>
-<body>
-    <h1>Directory Index: {}</h1>
-    <ul>
-",
-        output_dir.display(),
-    );
+    /// Print every request to stdout in the Common Log Format while serving
+    #[arg(long)]
+    serve_log: bool,
 
-    file.write_all(html.as_bytes())?;
+    /// Accept requests to delete, rename and recategorize files while serving, with a token from DIRSORT_TOKEN or printed at startup
+    #[arg(long)]
+    serve_manage: bool,
 
-    for entry in WalkDir::new(output_dir)
-        .min_depth(1)
-        .sort_by(|a, b| a.file_name().cmp(b.file_name()))
-    {
-        let entry = entry?;
-        let path = entry.path();
-        let relative_path = path.strip_prefix(output_dir).expect("AAAAAAA");
+    /// Tag placed files in Finder with the name of their category (macOS only)
+    #[arg(long = "finder-tag-by-category")]
+    finder_tag: bool,
 
-        if path.is_dir() {
-            writeln!(
-                file,
-                r#"        <li><span class="dir">📁 {}/</span></li>"#,
-                relative_path.display()
-            )?;
-        } else {
-            let abs_path = path.canonicalize()?;
-            writeln!(
-                file,
-                r#"        <li><a href="file://{}" target="_blank">📄  {}</a></li>"#,
-                abs_path.display(),
-                relative_path.display()
-            )?;
-        }
-    }
+    /// What to do with the quarantine attribute of placed files (macOS only)
+    #[arg(long, value_enum, default_value_t = QuarantinePolicy::Preserve)]
+    quarantine: QuarantinePolicy,
 
-    writeln!(
-        file,
-        "    </ul>
-</body>
-</html>"
-    )?;
+    /// File attributes to carry over when copying (comma-separated, Linux only)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    preserve: Vec<PreserveAttr>,
 
-    LOGGER_INTERFACE.info(format!("Generated HTML index at {}", index_path.display()).as_str());
+    /// Permission bits to set on placed files, in octal, e.g. '644' (Unix only)
+    #[arg(long, value_parser = parse_mode)]
+    chmod: Option<u32>,
 
-    Ok(())
-}
+    /// Owner to give placed files and created directories, as 'user[:group]' (Unix only)
+    #[arg(long, value_parser = parse_owner)]
+    chown: Option<Owner>,
 
-fn load_categories(
-    path: Option<&String>,
-) -> std::result::Result<HashMap<String, Vec<String>>, Box<dyn error::Error>> {
-    let content = path.map_or_else(
-        || DEFAULT_CATEGORY_CONFIG.to_string(),
-        |path_str| {
-            fs::read_to_string(path_str).unwrap_or_else(|e| {
-                LOGGER_INTERFACE.warning(
-                    format!(
-                        "Failed to read config file '{path_str}': {e}\nFalling back to default."
-                    )
-                    .as_str(),
-                );
-                DEFAULT_CATEGORY_CONFIG.to_string()
-            })
-        },
-    );
+    /// Permission bits to set on created category directories, in octal, e.g. '755' (Unix only)
+    #[arg(long = "dir-mode", value_parser = parse_mode)]
+    dir_mode: Option<u32>,
 
-    let config: SorterConfig = toml::from_str(&content)?;
-    let normalized = config
-        .categories
-        .into_iter()
-        .map(|(k, v)| {
-            let cleaned_exts = v
-                .into_iter()
-                .map(|ext| ext.trim_start_matches('.').to_lowercase())
-                .collect();
-            (k, cleaned_exts)
-        })
-        .collect();
+    /// What to do when a destination file already exists or is claimed by another file
+    #[arg(long = "on-conflict", value_enum, default_value_t = ConflictStrategy::Overwrite)]
+    on_conflict: ConflictStrategy,
 
-    Ok(normalized)
-}
+    /// Keep an existing destination before it is overwritten: 'numbered', 'suffix=.bak' or 'dir=<DIR>'
+    #[arg(long, value_parser = backup::parse, num_args = 0..=1, default_missing_value = "numbered")]
+    backup: Option<BackupMode>,
 
-fn get_category<'a>(ext: &str, categories: &'a HashMap<String, Vec<String>>) -> Option<&'a str> {
-    for (cat, exts) in categories {
-        if exts.contains(&ext.to_lowercase()) {
-            return Some(cat);
-        }
-    }
+    /// What to do with files that are open in another process when moving
+    #[arg(long = "in-use", value_enum, default_value_t = InUsePolicy::Defer)]
+    in_use: InUsePolicy,
 
-    None
-}
+    /// How to case destination extensions and the per-extension fallback folders
+    #[arg(long = "normalize-ext-case", value_enum, default_value_t = ExtCase::Keep)]
+    normalize_ext_case: ExtCase,
 
-fn copy_file(source: &str, dest: &str) -> Result<()> {
-    if Path::new(dest).exists() {
-        remove_file(dest)?;
-    }
+    /// Leave files that match no category untouched (same as `uncategorized.mode = "skip"`)
+    #[arg(long = "only-categorized")]
+    only_categorized: bool,
 
-    fs::copy(source, dest)?;
+    /// Template for destination file names, e.g. '{date}_{clean}_{hash8}.{ext}'
+    ///
+    /// Tokens: {date} (EXIF or modification date), {stem}, {clean} (stem without
+    /// '(copy)'/'(2)' markers), {name}, {ext}, {counter}, {category}, {hash}/{hashN},
+    /// and the audio tags {artist}, {album}, {title}, {track} and {year}
+    #[arg(long)]
+    rename: Option<RenameTemplate>,
 
-    Ok(())
-}
+    /// Hash algorithm for {hash} tokens and other checksums
+    #[arg(long, value_enum, default_value_t = HashAlgorithm::Blake3)]
+    hash: HashAlgorithm,
 
-fn send_finished_notif(operation: &str) {
-    if let Err(e) = Notification::new()
-        .summary(&format!("Finished {operation}"))
-        .body(&format!("`dirsort` has finished {operation} the directory"))
-        .icon("vivaldi")
-        .timeout(Timeout::Milliseconds(1000))
-        .show()
-    {
-        LOGGER_INTERFACE.warning(format!("Failed to display notification: {e}").as_str());
-    }
-}
+    /// Move near-duplicate images (resized or recompressed copies) into a review folder
+    #[arg(long = "dedup-perceptual")]
+    dedup_perceptual: bool,
 
-fn load_blacklist(argv: &Cli) -> std::result::Result<HashSet<String>, Box<dyn error::Error>> {
-    let mut blacklist = HashSet::new();
+    /// Sort PDFs into '<category>/<author>/' and EPUBs into 'Books/<author>/<title>.epub' using their metadata
+    #[arg(long = "docs-by-meta")]
+    docs_by_meta: bool,
 
-    if let Some(ref blacklist_str) = argv.blacklist {
-        for ext in blacklist_str.split(',') {
-            let ext = ext.trim().to_lowercase();
+    /// Extract the attachments of '.eml' and mbox files and sort them as files of their own
+    ///
+    /// A whole exported mailbox becomes a file tree: every attachment, including forwarded messages, goes into its own category, and the messages themselves are sorted like any other file, into 'Mail/' with `Mail = ["eml", "mbox"]` in `[categories]`. Attachments named alike, like 'image001.png', follow `--on-conflict`. Undo removes the extracted attachments again.
+    #[arg(long)]
+    extract_attachments: bool,
 
-            if !ext.is_empty() {
-                let ext = if ext.starts_with('.') {
-                    ext.strip_prefix('.').unwrap().to_string()
-                } else {
-                    ext
-                };
+    /// Sort downloads into a subfolder per site they came from, e.g. 'Documents/github.com/', where the browser recorded it
+    ///
+    /// Read from the 'user.xdg.origin.url' attribute on Linux, 'kMDItemWhereFroms' on macOS and the Zone.Identifier stream on Windows. The URL is also written into the --report, without credentials or query string.
+    #[arg(long)]
+    by_download_origin: bool,
 
-                blacklist.insert(ext);
-            }
-        }
-    }
+    /// Keep photo bursts and numbered sequences, like IMG_0001 to IMG_0087 shot in quick succession, together in a subfolder of Images each
+    #[arg(long)]
+    group_bursts: bool,
 
-    if let Some(ref file_path) = argv.blacklist_file {
-        let content = fs::read_to_string(file_path)
-            .map_err(|e| format!("Failed to read blacklist file '{file_path}': {e}"))?;
+    /// Longest pause between two shots of a sequence with --group-bursts, in seconds
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        default_value_t = 2,
+        requires = "group_bursts"
+    )]
+    burst_gap: u64,
 
-        for line in content.lines() {
-            let ext = line.trim().to_lowercase();
-            if !ext.is_empty() && !ext.starts_with('#') {
-                let ext = if ext.starts_with('.') {
-                    ext.strip_prefix('.').unwrap().to_string()
-                } else {
-                    ext
-                };
+    /// Sort photos with GPS data into '<country>/<region>' subfolders of Images, e.g. 'Images/Japan/Tokyo/', using a bundled offline list of places
+    ///
+    /// The nearest place to the photo's EXIF coordinates is looked up in a copy of the [GeoNames](https://www.geonames.org) cities list built into dirsort, so no location ever leaves the machine. Coordinates of 0,0, which cameras write without a fix, count as no GPS data.
+    #[arg(long)]
+    photo_by_place: bool,
 
-                blacklist.insert(ext);
-            }
-        }
-    }
+    /// Subfolder of Images for photos without GPS data with --photo-by-place
+    #[arg(long, default_value = "Unknown place", requires = "photo_by_place")]
+    no_place_folder: String,
 
-    Ok(blacklist)
-}
+    /// Split Videos into resolution and clip-length subfolders using ffprobe (see `[video_tiers]`)
+    #[arg(long = "video-tiers")]
+    video_tiers: bool,
 
-fn is_blacklisted(file_path: &Path, blacklist: &HashSet<String>) -> bool {
-    file_path
-        .extension()
-        .and_then(|ext| ext.to_str())
-        .is_some_and(|ext| blacklist.contains(ext))
-}
+    /// Group Videos into subfolders by the language of their main audio track, e.g. 'Videos/Japanese/', using ffprobe
+    ///
+    /// Subtitles and other sidecars go with their video. Videos without a language tag stay in 'Videos/'.
+    #[arg(long)]
+    by_audio_language: bool,
 
-fn setup_thread_pool(
-    thread_count: Option<usize>,
-) -> std::result::Result<(), Box<dyn error::Error>> {
-    if let Some(count) = thread_count {
-        if count == 0 {
-            return Err("Thread count must be greater than 0".into());
-        }
-        rayon::ThreadPoolBuilder::new()
-            .num_threads(count)
-            .build_global()
-            .map_err(|e| format!("Failed to configure thread pool: {e}"))?;
+    /// Split categories into subfolders by file size, e.g. 'Videos/Large/' (see `[size_tiers]`)
+    #[arg(long = "by-size-tier")]
+    by_size_tier: bool,
 
-        LOGGER_INTERFACE.info(format!("Using {count} threads").as_str());
-    } else {
-        let default_threads = rayon::current_num_threads();
-        LOGGER_INTERFACE.info(format!("Using {default_threads} threads").as_str());
-    }
-    Ok(())
-}
+    /// Add an 'A/' ... 'Z/', '0-9/' or '#/' level under each category based on the file name
+    #[arg(long = "alpha-buckets")]
+    alpha_buckets: bool,
 
-fn collect_files(max_depth: Option<usize>) -> Vec<walkdir::DirEntry> {
-    let mut walker = WalkDir::new(".").follow_links(true);
+    /// Convert images into formats older tools can read while placing them, e.g. 'heic:jpg,webp:png', keeping the originals in an 'Originals/' subfolder
+    ///
+    /// HEIC and HEIF need heif-convert (from libheif) in PATH.
+    #[arg(long, value_name = "FROM:TO", value_delimiter = ',', value_parser = convert::parse)]
+    convert: Vec<Conversion>,
 
-    if let Some(depth) = max_depth {
-        walker = walker.max_depth(depth);
-    }
+    /// Place executables under 'Quarantine/' with their execute permission removed
+    #[arg(long = "quarantine-executables")]
+    quarantine_executables: bool,
 
-    let (entries, dir_count) = walker.into_iter().filter_map(std::result::Result::ok).fold(
-        (Vec::new(), 0),
-        |(mut files, mut dirs), entry| {
-            if entry.file_type().is_dir() {
-                dirs += 1;
-            } else if entry.file_type().is_file() {
-                files.push(entry);
-            }
-            (files, dirs)
-        },
-    );
+    /// Scan each file with clamd before placing it, e.g. 'clamd://localhost' or 'clamd:///run/clamav/clamd.ctl'
+    ///
+    /// Infected files are placed in 'Quarantine/', which is never served.
+    #[arg(long, value_parser = clamav::parse_url)]
+    scan: Option<ClamdAddr>,
 
-    LOGGER_INTERFACE.info(
-        format!(
-            "Scanned {} directories, found {} files",
-            dir_count,
-            entries.len()
-        )
-        .as_str(),
-    );
+    /// Show a full-screen dashboard instead of the progress bar
+    #[arg(long)]
+    tui: bool,
 
-    entries
-}
+    /// How to show progress while sorting
+    #[arg(long, value_enum, default_value_t = ProgressMode::Auto)]
+    progress: ProgressMode,
 
-fn process_file(
-    entry: &walkdir::DirEntry,
-    out_dir: &Path,
-    use_move: bool,
-    blacklist: &HashSet<String>,
-    categories: &HashMap<String, Vec<String>>,
-    errors: &Arc<Mutex<Vec<String>>>,
-    skipped: &Arc<AtomicU64>,
-) {
-    if is_blacklisted(entry.path(), blacklist) {
-        skipped.fetch_add(1, Ordering::Relaxed);
-        return;
-    }
+    /// Write a report of every file's outcome into the output directory, e.g. 'dirsort-report.csv'
+    #[arg(long, value_enum)]
+    report: Option<ReportFormat>,
 
-    let result = || -> std::result::Result<(), Box<dyn error::Error + Send + Sync>> {
-        let file_name = entry
-            .file_name()
-            .to_str()
-            .ok_or("Invalid filename encoding")?;
+    /// Write a checksum file into each category folder, so the sorted files can be verified later without dirsort
+    #[arg(long, value_enum)]
+    emit_checksums: Option<ChecksumFormat>,
 
-        let source_path = entry.path().display().to_string();
+    /// Create PAR2 recovery files in each category folder with this much redundancy, e.g. '10%', using par2 from par2cmdline
+    #[arg(long, value_name = "PERCENT", value_parser = par2::parse_redundancy)]
+    par2: Option<u8>,
 
-        let (target_dir, dest_path) = if let Some(ext) = entry.path().extension() {
-            let ext_str = ext.to_str().ok_or("Invalid extension encoding")?;
-            let category = get_category(ext_str, categories);
-            let subfolder = category.unwrap_or(ext_str);
-            let target_dir = Path::new(out_dir).join(subfolder);
-            let dest_path = target_dir.join(file_name);
-            (target_dir, dest_path)
-        } else {
-            let target_dir = Path::new(out_dir).join("unknown");
-            let dest_path = target_dir.join(file_name);
-            (target_dir, dest_path)
-        };
+    /// Ask y/n/a(lways)/q(uit) before placing files
+    #[arg(long, value_enum, conflicts_with = "tui")]
+    confirm: Option<ConfirmMode>,
 
-        create_dir_all(&target_dir)?;
+    /// Start without asking after the estimate of what the run will do, which is shown when running in a terminal
+    #[arg(short, long)]
+    yes: bool,
 
-        if use_move {
-            move_file(
-                source_path.as_ref(),
-                dest_path.to_str().unwrap().to_string().as_ref(),
-            )?;
-        } else {
-            copy_file(&source_path, dest_path.to_str().unwrap())?;
-        }
+    /// Print what would be placed where, grouped by category, without touching any files
+    #[arg(long = "dry-run")]
+    dry_run: bool,
 
-        Ok(())
-    };
+    /// Write what would be placed where into a CSV file to edit in a spreadsheet, without touching any files, e.g. 'rename-plan.csv'
+    ///
+    /// Each row holds a file's source, old_name, new_name and destination folder. Change the new names or folders, then place the files with `dirsort apply-renames`.
+    #[arg(long, value_name = "FILE")]
+    rename_plan: Option<PathBuf>,
 
-    if let Err(e) = result() {
-        let error_msg = format!("Failed to process '{}': {}", entry.path().display(), e);
-        if let Ok(mut errors_vec) = errors.lock() {
-            if Cli::parse().verbose {
-                errors_vec.push(error_msg);
-            }
-        }
-    }
+    /// List every destination that more than one file would go to, or that already exists, without touching any files
+    #[arg(long)]
+    list_conflicts: bool,
+
+    /// Log which rule, category or detection decided each file's folder
+    #[arg(long)]
+    trace_rules: bool,
+
+    /// Language for messages and help, e.g. 'de' (default: from LC_ALL, LC_MESSAGES or LANG)
+    #[arg(long)]
+    lang: Option<String>,
+
+    #[arg(short, long)]
+    verbose: bool,
+
+    /// How log lines are written; which ones are shown follows RUST_LOG, e.g. 'dirsort=debug' to also log how long each file took
+    #[arg(long, value_enum, default_value_t = LogFormat::Pretty)]
+    log_format: LogFormat,
+
+    #[arg(short, long, hide = true)]
+    gen_docs: bool,
 }
 
-fn get_blacklist(
-    args: &Cli,
-) -> std::result::Result<HashSet<String, RandomState>, Box<dyn error::Error>> {
-    load_blacklist(args)
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum QuarantinePolicy {
+    /// Leave `com.apple.quarantine` as it was on the source file
+    Preserve,
+    /// Remove `com.apple.quarantine` from placed files
+    Strip,
 }
 
-fn get_categories(
-    path: &Option<String>,
-) -> std::result::Result<HashMap<String, Vec<String>>, Box<dyn Error>> {
-    load_categories(path.as_ref())
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum PreserveAttr {
+    /// Extended attributes in the `user.` namespace
+    Xattrs,
+    /// The SELinux security context
+    Context,
 }
 
-#[actix_web::main]
-async fn main() -> std::io::Result<()> {
-    let args = Cli::parse();
+#[derive(Subcommand)]
+enum Command {
+    /// Interactively write a config file, and optionally systemd units that sort folders as they change
+    Init,
+    /// Show how many bytes each category and extension would take up, without sorting
+    Usage {
+        #[arg(long, value_enum, default_value_t = UsageFormat::Chart)]
+        format: UsageFormat,
+    },
+    /// List the largest files with the folder each would be sorted into, without sorting
+    Analyze {
+        /// How many files to list
+        #[arg(long, default_value_t = 20)]
+        top: usize,
+    },
+    /// Read a file from stdin and print the folder it would be sorted into and its destination
+    Classify {
+        /// The file's name; without an extension, the type is guessed from the contents
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Compare two directories: files only one has, files whose contents differ, and how each category's totals change
+    Diff { dir_a: PathBuf, dir_b: PathBuf },
+    /// Wait for a camera card or USB stick and copy its new files into `<category>/<year>/<date>/`, verifying every copy
+    Ingest {
+        /// Where the device is mounted; without it, the next device to be mounted is used
+        device: Option<PathBuf>,
+        /// Eject the device once everything was imported
+        #[arg(long)]
+        eject: bool,
+    },
+    /// Keep running and sort the `[[watch]]` directories from the config on their schedules
+    Daemon,
+    /// Place files as listed in a rename plan written by --rename-plan, with the names and folders edited there
+    ApplyRenames {
+        /// The edited plan, e.g. 'rename-plan.csv'
+        plan: PathBuf,
+    },
+    /// Put back the files of a past run, by default the most recent one
+    Undo {
+        /// The run's id from `dirsort log`, or the start of it
+        #[arg(long, conflicts_with = "since")]
+        run: Option<String>,
+        /// Undo every run since then, e.g. '2 hours ago' or '2024-05-01 14:00'
+        #[arg(long, value_parser = journal::parse_since)]
+        since: Option<DateTime<Local>>,
+    },
+    /// Generate a directory of typical files and dry-run the current options and config against it
+    Simulate {
+        /// How many files to generate
+        #[arg(long, default_value_t = 1000)]
+        files: usize,
+        #[arg(long, value_enum, default_value_t = simulate::Profile::DownloadsMix)]
+        profile: simulate::Profile,
+        /// Seed for the generated names and sizes, the same seed gives the same files
+        #[arg(long, default_value_t = 1)]
+        seed: u64,
+        /// Keep the generated directory instead of deleting it afterwards
+        #[arg(long)]
+        keep: bool,
+    },
+    /// Measure scanning, categorizing and copying with the current config, and suggest thread counts
+    Bench {
+        /// How many files to generate
+        #[arg(long, default_value_t = 500)]
+        files: usize,
+        /// Size of each generated file
+        #[arg(long, default_value = "256KB", value_parser = parse_byte_size)]
+        file_size: ByteSize,
+        /// Print the results as JSON, for tracking them over time
+        #[arg(long)]
+        json: bool,
+    },
+    /// Serve an existing directory as `--serve` does, without sorting anything first
+    Serve {
+        /// The directory to serve; add `--index` before `serve` to regenerate its index first
+        dir: PathBuf,
+        /// Regenerate the index when files change and reload index pages open in a browser
+        #[arg(long)]
+        watch: bool,
+    },
+    /// Generate the HTML index of an existing directory, without sorting anything
+    Index {
+        /// The directory to index
+        dir: PathBuf,
+        /// Also write an `index.json` listing every file with its size and modification time, and each category's totals
+        #[arg(long)]
+        json: bool,
+    },
+    /// Add "Sort this folder with dirsort" to the folder context menu, running dirsort with the options given before `integrate`
+    #[command(group(clap::ArgGroup::new("target").required(true)))]
+    Integrate {
+        /// Add it to Explorer's context menu (Windows)
+        #[arg(long, group = "target")]
+        explorer: bool,
+        /// Install it as a Quick Action in Finder (macOS)
+        #[arg(long, group = "target")]
+        finder: bool,
+        /// Remove it again
+        #[arg(long)]
+        remove: bool,
+    },
+    /// List past runs
+    Log {
+        #[command(subcommand)]
+        action: Option<LogAction>,
+    },
+}
 
-    if args.gen_docs {
-        println!("{}", help_markdown::<Cli>());
-        process::exit(1);
-    }
+#[derive(Subcommand)]
+enum LogAction {
+    /// Show everything recorded about a run
+    Show {
+        /// The run's id, or the start of it
+        id: String,
+    },
+}
 
-    if let Err(e) = setup_thread_pool(args.threads) {
-        LOGGER_INTERFACE.error(format!("Error configuring threads: {e}").as_str());
-        process::exit(1);
-    }
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum ProcessOrder {
+    /// Small files first, so many land quickly
+    SmallestFirst,
+    /// Large files first, to get the long copies done while watching
+    LargestFirst,
+    /// By modification time, oldest first
+    OldestFirst,
+    /// By modification time, newest first
+    NewestFirst,
+    /// Shuffled, to spread the work evenly over folders and disks
+    Random,
+}
 
-    let blacklist = get_blacklist(&args).expect("Failed to fetch blacklist");
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum ConflictStrategy {
+    /// Replace the existing destination file
+    Overwrite,
+    /// Leave the source file where it is
+    Skip,
+    /// Append a counter to the file name, e.g. 'photo (1).jpg'
+    Rename,
+}
 
-    if !blacklist.is_empty() {
-        LOGGER_INTERFACE.info(
-            format!(
-                "Blacklisted extensions: {}",
-                blacklist
-                    .iter()
-                    .map(|s| format!(".{s}"))
-                    .collect::<Vec<_>>()
-                    .join(", ")
-            )
-            .as_str(),
-        );
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum ExtCase {
+    /// Lowercase extensions, e.g. 'jpg/photo.jpg'
+    Lower,
+    /// Uppercase extensions, e.g. 'JPG/photo.JPG'
+    Upper,
+    /// Keep extensions as they are on the source file
+    Keep,
+}
+
+impl ExtCase {
+    fn apply(self, ext: &str) -> String {
+        match self {
+            Self::Lower => ext.to_lowercase(),
+            Self::Upper => ext.to_uppercase(),
+            Self::Keep => ext.to_string(),
+        }
     }
+}
 
-    let entries = collect_files(args.max_depth);
+#[derive(Clone, Copy)]
+struct Owner {
+    uid: Option<u32>,
+    gid: Option<u32>,
+}
 
-    if entries.is_empty() {
-        LOGGER_INTERFACE.warning("No files found to process.");
-        return Ok(());
-    }
+fn parse_mode(mode: &str) -> std::result::Result<u32, String> {
+    u32::from_str_radix(mode.trim_start_matches("0o"), 8)
+        .ok()
+        .filter(|mode| *mode <= 0o7777)
+        .ok_or_else(|| format!("'{mode}' is not a valid octal mode"))
+}
 
-    let progress = Arc::new(Mutex::new(ProgressBar::new(entries.len() as u64)));
-    let out_dir = PathBuf::from(args.output_dir.unwrap_or_else(|| "sorted".to_string()));
-    let errors = Arc::new(Mutex::new(Vec::new()));
-    let skipped = Arc::new(AtomicU64::new(0));
+#[cfg(unix)]
+fn parse_owner(spec: &str) -> std::result::Result<Owner, String> {
+    let (user, group) = spec.split_once(':').unwrap_or((spec, ""));
 
-    if let Err(e) = create_dir_all(&out_dir) {
-        LOGGER_INTERFACE.error(
-            format!(
-                "Failed to create output directory '{}': {}",
-                out_dir.to_str().unwrap(),
-                e
-            )
-            .as_str(),
-        );
-        process::exit(1);
-    }
+    Ok(Owner {
+        uid: (!user.is_empty())
+            .then(|| perms::lookup_uid(user))
+            .transpose()?,
+        gid: (!group.is_empty())
+            .then(|| perms::lookup_gid(group))
+            .transpose()?,
+    })
+}
 
-    let operation = if args.mv { "moving" } else { "copying" };
-    LOGGER_INTERFACE.info(
-        format!(
-            "Starting {} {} files to '{}'...",
-            operation,
-            entries.len(),
-            out_dir.to_str().unwrap()
-        )
-        .as_str(),
-    );
+#[cfg(not(unix))]
+fn parse_owner(_spec: &str) -> std::result::Result<Owner, String> {
+    Err("--chown is only supported on Unix".to_string())
+}
 
-    let category_map = get_categories(&args.config).expect("Failed to fetch categories");
+#[derive(Serialize, Deserialize)]
+struct SorterConfig {
+    /// Extensions by category; subcategories are flattened to keys like
+    /// `Images/RAW`
+    #[serde(deserialize_with = "nested_categories")]
+    categories: HashMap<String, Vec<String>>,
 
-    if !category_map.is_empty() {
-        LOGGER_INTERFACE.info("Loaded categories:");
-        for (cat, exts) in &category_map {
-            LOGGER_INTERFACE.info(format!("  {cat}: {exts:?}").as_str());
-        }
-    }
+    /// Extensions that mean the same as another one, e.g. `jpeg = "jpg"`
+    #[serde(default)]
+    aliases: HashMap<String, String>,
 
-    entries.par_iter().for_each(|entry| {
-        process_file(
-            entry,
-            out_dir.as_ref(),
-            args.mv,
-            &blacklist,
-            &category_map,
-            &errors,
-            &skipped,
-        );
-        progress.lock().unwrap().inc(1);
-    });
+    /// Name of a category's folder on disk; `{category}` is the category and
+    /// `{count}` how many files the folder holds
+    #[serde(default = "default_folder_name")]
+    folder_name: String,
 
-    progress.lock().unwrap().finish();
+    /// Per-category folder names, taking precedence over `folder_name`
+    #[serde(default)]
+    folders: HashMap<String, String>,
 
-    if args.gen_html {
-        if let Err(e) = gen_html_index(out_dir.as_path()) {
-            LOGGER_INTERFACE.error(format!("Failed to generate html index: {e}").as_str());
-        }
-    }
+    /// Conditions on a file's attributes that place it in a folder before
+    /// the categories are consulted
+    #[serde(default)]
+    rules: Vec<rules::Rule>,
 
-    let skipped_count = skipped.load(Ordering::Relaxed);
-    let processed_count = entries.len() as u64 - skipped_count;
+    /// Which rule wins when several match a file
+    #[serde(default)]
+    resolution: Resolution,
 
-    if let Ok(errors_vec) = errors.lock() {
-        if !errors_vec.is_empty() {
-            LOGGER_INTERFACE.error("Errors encountered during processing:");
-            for error in errors_vec.iter() {
-                LOGGER_INTERFACE.error(format!("  {error}").as_str());
-            }
-            LOGGER_INTERFACE
-                .info(format!("Processing completed with {} errors.", errors_vec.len()).as_str());
-        }
-    }
+    /// Caps on how much a category's folder may hold
+    #[serde(default)]
+    quotas: HashMap<String, quotas::Quota>,
 
-    LOGGER_INTERFACE.info("Summary:");
-    LOGGER_INTERFACE.info(format!("  Files processed: {processed_count}").as_str());
-    if skipped_count > 0 {
-        LOGGER_INTERFACE.info(format!("  Files skipped (blacklisted): {skipped_count}").as_str());
-    }
+    /// Categories processed before others, higher first; unlisted ones are 0
+    #[serde(default)]
+    priorities: HashMap<String, i32>,
 
-    LOGGER_INTERFACE.info(format!("  Total files found: {}", entries.len()).as_str());
+    /// Folder for files without an extension, and for every uncategorized
+    /// file in `single-folder` mode
+    #[serde(default = "default_fallback")]
+    fallback: String,
 
-    if args.serve {
-        LOGGER_INTERFACE.info("Serving at 'http://127.0.0.1:6969'");
-        return HttpServer::new(|| {
-            App::new().service(
-                Files::new("/", Cli::parse().output_dir.unwrap_or("sorted".to_string()))
-                    .show_files_listing()
-                    .index_file("index.html"),
+    #[serde(default)]
+    uncategorized: UncategorizedConfig,
+
+    #[serde(default)]
+    sidecars: Vec<SidecarRule>,
+
+    live_photos: Option<LivePhotoConfig>,
+
+    raw_pairs: Option<RawPairConfig>,
+
+    screenshots: Option<ScreenshotConfig>,
+
+    executables: Option<ExecutableConfig>,
+
+    code: Option<CodeConfig>,
+
+    incomplete: Option<IncompleteConfig>,
+
+    projects: Option<ProjectConfig>,
+
+    seeding: Option<SeedingConfig>,
+
+    #[serde(default)]
+    directories: Vec<DirectoryRule>,
+
+    #[serde(default)]
+    video_tiers: VideoTierConfig,
+
+    #[serde(default)]
+    size_tiers: SizeTierConfig,
+
+    /// Output roots that categories always go into, overriding the free space
+    /// based choice between several `-o` roots
+    #[serde(default)]
+    destinations: HashMap<String, PathBuf>,
+
+    /// Per-category file name templates, taking precedence over `--rename`
+    #[serde(default)]
+    rename: HashMap<String, RenameTemplate>,
+
+    /// Directories `dirsort daemon` sorts on a schedule
+    #[serde(default)]
+    watch: Vec<daemon::Watch>,
+
+    /// Icons in the HTML index
+    #[serde(default)]
+    icons: icons::IconConfig,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+enum Resolution {
+    /// The first matching rule in the order they are written
+    #[default]
+    FirstMatch,
+    /// The matching rule with the most conditions, and the deepest category
+    MostSpecific,
+}
+
+/// A `[categories]` entry: a list of extensions, or a table with its own
+/// `extensions` and subcategories.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CategoryDef {
+    Extensions(Vec<String>),
+    Nested {
+        #[serde(default)]
+        extensions: Vec<String>,
+        #[serde(flatten)]
+        children: HashMap<String, CategoryDef>,
+    },
+}
+
+fn nested_categories<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<HashMap<String, Vec<String>>, D::Error> {
+    fn flatten(
+        prefix: Option<&str>,
+        defs: HashMap<String, CategoryDef>,
+        categories: &mut HashMap<String, Vec<String>>,
+    ) {
+        for (name, def) in defs {
+            let name = prefix.map_or_else(|| name.clone(), |prefix| format!("{prefix}/{name}"));
+            match def {
+                CategoryDef::Extensions(extensions) => {
+                    categories.insert(name, extensions);
+                }
+                CategoryDef::Nested {
+                    extensions,
+                    children,
+                } => {
+                    flatten(Some(&name), children, categories);
+                    categories.insert(name, extensions);
+                }
+            }
+        }
+    }
+
+    let mut categories = HashMap::new();
+    flatten(None, HashMap::deserialize(deserializer)?, &mut categories);
+    Ok(categories)
+}
+
+impl SorterConfig {
+    /// The extension `ext` is an alias of, or `ext` itself.
+    fn canonical_ext<'a>(&'a self, ext: &'a str) -> &'a str {
+        self.aliases
+            .get(&ext.to_lowercase())
+            .map_or(ext, String::as_str)
+    }
+}
+
+/// Relocates whole directories whose name matches `pattern`, e.g. every
+/// `Season *` folder into `Videos/`.
+#[derive(Serialize, Deserialize)]
+struct DirectoryRule {
+    /// Glob matched case-insensitively against the directory name
+    pattern: String,
+    /// Folder the matching directories are placed in
+    category: String,
+}
+
+/// Thresholds for `--video-tiers`. Short videos go into `clips_folder`,
+/// everything else into the first tier whose `min_height` it reaches.
+#[derive(Serialize, Deserialize)]
+struct VideoTierConfig {
+    #[serde(default = "default_clip_seconds")]
+    clip_seconds: f64,
+    #[serde(default = "default_clips_folder")]
+    clips_folder: String,
+    #[serde(default = "default_video_tiers")]
+    tiers: Vec<VideoTier>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct VideoTier {
+    name: String,
+    min_height: u32,
+}
+
+impl Default for VideoTierConfig {
+    fn default() -> Self {
+        Self {
+            clip_seconds: default_clip_seconds(),
+            clips_folder: default_clips_folder(),
+            tiers: default_video_tiers(),
+        }
+    }
+}
+
+fn default_clip_seconds() -> f64 {
+    120.0
+}
+
+fn default_clips_folder() -> String {
+    "Clips".to_string()
+}
+
+fn default_video_tiers() -> Vec<VideoTier> {
+    [
+        ("4K", 2160),
+        ("1440p", 1440),
+        ("1080p", 1080),
+        ("720p", 720),
+    ]
+    .into_iter()
+    .map(|(name, min_height)| VideoTier {
+        name: name.to_string(),
+        min_height,
+    })
+    .collect()
+}
+
+impl VideoTierConfig {
+    fn subfolder(&self, path: &Path) -> Option<&str> {
+        let info = video::probe(path)?;
+
+        if info
+            .duration
+            .is_some_and(|duration| duration < self.clip_seconds)
+        {
+            return Some(&self.clips_folder);
+        }
+
+        self.tiers
+            .iter()
+            .filter(|tier| info.height >= tier.min_height)
+            .max_by_key(|tier| tier.min_height)
+            .map(|tier| tier.name.as_str())
+    }
+}
+
+/// Buckets for `--by-size-tier`. Files go into the largest tier whose
+/// `min_size` they reach; smaller files stay in the category folder.
+#[derive(Serialize, Deserialize)]
+struct SizeTierConfig {
+    /// Categories that are split, or every category when empty
+    #[serde(default)]
+    categories: Vec<String>,
+    #[serde(default = "default_size_tiers")]
+    tiers: Vec<SizeTier>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SizeTier {
+    name: String,
+    min_size: ByteSize,
+}
+
+/// A size written as e.g. `"10MB"` or `"1.5 GiB"`.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+struct ByteSize(u64);
+
+impl TryFrom<String> for ByteSize {
+    type Error = String;
+
+    fn try_from(size: String) -> std::result::Result<Self, Self::Error> {
+        let size = size.trim();
+        let split = size
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(size.len());
+        let (number, unit) = size.split_at(split);
+        let number: f64 = number
+            .parse()
+            .map_err(|_| format!("invalid size '{size}'"))?;
+        let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+            "" | "B" => 1,
+            "KB" => 1_000,
+            "MB" => 1_000_000,
+            "GB" => 1_000_000_000,
+            "TB" => 1_000_000_000_000,
+            "KIB" => 1 << 10,
+            "MIB" => 1 << 20,
+            "GIB" => 1 << 30,
+            "TIB" => 1 << 40,
+            _ => return Err(format!("unknown size unit in '{size}'")),
+        };
+
+        Ok(Self((number * multiplier as f64) as u64))
+    }
+}
+
+fn parse_byte_size(size: &str) -> std::result::Result<ByteSize, String> {
+    ByteSize::try_from(size.to_string())
+}
+
+impl From<ByteSize> for String {
+    fn from(size: ByteSize) -> Self {
+        format!("{}B", size.0)
+    }
+}
+
+impl Default for SizeTierConfig {
+    fn default() -> Self {
+        Self {
+            categories: Vec::new(),
+            tiers: default_size_tiers(),
+        }
+    }
+}
+
+fn default_size_tiers() -> Vec<SizeTier> {
+    [("Large", 10_000_000), ("Huge", 1_000_000_000)]
+        .into_iter()
+        .map(|(name, min_size)| SizeTier {
+            name: name.to_string(),
+            min_size: ByteSize(min_size),
+        })
+        .collect()
+}
+
+impl SizeTierConfig {
+    fn subfolder(&self, category: &str, size: u64) -> Option<&str> {
+        if !self.categories.is_empty() && !self.categories.iter().any(|c| c == category) {
+            return None;
+        }
+
+        self.tiers
+            .iter()
+            .filter(|tier| size >= tier.min_size.0)
+            .max_by_key(|tier| tier.min_size.0)
+            .map(|tier| tier.name.as_str())
+    }
+}
+
+/// Routes screenshots into their own category ahead of `Images`. They are
+/// recognized by file name, by the text chunks screenshot tools leave in PNGs,
+/// and by PNG dimensions that match a common display.
+#[derive(Serialize, Deserialize)]
+struct ScreenshotConfig {
+    #[serde(default = "default_screenshots_folder")]
+    folder: String,
+    /// Globs matched case-insensitively against the file name
+    #[serde(default)]
+    patterns: Vec<String>,
+    #[serde(default)]
+    check_metadata: bool,
+    #[serde(default)]
+    check_dimensions: bool,
+    /// Extensions that are considered at all
+    #[serde(default = "default_screenshot_extensions")]
+    extensions: Vec<String>,
+}
+
+fn default_screenshots_folder() -> String {
+    "Screenshots".to_string()
+}
+
+fn default_screenshot_extensions() -> Vec<String> {
+    ["png", "jpg", "jpeg", "webp", "heic", "mov", "mp4"]
+        .map(str::to_string)
+        .to_vec()
+}
+
+/// Collects programs and installers, by extension and (with `detect_magic`)
+/// by PE / ELF / Mach-O headers, so renamed or extensionless binaries are
+/// caught too.
+#[derive(Serialize, Deserialize)]
+struct ExecutableConfig {
+    #[serde(default = "default_executables_folder")]
+    folder: String,
+    #[serde(default)]
+    extensions: Vec<String>,
+    #[serde(default)]
+    detect_magic: bool,
+}
+
+fn default_executables_folder() -> String {
+    "Executables".to_string()
+}
+
+/// Sorts source files into `<folder>/<language>/` by extension or shebang,
+/// after the regular categories have been tried.
+#[derive(Serialize, Deserialize)]
+struct CodeConfig {
+    #[serde(default = "default_code_folder")]
+    folder: String,
+}
+
+fn default_code_folder() -> String {
+    "Code".to_string()
+}
+
+/// Holds back files that look like downloads in progress: partial-download
+/// extensions, and files modified within `settle_seconds` that are still
+/// growing.
+#[derive(Serialize, Deserialize)]
+struct IncompleteConfig {
+    #[serde(default)]
+    extensions: Vec<String>,
+    #[serde(default = "default_settle_seconds")]
+    settle_seconds: u64,
+    #[serde(default)]
+    action: IncompleteAction,
+    /// Folder that incomplete files are placed in when `action = "pending"`
+    #[serde(default = "default_pending_folder")]
+    folder: String,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+enum IncompleteAction {
+    /// Leave incomplete files where they are
+    #[default]
+    Skip,
+    /// Place them in `folder` without renaming or splitting them
+    Pending,
+}
+
+/// Files torrent clients are seeding, which must stay where the client
+/// expects them.
+#[derive(Serialize, Deserialize)]
+struct SeedingConfig {
+    /// Folders with the `.torrent` files of active torrents, like the ones
+    /// clients keep their state in
+    #[serde(default)]
+    torrents: Vec<String>,
+    /// Folders whose files are all being seeded
+    #[serde(default)]
+    directories: Vec<String>,
+    #[serde(default)]
+    action: SeedingAction,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+enum SeedingAction {
+    /// Leave seeded files where they are
+    #[default]
+    Skip,
+    /// Hardlink them into place instead of moving or copying them, so the
+    /// client still finds them; copied where the output is on another disk
+    Hardlink,
+}
+
+impl SeedingConfig {
+    fn load(&self) -> Seeding {
+        let expand = |dirs: &[String]| -> Vec<PathBuf> {
+            match init::home_dir() {
+                Some(home) => dirs
+                    .iter()
+                    .map(|dir| init::expand_home(dir, &home))
+                    .collect(),
+                None => dirs.iter().map(PathBuf::from).collect(),
+            }
+        };
+        Seeding::load(&expand(&self.torrents), &expand(&self.directories))
+    }
+}
+
+fn default_settle_seconds() -> u64 {
+    5
+}
+
+fn default_pending_folder() -> String {
+    "Pending".to_string()
+}
+
+impl IncompleteConfig {
+    fn matches(&self, path: &Path, ext: Option<&str>) -> bool {
+        ext.is_some_and(|ext| self.extensions.contains(&ext.to_lowercase()))
+            || (self.settle_seconds > 0
+                && downloads::still_growing(path, Duration::from_secs(self.settle_seconds)))
+    }
+}
+
+/// Recognizes directories that only make sense as a whole (source trees,
+/// `.app` bundles, ...) so their contents aren't scattered by extension.
+#[derive(Serialize, Deserialize)]
+struct ProjectConfig {
+    /// Files or directories whose presence marks their parent as a project
+    #[serde(default)]
+    markers: Vec<String>,
+    /// Directory extensions that mark a bundle, e.g. `app` for `Foo.app/`
+    #[serde(default)]
+    bundles: Vec<String>,
+    #[serde(default)]
+    action: ProjectAction,
+    /// Folder that projects are placed in when `action = "move"`
+    #[serde(default = "default_projects_folder")]
+    folder: String,
+}
+
+fn default_projects_folder() -> String {
+    "Projects".to_string()
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+enum ProjectAction {
+    /// Leave projects where they are
+    #[default]
+    Skip,
+    /// Move (or copy) each project as a single unit
+    Move,
+}
+
+impl ProjectConfig {
+    fn matches(&self, dir: &Path) -> bool {
+        let is_bundle = dir
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| {
+                self.bundles
+                    .iter()
+                    .any(|bundle| bundle.eq_ignore_ascii_case(ext))
+            });
+
+        is_bundle || self.markers.iter().any(|marker| dir.join(marker).exists())
+    }
+}
+
+/// Keeps companion files next to the file they belong to, e.g. `movie.srt`
+/// goes wherever `movie.mkv` goes instead of into its own category.
+#[derive(Serialize, Deserialize)]
+struct SidecarRule {
+    /// Extensions of the companion files
+    extensions: Vec<String>,
+    /// Extensions of the files they follow
+    follows: Vec<String>,
+    /// Companion files larger than this stay in their own category
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_size: Option<ByteSize>,
+}
+
+/// Keeps the video half of Live Photos (`IMG_1234.HEIC` + `IMG_1234.MOV`) and
+/// of motion photos exported as two files with the still, as a built-in
+/// `[[sidecars]]` rule. Only short clips are paired, so a movie next to its
+/// poster image still goes into Videos.
+#[derive(Serialize, Deserialize)]
+struct LivePhotoConfig {
+    #[serde(default = "default_live_photo_stills")]
+    stills: Vec<String>,
+    #[serde(default = "default_live_photo_videos")]
+    videos: Vec<String>,
+    #[serde(default = "default_live_photo_max_video_size")]
+    max_video_size: ByteSize,
+}
+
+/// What happens to a RAW file and the JPEG the camera saved alongside it,
+/// e.g. `DSC_0042.NEF` and `DSC_0042.JPG`.
+#[derive(Serialize, Deserialize)]
+struct RawPairConfig {
+    #[serde(default)]
+    policy: RawPairPolicy,
+    #[serde(default = "default_raw_extensions")]
+    raw: Vec<String>,
+    #[serde(default = "default_raw_jpeg_extensions")]
+    jpeg: Vec<String>,
+    /// Subfolder of the RAW file's folder for its JPEG with `prefer-raw`
+    #[serde(default = "default_previews_folder")]
+    previews_folder: String,
+}
+
+impl RawPairConfig {
+    /// Whether a file routed by `routing_ext` instead of its own `ext` is
+    /// the JPEG preview of a RAW file under `prefer-raw`.
+    fn is_preview(&self, ext: &str, routing_ext: &str) -> bool {
+        self.policy == RawPairPolicy::PreferRaw
+            && self.jpeg.contains(&ext.to_lowercase())
+            && self.raw.contains(&routing_ext.to_lowercase())
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+enum RawPairPolicy {
+    /// The RAW file goes wherever its JPEG goes
+    #[default]
+    Together,
+    /// The JPEG goes into a `Previews` subfolder of wherever its RAW file goes
+    PreferRaw,
+    /// Each goes into its own category, as if they weren't a pair
+    Split,
+}
+
+fn default_raw_extensions() -> Vec<String> {
+    [
+        "raw", "dng", "cr2", "cr3", "nef", "arw", "orf", "rw2", "raf",
+    ]
+    .map(str::to_string)
+    .to_vec()
+}
+
+fn default_raw_jpeg_extensions() -> Vec<String> {
+    ["jpg", "jpeg"].map(str::to_string).to_vec()
+}
+
+fn default_previews_folder() -> String {
+    "Previews".to_string()
+}
+
+fn default_live_photo_stills() -> Vec<String> {
+    ["heic", "heif", "jpg", "jpeg"].map(str::to_string).to_vec()
+}
+
+fn default_live_photo_videos() -> Vec<String> {
+    ["mov", "mp4"].map(str::to_string).to_vec()
+}
+
+fn default_live_photo_max_video_size() -> ByteSize {
+    ByteSize(20 * 1024 * 1024)
+}
+
+fn default_folder_name() -> String {
+    "{category}".to_string()
+}
+
+fn default_fallback() -> String {
+    "unknown".to_string()
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct UncategorizedConfig {
+    #[serde(default)]
+    mode: UncategorizedMode,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+enum UncategorizedMode {
+    /// One folder per extension, e.g. `xyz/`
+    #[default]
+    ByExtension,
+    /// Everything goes into the fallback folder
+    SingleFolder,
+    /// Leave uncategorized files where they are
+    Skip,
+}
+
+/// Renames `from` to `to`, falling back to copying and deleting when they are
+/// on different filesystems. Returns whether the rename worked.
+fn move_file(from: &Path, to: &Path) -> Result<bool> {
+    readonly::check(from)?;
+    match rename(from, to) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            if from.is_dir() {
+                copy_dir(from, to)?;
+                fs::remove_dir_all(from)?;
+            } else {
+                copy_file(from, to)?;
+                remove_file(from)?;
+            }
+            Ok(false)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+fn load_config(path: Option<&String>) -> std::result::Result<SorterConfig, DirsortError> {
+    let content = path.map_or_else(
+        || DEFAULT_CATEGORY_CONFIG.to_string(),
+        |path_str| {
+            fs::read_to_string(path_str).unwrap_or_else(|e| {
+                warn!("Failed to read config file '{path_str}': {e}\nFalling back to default.");
+                DEFAULT_CATEGORY_CONFIG.to_string()
+            })
+        },
+    );
+
+    let mut config: SorterConfig = toml::from_str(&content).map_err(|e| {
+        DirsortError::config(
+            format!("Invalid config '{}'", path.map_or("(default)", |path| path)),
+            e,
+        )
+    })?;
+    config.categories = config
+        .categories
+        .into_iter()
+        .map(|(k, v)| {
+            let cleaned_exts = v
+                .into_iter()
+                .map(|ext| ext.trim_start_matches('.').to_lowercase())
+                .collect();
+            (k, cleaned_exts)
+        })
+        .collect();
+
+    config.aliases = config
+        .aliases
+        .into_iter()
+        .map(|(alias, ext)| {
+            (
+                alias.trim_start_matches('.').to_lowercase(),
+                ext.trim_start_matches('.').to_lowercase(),
             )
         })
-        .bind("127.0.0.1:6969")?
-        .run()
-        .await;
+        .collect();
+    // Files are matched by their canonical extension, so a category listing
+    // only an alias still claims them.
+    for exts in config.categories.values_mut() {
+        let canonical = exts
+            .iter()
+            .filter_map(|ext| config.aliases.get(ext))
+            .filter(|ext| !exts.contains(ext))
+            .cloned()
+            .collect::<Vec<_>>();
+        exts.extend(canonical);
+    }
+    // Subcategories take their extensions away from their parents.
+    let claimed = config
+        .categories
+        .iter()
+        .filter(|(name, _)| name.contains('/'))
+        .map(|(name, exts)| (name.clone(), exts.clone()))
+        .collect::<Vec<_>>();
+    for (parent, exts) in &mut config.categories {
+        let prefix = format!("{parent}/");
+        exts.retain(|ext| {
+            !claimed
+                .iter()
+                .any(|(child, child_exts)| child.starts_with(&prefix) && child_exts.contains(ext))
+        });
+    }
+
+    if let Some(incomplete) = &mut config.incomplete {
+        for ext in &mut incomplete.extensions {
+            *ext = ext.trim_start_matches('.').to_lowercase();
+        }
+    }
+
+    if let Some(live_photos) = &config.live_photos {
+        config.sidecars.push(SidecarRule {
+            extensions: live_photos.videos.clone(),
+            follows: live_photos.stills.clone(),
+            max_size: Some(live_photos.max_video_size),
+        });
+    }
+
+    if let Some(raw_pairs) = &mut config.raw_pairs {
+        for ext in raw_pairs.raw.iter_mut().chain(raw_pairs.jpeg.iter_mut()) {
+            *ext = ext.trim_start_matches('.').to_lowercase();
+        }
+        let pairing = match raw_pairs.policy {
+            RawPairPolicy::Together => Some((&raw_pairs.raw, &raw_pairs.jpeg)),
+            RawPairPolicy::PreferRaw => Some((&raw_pairs.jpeg, &raw_pairs.raw)),
+            RawPairPolicy::Split => None,
+        };
+        if let Some((extensions, follows)) = pairing {
+            config.sidecars.push(SidecarRule {
+                extensions: extensions.clone(),
+                follows: follows.clone(),
+                max_size: None,
+            });
+        }
+    }
+
+    for rule in &mut config.sidecars {
+        for ext in rule.extensions.iter_mut().chain(rule.follows.iter_mut()) {
+            *ext = ext.trim_start_matches('.').to_lowercase();
+        }
+    }
+
+    if let Some(index) = config
+        .rules
+        .iter()
+        .position(|rule| rule.when.is_none() && rule.keywords.is_empty())
+    {
+        return Err(DirsortError::config(
+            format!("Invalid rule #{}", index + 1),
+            "it needs `when`, `keywords` or both",
+        ));
+    }
+
+    // Quotas apply to the folder a file's top-level category is placed in.
+    let top_level: HashSet<&str> = config
+        .categories
+        .keys()
+        .filter_map(|category| category.split('/').next())
+        .chain([config.fallback.as_str()])
+        .collect();
+    if let Some(unknown) = config
+        .quotas
+        .keys()
+        .find(|category| !top_level.contains(category.as_str()))
+    {
+        return Err(DirsortError::config(
+            format!("Invalid quota for '{unknown}'"),
+            "quotas apply to top-level categories only",
+        ));
+    }
+
+    if let Some(nested) = config
+        .folders
+        .keys()
+        .find(|category| category.contains('/'))
+    {
+        return Err(DirsortError::config(
+            format!("Invalid folder name for '{nested}'"),
+            "only top-level categories can be renamed in `[folders]`",
+        ));
+    }
+
+    Ok(config)
+}
+
+fn get_category<'a>(ext: &str, categories: &'a HashMap<String, Vec<String>>) -> Option<&'a str> {
+    for (cat, exts) in categories {
+        if exts.contains(&ext.to_lowercase()) {
+            return Some(cat);
+        }
+    }
+
+    None
+}
+
+/// The category the config sorts JPEGs into, `Images` by default, which the
+/// photo options work within whatever it is called.
+fn photos_category(config: &SorterConfig) -> Option<&str> {
+    get_category("jpg", &config.categories)
+}
+
+/// The category the config sorts MP4s into, `Videos` by default.
+fn videos_category(config: &SorterConfig) -> Option<&str> {
+    get_category("mp4", &config.categories)
+}
+
+/// Whether `category` is `parent` or one of its subcategories.
+fn in_category(category: Option<&str>, parent: Option<&str>) -> bool {
+    match (category, parent) {
+        (Some(category), Some(parent)) => category
+            .strip_prefix(parent)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/')),
+        _ => false,
+    }
+}
+
+/// The top-level folder the file at `path` would be sorted into, for commands
+/// that only look at the files.
+fn planned_folder(
+    path: &Path,
+    args: &Cli,
+    config: &SorterConfig,
+    screenshot_patterns: &[glob::Pattern],
+) -> String {
+    let ext = path.extension().and_then(|ext| ext.to_str());
+    let category = categorize(path, ext, config, screenshot_patterns);
+
+    match (category, ext, config.uncategorized.mode) {
+        (Some(category), _, _) => category,
+        (None, _, UncategorizedMode::Skip) => LEFT_IN_PLACE.to_string(),
+        (None, Some(ext), UncategorizedMode::ByExtension) => {
+            args.normalize_ext_case.apply(config.canonical_ext(ext))
+        }
+        (None, _, _) => config.fallback.clone(),
+    }
+}
+
+/// Works out what the run is about to do from the scan, for the estimate
+/// shown before it starts: where files go, and how long copying them takes
+/// at the speed a short probe copy reaches.
+fn estimate_run(
+    entries: &[walkdir::DirEntry],
+    blacklist: &HashSet<String>,
+    args: &Cli,
+    config: &SorterConfig,
+    screenshot_patterns: &[glob::Pattern],
+    out_dir: &Path,
+) -> Estimate {
+    let mut estimate = Estimate::default();
+    let mut claimed = HashSet::new();
+    let mut sources = Vec::new();
+    for entry in entries
+        .iter()
+        .filter(|entry| !is_blacklisted(entry.path(), blacklist))
+    {
+        let folder = planned_folder(entry.path(), args, config, screenshot_patterns);
+        if folder == LEFT_IN_PLACE {
+            continue;
+        }
+        let size = entry.metadata().map_or(0, |metadata| metadata.len());
+        let top_level = folder.split('/').next().unwrap_or(&folder);
+        let root = config
+            .destinations
+            .get(top_level)
+            .map_or(out_dir, PathBuf::as_path);
+        let name = entry.file_name().to_string_lossy();
+        estimate.add(&folder, root, &name, size, &mut claimed);
+        sources.push((entry.path(), size));
+    }
+
+    let probe_dir = estimate::existing_ancestor(out_dir);
+    let total = sources.iter().map(|(_, size)| size).sum();
+    estimate.copy_bytes = match args.mv {
+        true => volumes::split_by_volume(sources.iter().map(|(path, _)| *path), probe_dir)
+            .map_or(total, |(_, other)| other),
+        false => total,
+    };
+    if estimate.copy_bytes > 0
+        && let Some((sample, _)) = sources.iter().max_by_key(|(_, size)| *size)
+    {
+        estimate.rate = estimate::probe(sample, probe_dir);
+    }
+    estimate
+}
+
+/// Picks the category for a file: `[[rules]]` first, then screenshots, then the configured
+/// categories by extension, then executables, then the built-in `Code` family.
+fn categorize(
+    path: &Path,
+    ext: Option<&str>,
+    config: &SorterConfig,
+    screenshot_patterns: &[glob::Pattern],
+) -> Option<String> {
+    categorize_traced(path, ext, config, screenshot_patterns).map(|(category, _)| category)
+}
+
+/// `categorize`, along with what decided the category, for `--trace-rules`.
+fn categorize_traced(
+    path: &Path,
+    ext: Option<&str>,
+    config: &SorterConfig,
+    screenshot_patterns: &[glob::Pattern],
+) -> Option<(String, String)> {
+    if let Some((index, rule)) = rules::winner(&config.rules, path, ext, config.resolution) {
+        return Some((rule.folder(path), format!("rule #{} `{rule}`", index + 1)));
+    }
+
+    if let (Some(screenshots), Some(ext)) = (&config.screenshots, ext)
+        && screenshots.extensions.contains(&ext.to_lowercase())
+        && screenshots::is_screenshot(
+            path,
+            screenshot_patterns,
+            screenshots.check_metadata,
+            screenshots.check_dimensions,
+        )
+    {
+        return Some((
+            screenshots.folder.clone(),
+            "screenshot detection".to_string(),
+        ));
+    }
+
+    ext.and_then(|ext| {
+        let ext = config.canonical_ext(ext);
+        let category = match config.resolution {
+            Resolution::FirstMatch => get_category(ext, &config.categories),
+            // The deepest of the categories listing the extension.
+            Resolution::MostSpecific => config
+                .categories
+                .iter()
+                .filter(|(_, exts)| exts.contains(&ext.to_lowercase()))
+                .map(|(category, _)| category.as_str())
+                .max_by_key(|category| category.matches('/').count()),
+        }?;
+        Some((category.to_string(), format!("category extension '{ext}'")))
+    })
+    .or_else(|| {
+        let executables = config.executables.as_ref()?;
+        let by_ext = ext.is_some_and(|ext| executables.extensions.contains(&ext.to_lowercase()));
+        if by_ext {
+            Some((
+                executables.folder.clone(),
+                "executable extension".to_string(),
+            ))
+        } else {
+            (executables.detect_magic && executables::is_executable(path))
+                .then(|| (executables.folder.clone(), "executable header".to_string()))
+        }
+    })
+    .or_else(|| {
+        let code = config.code.as_ref()?;
+        code::language(path, ext).map(|language| {
+            (
+                format!("{}/{language}", code.folder),
+                "source code detection".to_string(),
+            )
+        })
+    })
+}
+
+/// Refines where a PDF or EPUB goes based on its embedded metadata, keeping
+/// the plain placement when there is none.
+fn document_placement(
+    path: &Path,
+    ext: &str,
+    subfolder: String,
+    dest_name: String,
+) -> (String, String) {
+    match ext.to_lowercase().as_str() {
+        "pdf" => match documents::pdf_meta(path).and_then(|meta| meta.author) {
+            Some(author) => (format!("{subfolder}/{}", sanitize(&author)), dest_name),
+            None => (subfolder, dest_name),
+        },
+        "epub" => match documents::epub_meta(path) {
+            Some(meta) => {
+                let author = meta
+                    .author
+                    .map_or_else(|| "Unknown Author".to_string(), |a| sanitize(&a));
+                let name = meta
+                    .title
+                    .map(|title| format!("{}.{ext}", sanitize(&title)))
+                    .unwrap_or(dest_name);
+                (format!("Books/{author}"), name)
+            }
+            None => ("Books".to_string(), dest_name),
+        },
+        _ => (subfolder, dest_name),
+    }
+}
+
+/// Copies `source` next to `dest` under a temporary name first and renames it
+/// into place once it is complete, so an interrupted run never leaves a
+/// truncated file that looks sorted.
+fn copy_file(source: &Path, dest: &Path) -> Result<()> {
+    let mut temp = dest.as_os_str().to_owned();
+    temp.push(TEMP_SUFFIX);
+    let temp = PathBuf::from(temp);
+
+    let copied = fs::copy(source, &temp)
+        .and_then(|_| fs::OpenOptions::new().write(true).open(&temp)?.sync_all())
+        .and_then(|()| rename(&temp, dest));
+
+    if copied.is_err() {
+        let _ = remove_file(&temp);
+    }
+
+    copied
+}
+
+/// Removes temporary files left in `out_dir` by copies that were interrupted.
+fn remove_stale_temp_files(out_dir: &Path) -> usize {
+    WalkDir::new(out_dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry.file_type().is_file()
+                && entry.file_name().to_string_lossy().ends_with(TEMP_SUFFIX)
+        })
+        .filter(|entry| remove_file(entry.path()).is_ok())
+        .count()
+}
+
+fn send_finished_notif(operation: &str) {
+    if let Err(e) = Notification::new()
+        .summary(&t!("notify-summary", operation = operation))
+        .body(&t!("notify-body", operation = operation))
+        .icon("vivaldi")
+        .timeout(Timeout::Milliseconds(1000))
+        .show()
+    {
+        warn!("Failed to display notification: {e}");
+    }
+}
+
+fn load_blacklist(argv: &Cli) -> std::result::Result<HashSet<String>, DirsortError> {
+    let mut blacklist = HashSet::new();
+
+    if let Some(ref blacklist_str) = argv.blacklist {
+        for ext in blacklist_str.split(',') {
+            let ext = ext.trim().to_lowercase();
+
+            if !ext.is_empty() {
+                let ext = if ext.starts_with('.') {
+                    ext.strip_prefix('.').unwrap().to_string()
+                } else {
+                    ext
+                };
+
+                blacklist.insert(ext);
+            }
+        }
+    }
+
+    if let Some(ref file_path) = argv.blacklist_file {
+        let content = fs::read_to_string(file_path).map_err(|e| {
+            DirsortError::config(format!("Failed to read blacklist file '{file_path}'"), e)
+        })?;
+
+        for line in content.lines() {
+            let ext = line.trim().to_lowercase();
+            if !ext.is_empty() && !ext.starts_with('#') {
+                let ext = if ext.starts_with('.') {
+                    ext.strip_prefix('.').unwrap().to_string()
+                } else {
+                    ext
+                };
+
+                blacklist.insert(ext);
+            }
+        }
+    }
+
+    Ok(blacklist)
+}
+
+fn is_blacklisted(file_path: &Path, blacklist: &HashSet<String>) -> bool {
+    file_path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| blacklist.contains(ext))
+}
+
+fn setup_thread_pool(thread_count: Option<usize>) -> std::result::Result<(), DirsortError> {
+    if let Some(count) = thread_count {
+        if count == 0 {
+            return Err(DirsortError::config(
+                "Invalid --threads",
+                "thread count must be greater than 0",
+            ));
+        }
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(count)
+            .build_global()
+            .map_err(|e| DirsortError::config("Failed to configure thread pool", e))?;
+
+        info!("{}", t!("using-threads", count = count));
+    } else {
+        let default_threads = rayon::current_num_threads();
+        info!("{}", t!("using-threads", count = default_threads));
+    }
+    Ok(())
+}
+
+/// A directory that is handled as a unit instead of being descended into.
+struct DirPlacement {
+    entry: walkdir::DirEntry,
+    /// Folder to place it in, or `None` to leave it where it is
+    folder: Option<String>,
+}
+
+/// Walks the source directories, returning the files to sort and the
+/// directories that matched a directory rule or were recognized as projects.
+fn collect_files(
+    sources: &[PathBuf],
+    max_depth: Option<usize>,
+    config: &SorterConfig,
+    progress: &Progress,
+) -> std::result::Result<(Vec<walkdir::DirEntry>, Vec<DirPlacement>), DirsortError> {
+    let dir_rules = config
+        .directories
+        .iter()
+        .map(|rule| {
+            glob::Pattern::new(&rule.pattern)
+                .map(|pattern| (pattern, rule.category.as_str()))
+                .map_err(|e| {
+                    DirsortError::config(format!("Invalid directory pattern '{}'", rule.pattern), e)
+                })
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let match_options = glob::MatchOptions {
+        case_sensitive: false,
+        ..Default::default()
+    };
+
+    let mut entries = Vec::new();
+    let mut dirs = Vec::new();
+    let mut dir_count = 0;
+
+    for source in sources {
+        let mut walker = WalkDir::new(source).follow_links(true);
+        if let Some(depth) = max_depth {
+            walker = walker.max_depth(depth);
+        }
+        let mut walker = walker.into_iter();
+
+        while let Some(entry) = walker.next() {
+            let entry = match entry {
+                Ok(entry) => entry,
+                // Unreadable entries further down are skipped, but not the root.
+                Err(e) if e.depth() == 0 => {
+                    return Err(DirsortError::Scan {
+                        path: source.clone(),
+                        source: e.into(),
+                    });
+                }
+                Err(_) => continue,
+            };
+
+            if entry.file_type().is_dir() {
+                if entry.depth() > 0 {
+                    let name = entry.file_name().to_string_lossy();
+                    let folder = dir_rules
+                        .iter()
+                        .find(|(pattern, _)| pattern.matches_with(&name, match_options))
+                        .map(|(_, category)| Some(category.to_string()))
+                        .or_else(|| {
+                            config
+                                .projects
+                                .as_ref()
+                                .filter(|projects| projects.matches(entry.path()))
+                                .map(|projects| match projects.action {
+                                    ProjectAction::Move => Some(projects.folder.clone()),
+                                    ProjectAction::Skip => None,
+                                })
+                        });
+
+                    if let Some(folder) = folder {
+                        walker.skip_current_dir();
+                        dirs.push(DirPlacement { entry, folder });
+                        continue;
+                    }
+                }
+                dir_count += 1;
+            } else if entry.file_type().is_file() {
+                entries.push(entry);
+            } else {
+                continue;
+            }
+            progress.scanning(dir_count, entries.len() as u64);
+        }
+    }
+
+    info!("{}", t!("scanned", dirs = dir_count, files = entries.len()));
+
+    if !dirs.is_empty() {
+        info!("Found {} directories to handle as a whole", dirs.len());
+    }
+
+    Ok((entries, dirs))
+}
+
+/// Runs `handle` on every entry in parallel. In `--order`, the workers take
+/// the entries one after another instead of splitting the list up front.
+fn for_each_entry(
+    entries: &[&walkdir::DirEntry],
+    ordered: bool,
+    handle: impl Fn(&walkdir::DirEntry) + Sync + Send,
+) {
+    if ordered {
+        entries.iter().par_bridge().for_each(|entry| handle(entry));
+    } else {
+        entries.par_iter().for_each(|entry| handle(entry));
+    }
+}
+
+/// Sorts `entries` into the `--order` they are processed in.
+fn order_entries(entries: &mut [walkdir::DirEntry], order: ProcessOrder) {
+    let size = |entry: &walkdir::DirEntry| entry.metadata().map_or(0, |metadata| metadata.len());
+    let modified = |entry: &walkdir::DirEntry| {
+        entry
+            .metadata()
+            .ok()
+            .and_then(|metadata| metadata.modified().ok())
+            .unwrap_or(SystemTime::UNIX_EPOCH)
+    };
+    match order {
+        ProcessOrder::SmallestFirst => entries.sort_by_cached_key(size),
+        ProcessOrder::LargestFirst => entries.sort_by_cached_key(|entry| Reverse(size(entry))),
+        ProcessOrder::OldestFirst => entries.sort_by_cached_key(modified),
+        ProcessOrder::NewestFirst => entries.sort_by_cached_key(|entry| Reverse(modified(entry))),
+        ProcessOrder::Random => {
+            let seed = SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(1, |now| now.as_nanos() as u64);
+            let mut rng = simulate::Rng(seed.max(1));
+            for i in (1..entries.len()).rev() {
+                entries.swap(i, rng.below(i as u64 + 1) as usize);
+            }
+        }
+    }
+}
+
+/// Moves the files of categories with a higher `[priorities]` entry to the
+/// front, keeping the order within each priority. A subcategory without an
+/// entry of its own has its parent's.
+fn prioritize_entries(
+    entries: &mut [walkdir::DirEntry],
+    config: &SorterConfig,
+    screenshot_patterns: &[glob::Pattern],
+) {
+    entries.sort_by_cached_key(|entry| {
+        let ext = entry.path().extension().and_then(|ext| ext.to_str());
+        let priority = categorize(entry.path(), ext, config, screenshot_patterns)
+            .and_then(|category| {
+                let mut category = category.as_str();
+                loop {
+                    if let Some(priority) = config.priorities.get(category) {
+                        return Some(*priority);
+                    }
+                    category = category.rsplit_once('/')?.0;
+                }
+            })
+            .unwrap_or(0);
+        Reverse(priority)
+    });
+}
+
+/// Whether `entry` is left where it is by every run, being blacklisted,
+/// uncategorized with `mode = "skip"`, seeding, in use or incomplete.
+fn always_left(
+    entry: &walkdir::DirEntry,
+    blacklist: &HashSet<String>,
+    args: &Cli,
+    config: &SorterConfig,
+    screenshot_patterns: &[glob::Pattern],
+    seeding: Option<&Seeding>,
+    open_files: Option<&OpenFiles>,
+) -> bool {
+    let path = entry.path();
+    let ext = path.extension().and_then(|ext| ext.to_str());
+    is_blacklisted(path, blacklist)
+        || config.incomplete.as_ref().is_some_and(|incomplete| {
+            incomplete.action == IncompleteAction::Skip && incomplete.matches(path, ext)
+        })
+        || seeding.is_some_and(|seeding| {
+            config
+                .seeding
+                .as_ref()
+                .is_none_or(|seeding| seeding.action == SeedingAction::Skip)
+                && entry
+                    .metadata()
+                    .is_ok_and(|metadata| seeding.contains(path, metadata.len()))
+        })
+        || open_files.is_some_and(|open_files| open_files.in_use(path))
+        || planned_folder(path, args, config, screenshot_patterns) == LEFT_IN_PLACE
+}
+
+/// Cuts `entries`, already in processing order, down to the batch `--limit`
+/// and `--limit-bytes` allow. Files `left` says stay where they are don't
+/// count, or they would take up the batch every run.
+fn limit_batch(
+    entries: &mut Vec<walkdir::DirEntry>,
+    left: impl Fn(&walkdir::DirEntry) -> bool,
+    limit: Option<usize>,
+    limit_bytes: Option<u64>,
+) {
+    let mut files = 0;
+    let mut bytes = 0;
+    let mut end = entries.len();
+    for (index, entry) in entries.iter().enumerate() {
+        if left(entry) {
+            continue;
+        }
+        let size = entry.metadata().map_or(0, |metadata| metadata.len());
+        let full = limit.is_some_and(|limit| files >= limit)
+            || limit_bytes.is_some_and(|limit| files > 0 && bytes + size > limit);
+        if full {
+            end = index;
+            break;
+        }
+        files += 1;
+        bytes += size;
+    }
+    entries.truncate(end);
+}
+
+fn copy_dir(source: &Path, dest: &Path) -> Result<()> {
+    for entry in WalkDir::new(source) {
+        let entry = entry?;
+        let target = dest.join(
+            entry
+                .path()
+                .strip_prefix(source)
+                .map_err(io::Error::other)?,
+        );
+
+        if entry.file_type().is_dir() {
+            create_dir_all(&target)?;
+        } else {
+            copy_file(entry.path(), &target)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Places a whole directory under `<out_dir>/<folder>/`, keeping its name.
+fn process_dir(
+    entry: &walkdir::DirEntry,
+    folder: &str,
+    ctx: &SortContext,
+    errors: &Arc<Mutex<Vec<SortError>>>,
+) {
+    pause::wait();
+
+    let _span = debug_span!("dir", path = %entry.path().display(), folder).entered();
+
+    let mut dest = None;
+    let mut result = || -> std::result::Result<(), Box<dyn error::Error + Send + Sync>> {
+        let top_level = folder.split('/').next().unwrap_or(folder);
+        let root = ctx
+            .roots
+            .pick(top_level, 0, |root| ctx.folders.resolve(root, top_level));
+        let target_dir = root.join(ctx.folders.resolve(&root, folder));
+        let dest_path = target_dir.join(entry.file_name());
+
+        let wanted = dest_path.clone();
+        let (dest_path, action) = ctx.placements.claim(dest_path, ctx.args.on_conflict);
+        dest = Some(dest_path.clone());
+        if let Some(plan) = &ctx.plan {
+            plan.record(entry.path(), wanted, dest_path, action);
+            return Ok(());
+        }
+        if action == Action::Skip || !ctx.allows(entry.path(), &dest_path, action) {
+            return Ok(());
+        }
+
+        readonly::check(&dest_path)?;
+        let created = journal::missing_dirs(&target_dir);
+        create_dir_all(&target_dir)?;
+        apply_dir_policy(&target_dir, ctx.args)?;
+
+        let backup = match (&ctx.args.backup, action) {
+            (Some(mode), Action::Overwrite) => backup::backup(&dest_path, &root, mode)?,
+            _ => None,
+        };
+        if backup.is_some() {
+            ctx.backups.fetch_add(1, Ordering::Relaxed);
+        } else if dest_path.exists() {
+            fs::remove_dir_all(&dest_path)?;
+        }
+
+        if ctx.args.mv {
+            move_file(entry.path(), &dest_path)?;
+        } else {
+            copy_dir(entry.path(), &dest_path)?;
+        }
+        ctx.journal(entry.path(), &dest_path, ctx.args.mv, backup, created);
+
+        let category_dir = root.join(
+            ctx.folders
+                .resolve(&root, folder.split('/').next().unwrap_or(folder)),
+        );
+        if let Some(checksums) = &ctx.checksums {
+            for file in WalkDir::new(&dest_path)
+                .into_iter()
+                .filter_map(std::result::Result::ok)
+                .filter(|file| file.file_type().is_file())
+            {
+                if let Err(e) = checksums.add(&category_dir, file.path()) {
+                    warn!("Failed to checksum '{}': {e}", file.path().display());
+                }
+            }
+        }
+        if ctx.args.par2.is_some() {
+            ctx.category_dirs
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(category_dir);
+        }
+
+        Ok(())
+    };
+
+    if let Err(e) = result()
+        && let Ok(mut errors_vec) = errors.lock()
+    {
+        errors_vec.push(SortError::new(entry.path(), dest, e));
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn apply_finder_attrs(dest: &Path, category: Option<&str>, args: &Cli) -> Result<()> {
+    if args.quarantine == QuarantinePolicy::Strip {
+        finder::strip_quarantine(dest)?;
+    }
+
+    if args.finder_tag
+        && let Some(category) = category
+    {
+        finder::tag_with_category(dest, category)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(not(target_os = "macos"))]
+fn apply_finder_attrs(_dest: &Path, _category: Option<&str>, _args: &Cli) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn preserve_attrs(source: &Path, dest: &Path, args: &Cli) -> Result<()> {
+    if args.preserve.is_empty() {
+        return Ok(());
+    }
+
+    xattrs::preserve(source, dest, &args.preserve)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn preserve_attrs(_source: &Path, _dest: &Path, _args: &Cli) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(unix)]
+fn apply_file_policy(dest: &Path, args: &Cli) -> Result<()> {
+    perms::apply_file_policy(dest, args)
+}
+
+#[cfg(not(unix))]
+fn apply_file_policy(_dest: &Path, _args: &Cli) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(unix)]
+fn strip_exec_bits(dest: &Path) -> Result<()> {
+    perms::strip_exec_bits(dest)
+}
+
+#[cfg(not(unix))]
+fn strip_exec_bits(_dest: &Path) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(unix)]
+fn apply_dir_policy(dir: &Path, args: &Cli) -> Result<()> {
+    perms::apply_dir_policy(dir, args)
+}
+
+#[cfg(not(unix))]
+fn apply_dir_policy(_dir: &Path, _args: &Cli) -> Result<()> {
+    Ok(())
+}
+
+/// The `--alpha-buckets` folder for a file name: its first letter, `0-9` or `#`.
+fn alpha_bucket(file_name: &str) -> String {
+    match file_name.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() => c.to_ascii_uppercase().to_string(),
+        Some(c) if c.is_ascii_digit() => "0-9".to_string(),
+        _ => "#".to_string(),
+    }
+}
+
+/// Probes whether `dir` lives on a case-insensitive filesystem (the default on
+/// macOS and Windows), where `Photo.JPG` and `photo.jpg` are the same file.
+fn is_case_insensitive(dir: &Path) -> Result<bool> {
+    let probe = dir.join(".dirsort-case-probe");
+    File::create(&probe)?;
+    let insensitive = dir.join(".DIRSORT-CASE-PROBE").exists();
+    remove_file(&probe)?;
+
+    Ok(insensitive)
+}
+
+fn numbered_path(path: &Path, n: u64) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let name = match path.extension() {
+        Some(ext) => format!("{stem} ({n}).{}", ext.to_string_lossy()),
+        None => format!("{stem} ({n})"),
+    };
+
+    path.with_file_name(name)
+}
+
+/// Tracks which destinations have been handed out during this run, so that
+/// two sources mapping to the same path (or, on case-insensitive filesystems,
+/// to two spellings of it) go through the conflict strategy instead of
+/// silently overwriting each other.
+struct Placements {
+    case_insensitive: bool,
+    claimed: Mutex<HashSet<String>>,
+    conflicts: AtomicU64,
+}
+
+impl Placements {
+    fn new(out_dir: &Path) -> Self {
+        // A dry run doesn't create the output directory, so there may be
+        // nothing to probe; guess from the platform's default filesystem.
+        if !out_dir.is_dir() {
+            return Self {
+                case_insensitive: cfg!(any(target_os = "macos", windows)),
+                claimed: Mutex::new(HashSet::new()),
+                conflicts: AtomicU64::new(0),
+            };
+        }
+
+        let case_insensitive = is_case_insensitive(out_dir).unwrap_or_else(|e| {
+            warn!(
+                "Failed to probe case sensitivity of '{}': {e}",
+                out_dir.display()
+            );
+            false
+        });
+
+        if case_insensitive {
+            info!("{}", t!("case-insensitive"));
+        }
+
+        Self {
+            case_insensitive,
+            claimed: Mutex::new(HashSet::new()),
+            conflicts: AtomicU64::new(0),
+        }
+    }
+
+    fn key(&self, path: &Path) -> String {
+        let path = path.to_string_lossy();
+
+        if self.case_insensitive {
+            path.to_lowercase()
+        } else {
+            path.into_owned()
+        }
+    }
+
+    /// Reserves `dest` for the current file, returning the path it should
+    /// actually be written to and how it was decided.
+    fn claim(&self, dest: PathBuf, strategy: ConflictStrategy) -> (PathBuf, Action) {
+        let mut claimed = self.claimed.lock().unwrap();
+        let taken = |path: &Path, claimed: &HashSet<String>| {
+            claimed.contains(&self.key(path)) || path.exists()
+        };
+
+        if !taken(&dest, &claimed) {
+            claimed.insert(self.key(&dest));
+            return (dest, Action::New);
+        }
+
+        self.conflicts.fetch_add(1, Ordering::Relaxed);
+
+        match strategy {
+            ConflictStrategy::Overwrite => {
+                claimed.insert(self.key(&dest));
+                (dest, Action::Overwrite)
+            }
+            ConflictStrategy::Skip => (dest, Action::Skip),
+            ConflictStrategy::Rename => {
+                let renamed = (1..)
+                    .map(|n| numbered_path(&dest, n))
+                    .find(|candidate| !taken(candidate, &claimed))
+                    .unwrap_or(dest);
+                claimed.insert(self.key(&renamed));
+                (renamed, Action::Rename)
+            }
+        }
+    }
+}
+
+/// Maps `(directory, lowercased stem)` to the extensions of the files there
+/// that sidecars can follow.
+type PrimaryIndex = HashMap<(PathBuf, String), Vec<String>>;
+
+fn index_primaries(entries: &[walkdir::DirEntry], rules: &[SidecarRule]) -> PrimaryIndex {
+    let mut index = PrimaryIndex::new();
+
+    if rules.is_empty() {
+        return index;
+    }
+
+    for entry in entries {
+        let path = entry.path();
+        let (Some(parent), Some(stem), Some(ext)) = (
+            path.parent(),
+            path.file_stem().and_then(|s| s.to_str()),
+            path.extension().and_then(|e| e.to_str()),
+        ) else {
+            continue;
+        };
+
+        let ext_lower = ext.to_lowercase();
+        if rules.iter().any(|rule| rule.follows.contains(&ext_lower)) {
+            index
+                .entry((parent.to_path_buf(), stem.to_lowercase()))
+                .or_default()
+                .push(ext.to_string());
+        }
+    }
+
+    index
+}
+
+/// The file a sidecar found by `sidecar_primary` belongs to, e.g.
+/// `movie.mkv` for `movie.en.srt`; `path` itself for any other file.
+fn sidecar_primary_path(path: &Path, ext: &str, routing_ext: &str) -> PathBuf {
+    if ext == routing_ext {
+        return path.to_path_buf();
+    }
+    let mut stem = path
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    loop {
+        let primary = path.with_file_name(format!("{stem}.{routing_ext}"));
+        if primary.exists() {
+            return primary;
+        }
+        match stem.rsplit_once('.') {
+            Some((shorter, _)) => stem = shorter.to_string(),
+            None => return path.to_path_buf(),
+        }
+    }
+}
+
+/// Finds the extension of the file a sidecar belongs to, if any.
+///
+/// Language-tagged sidecars like `movie.en.srt` are matched by dropping
+/// dotted suffixes from the stem until a primary file is found.
+fn sidecar_primary<'a>(
+    path: &Path,
+    ext: &str,
+    rules: &[SidecarRule],
+    index: &'a PrimaryIndex,
+) -> Option<&'a str> {
+    let ext = ext.to_lowercase();
+    let size = OnceCell::new();
+    let rules = rules
+        .iter()
+        .filter(|rule| rule.extensions.contains(&ext))
+        .filter(|rule| {
+            rule.max_size.is_none_or(|max_size| {
+                let size = size.get_or_init(|| path.metadata().map_or(u64::MAX, |m| m.len()));
+                *size <= max_size.0
+            })
+        })
+        .collect::<Vec<_>>();
+
+    if rules.is_empty() {
+        return None;
+    }
+
+    let parent = path.parent()?.to_path_buf();
+    let mut stem = path.file_stem()?.to_str()?.to_lowercase();
+
+    loop {
+        if let Some(primaries) = index.get(&(parent.clone(), stem.clone()))
+            && let Some(primary) = primaries.iter().find(|primary| {
+                rules
+                    .iter()
+                    .any(|rule| rule.follows.contains(&primary.to_lowercase()))
+            })
+        {
+            return Some(primary);
+        }
+
+        let (shorter, _) = stem.rsplit_once('.')?;
+        stem = shorter.to_string();
+    }
+}
+
+fn find_bursts(
+    entries: &[walkdir::DirEntry],
+    config: &SorterConfig,
+    max_gap_secs: u64,
+) -> HashMap<PathBuf, String> {
+    let images = entries
+        .iter()
+        .map(walkdir::DirEntry::path)
+        .filter(|path| {
+            let category = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .and_then(|ext| get_category(config.canonical_ext(ext), &config.categories));
+            in_category(category, photos_category(config))
+        })
+        .collect::<Vec<_>>();
+
+    let bursts = bursts::find(&images, max_gap_secs);
+    if !bursts.is_empty() {
+        let sequences = bursts.values().collect::<HashSet<_>>().len();
+        info!(
+            "Found {sequences} bursts and sequences with {} photos",
+            bursts.len()
+        );
+    }
+    bursts
+}
+
+fn find_near_duplicates(
+    entries: &[walkdir::DirEntry],
+    config: &SorterConfig,
+) -> HashMap<PathBuf, PathBuf> {
+    let images = entries
+        .iter()
+        .map(walkdir::DirEntry::path)
+        .filter(|path| {
+            let category = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .and_then(|ext| get_category(config.canonical_ext(ext), &config.categories));
+            in_category(category, photos_category(config))
+        })
+        .collect::<Vec<_>>();
+
+    info!("Hashing {} images for near-duplicates...", images.len());
+
+    let duplicates = perceptual::near_duplicates(&images);
+
+    if !duplicates.is_empty() {
+        info!(
+            "Found {} near-duplicate images, they will be placed in '{NEAR_DUPLICATES_DIR}'",
+            duplicates.len()
+        );
+    }
+
+    duplicates
+}
+
+fn write_near_duplicates_report(
+    out_dir: &Path,
+    duplicates: &HashMap<PathBuf, PathBuf>,
+) -> Result<()> {
+    let mut groups: HashMap<&Path, Vec<&Path>> = HashMap::new();
+    for (duplicate, keeper) in duplicates {
+        groups.entry(keeper).or_default().push(duplicate);
+    }
+
+    let mut file = File::create(out_dir.join("near-duplicates.txt"))?;
+    for (keeper, mut members) in groups {
+        members.sort();
+        writeln!(file, "{}", keeper.display())?;
+        for member in members {
+            writeln!(file, "  {}", member.display())?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Everything `process_file` needs that stays the same for every file in a run.
+struct SortContext<'a> {
+    args: &'a Cli,
+    blacklist: &'a HashSet<String>,
+    config: &'a SorterConfig,
+    primaries: PrimaryIndex,
+    screenshot_patterns: Vec<glob::Pattern>,
+    placements: Placements,
+    roots: Roots,
+    folders: FolderNames,
+    quotas: Quotas<'a>,
+    /// Files left in place because their category reached its quota
+    over_quota: AtomicU64,
+    journal: Option<Journal>,
+    uncategorized_skipped: AtomicU64,
+    /// Files that looked like downloads in progress
+    incomplete: AtomicU64,
+    /// Files torrent clients are seeding, with `[seeding]`
+    seeding: Option<Seeding>,
+    /// Seeded files that were left in place
+    seeding_skipped: AtomicU64,
+    /// Set when moving, to check whether files are in use
+    open_files: Option<OpenFiles>,
+    /// Files that were in use, to be retried after the others
+    deferred: Mutex<Vec<walkdir::DirEntry>>,
+    retrying: AtomicBool,
+    /// Files left in place because they were in use
+    in_use: Mutex<Vec<PathBuf>>,
+    /// Destinations moved aside by `--backup`
+    backups: AtomicU64,
+    /// Bytes moved with a rename, and bytes that had to be copied across filesystems
+    moved_by_rename: AtomicU64,
+    moved_by_copy: AtomicU64,
+    rename_counter: AtomicU64,
+    /// Near-duplicate images, mapped to the copy that is kept
+    near_duplicates: HashMap<PathBuf, PathBuf>,
+    /// The sequence folder of each photo in a burst, with `--group-bursts`
+    bursts: HashMap<PathBuf, String>,
+    /// Files clamd flagged, with the signature it found
+    detections: Mutex<Vec<(PathBuf, String)>>,
+    dashboard: Option<tui::Dashboard>,
+    /// Set with `--dry-run`, collects placements instead of performing them
+    plan: Option<Plan>,
+    confirmer: Option<Confirmer>,
+    report: Option<Report>,
+    checksums: Option<Checksums>,
+    /// Category folders files were placed in, for `--par2`
+    category_dirs: Mutex<HashSet<PathBuf>>,
+    snapshot: Option<Snapshot>,
+    sources: Vec<Source>,
+    /// Set with `--transactional`, holds the staged files until the end
+    transaction: Option<Transaction>,
+    /// Every file placed, for the integrity check after the run, by where
+    /// it went; a later file placed at the same destination replaces it
+    placed_files: Mutex<HashMap<PathBuf, integrity::Placed>>,
+    /// The mail file each attachment `--extract-attachments` extracted came
+    /// from, by where it was extracted to
+    extracted: HashMap<PathBuf, PathBuf>,
+}
+
+impl SortContext<'_> {
+    /// Asks about the placement with `--confirm`, otherwise always true.
+    fn allows(&self, source: &Path, dest: &Path, action: Action) -> bool {
+        let verb = if self.args.mv { "Move" } else { "Copy" };
+        self.confirmer
+            .as_ref()
+            .is_none_or(|confirmer| confirmer.allows(verb, source, dest, action))
+    }
+
+    fn journal_made(&self, source: &Path, dest: &Path, created: Vec<PathBuf>) {
+        if let Some(journal) = &self.journal
+            && let Err(e) = journal.record_made(source, dest, created)
+        {
+            warn!(
+                "Failed to journal '{}', it can't be undone: {e}",
+                dest.display()
+            );
+        }
+    }
+
+    fn journal(
+        &self,
+        source: &Path,
+        dest: &Path,
+        moved: bool,
+        backup: Option<PathBuf>,
+        created: Vec<PathBuf>,
+    ) {
+        // Attachments are made from their message, and the scratch copy
+        // they were placed from is gone by the time anyone undoes the run.
+        let recorded = match (&self.journal, self.extracted.get(source)) {
+            (None, _) => return,
+            (Some(journal), Some(message)) => journal.record_made(message, dest, created),
+            (Some(journal), None) => {
+                journal.record(source, dest, moved, backup, self.origin(source), created)
+            }
+        };
+        if let Err(e) = recorded {
+            warn!(
+                "Failed to journal '{}', it can't be undone: {e}",
+                dest.display()
+            );
+        }
+    }
+
+    /// The label of the `--source` that `path` was found in.
+    fn origin(&self, path: &Path) -> Option<&str> {
+        sources::origin(&self.sources, path)
+    }
+
+    fn record(&self, row: Row, status: Status, error: Option<&SortError>) {
+        if let Some(report) = &self.report {
+            report.record(row, status, error);
+        }
+    }
+}
+
+fn process_file(
+    entry: &walkdir::DirEntry,
+    ctx: &SortContext,
+    errors: &Arc<Mutex<Vec<SortError>>>,
+    skipped: &Arc<AtomicU64>,
+) {
+    let SortContext {
+        args,
+        blacklist,
+        config,
+        ..
+    } = *ctx;
+
+    pause::wait();
+
+    let span = debug_span!(
+        "file",
+        path = %entry.path().display(),
+        category = field::Empty
+    );
+    let _entered = span.enter();
+
+    let started = Instant::now();
+    let mut row = Row {
+        source: entry.path().to_path_buf(),
+        origin: ctx.origin(entry.path()).map(str::to_string),
+        bytes: match (&ctx.dashboard, &ctx.report) {
+            (None, None) => 0,
+            _ => entry.metadata().map_or(0, |metadata| metadata.len()),
+        },
+        ..Row::default()
+    };
+
+    if ctx.confirmer.as_ref().is_some_and(Confirmer::quit) {
+        ctx.record(row, Status::Declined, None);
+        return;
+    }
+
+    if is_blacklisted(entry.path(), blacklist) {
+        skipped.fetch_add(1, Ordering::Relaxed);
+        ctx.record(row, Status::Blacklisted, None);
+        return;
+    }
+
+    let mut result = || -> std::result::Result<Status, Box<dyn error::Error + Send + Sync>> {
+        let file_name = entry
+            .file_name()
+            .to_str()
+            .ok_or("Invalid filename encoding")?;
+
+        let pending = match &config.incomplete {
+            Some(incomplete)
+                if incomplete.matches(
+                    entry.path(),
+                    entry.path().extension().and_then(|ext| ext.to_str()),
+                ) =>
+            {
+                ctx.incomplete.fetch_add(1, Ordering::Relaxed);
+                if incomplete.action == IncompleteAction::Skip {
+                    return Ok(Status::Incomplete);
+                }
+                Some(incomplete.folder.as_str())
+            }
+            _ => None,
+        };
+
+        let seeded = match &ctx.seeding {
+            Some(seeding) if seeding.contains(entry.path(), entry.metadata()?.len()) => {
+                if config
+                    .seeding
+                    .as_ref()
+                    .is_none_or(|seeding| seeding.action == SeedingAction::Skip)
+                {
+                    ctx.seeding_skipped.fetch_add(1, Ordering::Relaxed);
+                    return Ok(Status::Seeding);
+                }
+                true
+            }
+            _ => false,
+        };
+
+        if let Some(open_files) = &ctx.open_files
+            && open_files.in_use(entry.path())
+        {
+            if args.in_use == InUsePolicy::Defer && !ctx.retrying.load(Ordering::Relaxed) {
+                ctx.deferred.lock().unwrap().push(entry.clone());
+                return Ok(Status::Deferred);
+            }
+            ctx.in_use.lock().unwrap().push(entry.path().to_path_buf());
+            return Ok(Status::InUse);
+        }
+
+        let infection = match &args.scan {
+            Some(addr) => match clamav::scan(addr, entry.path())? {
+                Verdict::Infected(signature) => {
+                    ctx.detections
+                        .lock()
+                        .unwrap()
+                        .push((entry.path().to_path_buf(), signature.clone()));
+                    Some(signature)
+                }
+                Verdict::Clean => None,
+            },
+            None => None,
+        };
+
+        let ext = entry
+            .path()
+            .extension()
+            .map(|ext| ext.to_str().ok_or("Invalid extension encoding"))
+            .transpose()?;
+        let routing_ext = ext.map(|ext| {
+            sidecar_primary(entry.path(), ext, &config.sidecars, &ctx.primaries).unwrap_or(ext)
+        });
+        let traced = categorize_traced(entry.path(), routing_ext, config, &ctx.screenshot_patterns);
+        if args.trace_rules {
+            info!(
+                "{}: {}",
+                entry.path().display(),
+                traced.as_ref().map_or_else(
+                    || "no rule or category matched".to_string(),
+                    |(category, reason)| format!("{category} by {reason}")
+                )
+            );
+        }
+        let category = traced.map(|(category, _)| category);
+        if let Some(category) = &category {
+            span.record("category", category.as_str());
+        }
+        row.category = category.clone();
+        let ext_cased = ext.map(|ext| args.normalize_ext_case.apply(ext));
+
+        let (subfolder, dest_name) = match (ext, routing_ext, &ext_cased) {
+            (Some(ext), Some(routing_ext), Some(ext_cased)) => {
+                let stem = &file_name[..file_name.len() - ext.len()];
+                let subfolder = match (&category, config.uncategorized.mode) {
+                    (Some(category), _) => category.clone(),
+                    (None, UncategorizedMode::ByExtension) => args
+                        .normalize_ext_case
+                        .apply(config.canonical_ext(routing_ext)),
+                    (None, UncategorizedMode::SingleFolder) => config.fallback.clone(),
+                    (None, UncategorizedMode::Skip) => {
+                        ctx.uncategorized_skipped.fetch_add(1, Ordering::Relaxed);
+                        return Ok(Status::Uncategorized);
+                    }
+                };
+                (subfolder, format!("{stem}{ext_cased}"))
+            }
+            _ => match (&category, config.uncategorized.mode) {
+                (Some(category), _) => (category.clone(), file_name.to_string()),
+                (None, UncategorizedMode::Skip) => {
+                    ctx.uncategorized_skipped.fetch_add(1, Ordering::Relaxed);
+                    return Ok(Status::Uncategorized);
+                }
+                (None, _) => (config.fallback.clone(), file_name.to_string()),
+            },
+        };
+
+        let subfolder = match (args.by_size_tier, category.as_deref()) {
+            (true, Some(category)) => match config
+                .size_tiers
+                .subfolder(category, entry.metadata()?.len())
+            {
+                Some(tier) => format!("{subfolder}/{tier}"),
+                None => subfolder,
+            },
+            _ => subfolder,
+        };
+
+        let (subfolder, dest_name) = match (args.docs_by_meta, ext_cased.as_deref()) {
+            (true, Some(ext)) => document_placement(entry.path(), ext, subfolder, dest_name),
+            _ => (subfolder, dest_name),
+        };
+
+        if args.by_download_origin || ctx.report.is_some() {
+            row.downloaded_from =
+                downloads::origin_url(entry.path()).map(|url| downloads::redact(&url));
+        }
+        let subfolder = match (args.by_download_origin, &row.downloaded_from) {
+            (true, Some(url)) => match downloads::domain(url) {
+                Some(domain) => format!("{subfolder}/{}", sanitize(&domain)),
+                None => subfolder,
+            },
+            _ => subfolder,
+        };
+
+        let is_photo = in_category(category.as_deref(), photos_category(config));
+        let is_video = in_category(category.as_deref(), videos_category(config));
+
+        let subfolder = match (args.photo_by_place, is_photo) {
+            (true, true) => match places::folder(entry.path()) {
+                Some(place) => format!("{subfolder}/{place}"),
+                None => format!("{subfolder}/{}", args.no_place_folder),
+            },
+            _ => subfolder,
+        };
+
+        let subfolder = match (ctx.bursts.get(entry.path()), is_photo) {
+            (Some(sequence), true) => format!("{subfolder}/{sequence}"),
+            _ => subfolder,
+        };
+
+        let subfolder = match (args.by_audio_language, is_video, ext, routing_ext) {
+            (true, true, Some(ext), Some(routing_ext)) => {
+                match video::audio_language(&sidecar_primary_path(entry.path(), ext, routing_ext)) {
+                    Some(language) => format!("{subfolder}/{}", sanitize(&language)),
+                    None => subfolder,
+                }
+            }
+            _ => subfolder,
+        };
+
+        let subfolder = match (args.video_tiers, is_video) {
+            (true, true) => match config.video_tiers.subfolder(entry.path()) {
+                Some(tier) => format!("{subfolder}/{tier}"),
+                None => subfolder,
+            },
+            _ => subfolder,
+        };
+
+        let subfolder = match (&config.raw_pairs, ext, routing_ext) {
+            (Some(raw_pairs), Some(ext), Some(routing_ext))
+                if raw_pairs.is_preview(ext, routing_ext) =>
+            {
+                format!("{subfolder}/{}", raw_pairs.previews_folder)
+            }
+            _ => subfolder,
+        };
+
+        let quarantined = infection.is_some()
+            || (args.quarantine_executables
+                && config
+                    .executables
+                    .as_ref()
+                    .is_some_and(|executables| category.as_ref() == Some(&executables.folder)));
+        let subfolder = match pending {
+            Some(folder) => folder.to_string(),
+            None => subfolder,
+        };
+        let subfolder = match (&infection, quarantined) {
+            (Some(_), _) => QUARANTINE_DIR.to_string(),
+            (None, true) => format!("{QUARANTINE_DIR}/{subfolder}"),
+            (None, false) => subfolder,
+        };
+
+        let template = category
+            .as_ref()
+            .filter(|_| infection.is_none() && pending.is_none())
+            .and_then(|category| config.rename.get(category))
+            .or(args.rename.as_ref());
+
+        let dest_name = match template {
+            Some(template) => template.render(&NameFields {
+                path: entry.path(),
+                file_name,
+                stem: ext.map_or(file_name, |ext| {
+                    &file_name[..file_name.len() - ext.len() - 1]
+                }),
+                ext: ext_cased.as_deref(),
+                category: &subfolder,
+                counter: ctx.rename_counter.fetch_add(1, Ordering::Relaxed) + 1,
+                hash: args.hash,
+            })?,
+            None => dest_name,
+        };
+
+        let dest_name = match (args.tag_origin, ctx.origin(entry.path())) {
+            (Some(OriginTag::Name), Some(origin)) => sources::tag_name(&dest_name, origin),
+            _ => dest_name,
+        };
+
+        let subfolder = if args.alpha_buckets && infection.is_none() && pending.is_none() {
+            format!("{subfolder}/{}", alpha_bucket(&dest_name))
+        } else {
+            subfolder
+        };
+
+        let near_duplicate = ctx.near_duplicates.get(entry.path());
+        let subfolder = match near_duplicate {
+            Some(keeper) => Path::new(NEAR_DUPLICATES_DIR)
+                .join(keeper.file_stem().unwrap_or_default())
+                .display()
+                .to_string(),
+            None => subfolder,
+        };
+
+        let conversion = ext
+            .filter(|_| {
+                infection.is_none() && !quarantined && pending.is_none() && near_duplicate.is_none()
+            })
+            .and_then(|ext| {
+                args.convert
+                    .iter()
+                    .find(|conversion| conversion.from.eq_ignore_ascii_case(ext))
+            });
+        let subfolder = match conversion {
+            Some(_) => format!("{subfolder}/{}", convert::ORIGINALS_DIR),
+            None => subfolder,
+        };
+
+        let size = entry.metadata()?.len();
+        let top_level = subfolder.split('/').next().unwrap_or_default();
+        let in_category = category
+            .as_deref()
+            .is_some_and(|category| category.split('/').next() == Some(top_level));
+        let on_disk = |root: &Path, folder: &str| {
+            if in_category {
+                ctx.folders.resolve(root, folder)
+            } else {
+                folder.to_string()
+            }
+        };
+        let root = ctx
+            .roots
+            .pick(top_level, size, |root| on_disk(root, top_level));
+        let (root, reservation) =
+            match ctx
+                .quotas
+                .admit(top_level, &root.join(on_disk(&root, top_level)), size)
+            {
+                Admission::Fits(reservation) => (root, Some(reservation)),
+                Admission::Overflow(overflow) => (overflow, None),
+                Admission::Full => {
+                    ctx.over_quota.fetch_add(1, Ordering::Relaxed);
+                    return Ok(Status::OverQuota);
+                }
+            };
+        let category_dir = root.join(on_disk(&root, top_level));
+        let target_dir = root.join(on_disk(&root, &subfolder));
+        let dest_path = target_dir.join(dest_name);
+
+        let wanted = dest_path.clone();
+        let (dest_path, action) = ctx.placements.claim(dest_path, args.on_conflict);
+        row.dest = Some(dest_path.clone());
+        if let Some(plan) = &ctx.plan {
+            plan.record(entry.path(), wanted, dest_path, action);
+            if let Some(reservation) = reservation {
+                reservation.keep();
+            }
+            return Ok(Status::Planned);
+        }
+        if action == Action::Skip {
+            return Ok(Status::Conflict);
+        }
+        if !ctx.allows(entry.path(), &dest_path, action) {
+            return Ok(Status::Declined);
+        }
+        ctx.roots.reserve(&root, size);
+
+        let dest_dir = dest_path.parent().unwrap_or(&target_dir);
+        readonly::check(&dest_path)?;
+        // Staged files are written elsewhere and only moved to `dest_path`
+        // when the run is committed.
+        let created = match &ctx.transaction {
+            Some(_) => Vec::new(),
+            None => journal::missing_dirs(dest_dir),
+        };
+        let staged = match &ctx.transaction {
+            Some(transaction) => Some(transaction.stage(&dest_path)?),
+            None => {
+                create_dir_all(dest_dir)?;
+                apply_dir_policy(dest_dir, args)?;
+                None
+            }
+        };
+        let written = staged.as_deref().unwrap_or(&dest_path);
+
+        let backup = match (&args.backup, action, &staged) {
+            (Some(mode), Action::Overwrite, None) => backup::backup(&dest_path, &root, mode)?,
+            _ => None,
+        };
+        if backup.is_some() {
+            ctx.backups.fetch_add(1, Ordering::Relaxed);
+        }
+
+        if let Some(dashboard) = &ctx.dashboard {
+            dashboard.begin(entry.path());
+        }
+
+        let size = entry.metadata()?.len();
+        // The client keeps seeding from the original, which a link shares
+        // its data, permissions and attributes with.
+        let linked = seeded && fs::hard_link(entry.path(), written).is_ok();
+        if seeded {
+            if !linked {
+                copy_file(entry.path(), written)?;
+                preserve_attrs(entry.path(), written, args)?;
+            }
+        } else if args.mv && staged.is_none() {
+            let moved = match move_file(entry.path(), &dest_path)? {
+                true => &ctx.moved_by_rename,
+                false => &ctx.moved_by_copy,
+            };
+            moved.fetch_add(size, Ordering::Relaxed);
+        } else if let Some(snapshot) = &ctx.snapshot {
+            let copy = || copy_file(entry.path(), written).map_err(io::Error::other);
+            if !snapshot.place(entry.path(), written, copy)? {
+                preserve_attrs(entry.path(), written, args)?;
+            }
+        } else {
+            copy_file(entry.path(), written)?;
+            preserve_attrs(entry.path(), written, args)?;
+        }
+        match &ctx.transaction {
+            Some(transaction) => transaction.record(Staged {
+                source: Some(entry.path().to_path_buf()),
+                made_from: None,
+                staged: written.to_path_buf(),
+                dest: dest_path.clone(),
+                root: root.clone(),
+                category_dir: category_dir.clone(),
+                moved: args.mv && !seeded,
+                overwrite: action == Action::Overwrite,
+                size,
+            }),
+            None => {
+                ctx.journal(
+                    entry.path(),
+                    &dest_path,
+                    args.mv && !seeded,
+                    backup,
+                    created,
+                );
+                if let Some(checksums) = &ctx.checksums
+                    && let Err(e) = checksums.add(&category_dir, &dest_path)
+                {
+                    warn!("Failed to checksum '{}': {e}", dest_path.display());
+                }
+            }
+        }
+        if let Some(reservation) = reservation {
+            reservation.keep();
+        }
+        // Staged files count once they are committed.
+        if ctx.transaction.is_none() {
+            ctx.placed_files
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(
+                    dest_path.clone(),
+                    integrity::Placed {
+                        source: entry.path().to_path_buf(),
+                        dest: dest_path.clone(),
+                        category_dir: category_dir.clone(),
+                        size,
+                        moved: args.mv && !seeded,
+                    },
+                );
+        }
+        if args.par2.is_some() {
+            ctx.category_dirs
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(category_dir.clone());
+        }
+
+        // Changing a link would change the file being seeded too.
+        if !linked {
+            apply_file_policy(written, args)?;
+            apply_finder_attrs(written, category.as_deref(), args)?;
+            if args.tag_origin == Some(OriginTag::Xattr)
+                && let Some(origin) = ctx.origin(entry.path())
+            {
+                sources::tag_xattr(written, origin)?;
+            }
+
+            if quarantined {
+                strip_exec_bits(written)?;
+            }
+        }
+
+        if let Some(conversion) = conversion {
+            let converted_dir = dest_dir.parent().unwrap_or(dest_dir);
+            let (converted, action) = ctx
+                .placements
+                .claim(conversion.dest(&dest_path, converted_dir), args.on_conflict);
+            // The original is placed already, so a failed conversion only
+            // costs the converted copy.
+            if action != Action::Skip {
+                let result = match &ctx.transaction {
+                    Some(transaction) => transaction.stage(&converted).and_then(|staged| {
+                        conversion.run(written, &staged)?;
+                        apply_file_policy(&staged, args)?;
+                        transaction.record(Staged {
+                            source: None,
+                            made_from: Some(dest_path.clone()),
+                            staged,
+                            dest: converted.clone(),
+                            root,
+                            category_dir,
+                            moved: false,
+                            overwrite: action == Action::Overwrite,
+                            size: 0,
+                        });
+                        Ok(())
+                    }),
+                    None => match conversion.run(written, &converted) {
+                        Ok(()) => {
+                            ctx.journal_made(&dest_path, &converted, Vec::new());
+                            apply_file_policy(&converted, args)
+                        }
+                        Err(e) => {
+                            let _ = fs::remove_file(&converted);
+                            Err(e)
+                        }
+                    },
+                };
+                if let Err(e) = result {
+                    warn!("Failed to convert '{}': {e}", dest_path.display());
+                }
+            }
+        }
+
+        if let Some(dashboard) = &ctx.dashboard {
+            let top_level = subfolder.split('/').next().unwrap_or_default();
+            dashboard.placed(top_level, row.bytes);
+        }
+
+        Ok(match action {
+            _ if quarantined => Status::Quarantined,
+            _ if pending.is_some() => Status::Pending,
+            Action::Rename => Status::Renamed,
+            Action::Overwrite => Status::Overwritten,
+            _ => Status::Placed,
+        })
+    };
+
+    match result() {
+        Ok(status) => {
+            row.duration = started.elapsed();
+            ctx.record(row, status, None);
+        }
+        Err(e) => {
+            row.duration = started.elapsed();
+            let error = SortError::new(entry.path(), row.dest.clone(), e);
+            ctx.record(row, Status::Failed, Some(&error));
+
+            if let Some(dashboard) = &ctx.dashboard {
+                dashboard.error(error.to_string());
+            }
+            if let Ok(mut errors_vec) = errors.lock() {
+                errors_vec.push(error);
+            }
+        }
+    }
+}
+
+/// Checks the placed files for ones that went missing, came out empty or
+/// were cut short, and copies them over again with `--repair`. Returns the
+/// ones still damaged.
+fn check_integrity(placed: Vec<integrity::Placed>, ctx: &SortContext) -> Vec<integrity::Damaged> {
+    let damaged = integrity::sweep(placed);
+    if !ctx.args.repair || damaged.is_empty() {
+        return damaged;
+    }
+
+    info!("{}", t!("repairing", count = damaged.len()));
+    damaged
+        .into_iter()
+        .filter(|file| {
+            let integrity::Placed {
+                source,
+                dest,
+                category_dir,
+                ..
+            } = &file.placed;
+            let repaired = integrity::repair(file).and_then(|()| {
+                preserve_attrs(source, dest, ctx.args)?;
+                apply_file_policy(dest, ctx.args)?;
+                match &ctx.checksums {
+                    Some(checksums) => checksums.add(category_dir, dest),
+                    None => Ok(()),
+                }
+            });
+            match repaired {
+                Ok(()) => {
+                    info!("{}", t!("repaired", path = dest.display().to_string()));
+                    false
+                }
+                Err(e) => {
+                    error!("Failed to repair '{}': {e}", dest.display());
+                    true
+                }
+            }
+        })
+        .collect()
+}
+
+/// `--transactional`: moves every staged file into place, unless `failed`,
+/// and then removes the sources of moved files. If anything goes wrong
+/// before the sources are touched, the files already in place are put back
+/// into staging and all of it is discarded. Returns whether the run was
+/// committed.
+fn commit(ctx: &SortContext, transaction: &Transaction, failed: bool) -> bool {
+    let staged = transaction.take();
+    let discard = |message: String| {
+        error!("{message}");
+        if let Err(e) = transaction.discard() {
+            warn!("Failed to remove the staging area: {e}");
+        }
+        if let Some(report) = &ctx.report {
+            report.roll_back();
+        }
+        false
+    };
+    if failed {
+        return discard(
+            "Some files failed, so nothing was placed or moved (--transactional)".to_string(),
+        );
+    }
+
+    /// A staged file moved into place, with what it takes to undo that.
+    struct Committed<'a> {
+        file: &'a Staged,
+        backup: Option<PathBuf>,
+        /// The file it replaced without `--backup`, kept in staging
+        replaced: Option<PathBuf>,
+        /// Folders created for it, deepest first
+        created_dirs: Vec<PathBuf>,
+    }
+
+    let mut placed: Vec<Committed> = Vec::new();
+    for file in &staged {
+        let dest_dir = file.dest.parent().unwrap_or(&file.root);
+        let created_dirs = journal::missing_dirs(dest_dir);
+        let result = (|| -> io::Result<(Option<PathBuf>, Option<PathBuf>)> {
+            create_dir_all(dest_dir)?;
+            apply_dir_policy(dest_dir, ctx.args)?;
+            let backup = match (&ctx.args.backup, file.overwrite) {
+                (Some(mode), true) => backup::backup(&file.dest, &file.root, mode)?,
+                _ => None,
+            };
+            // Without a backup, the file being replaced is set aside so a
+            // rollback can put it back.
+            let replaced = match backup {
+                None if file.dest.exists() => {
+                    let mut aside = file.staged.clone().into_os_string();
+                    aside.push(".replaced");
+                    let aside = PathBuf::from(aside);
+                    fs::rename(&file.dest, &aside)?;
+                    Some(aside)
+                }
+                _ => None,
+            };
+            if let Err(e) = fs::rename(&file.staged, &file.dest) {
+                if let Some(previous) = backup.as_ref().or(replaced.as_ref()) {
+                    let _ = fs::rename(previous, &file.dest);
+                }
+                return Err(e);
+            }
+            Ok((backup, replaced))
+        })();
+        match result {
+            Ok((backup, replaced)) => placed.push(Committed {
+                file,
+                backup,
+                replaced,
+                created_dirs,
+            }),
+            Err(e) => {
+                for dir in &created_dirs {
+                    let _ = fs::remove_dir(dir);
+                }
+                for committed in placed.iter().rev() {
+                    let file = committed.file;
+                    let _ = fs::rename(&file.dest, &file.staged);
+                    if let Some(previous) =
+                        committed.backup.as_ref().or(committed.replaced.as_ref())
+                    {
+                        let _ = fs::rename(previous, &file.dest);
+                    }
+                    for dir in &committed.created_dirs {
+                        let _ = fs::remove_dir(dir);
+                    }
+                }
+                return discard(format!(
+                    "Failed to move '{}' into place, so nothing was placed or moved (--transactional): {e}",
+                    file.dest.display()
+                ));
+            }
+        }
+    }
+
+    for Committed {
+        file,
+        backup,
+        created_dirs,
+        ..
+    } in placed
+    {
+        if backup.is_some() {
+            ctx.backups.fetch_add(1, Ordering::Relaxed);
+        }
+        if let Some(made_from) = &file.made_from {
+            ctx.journal_made(made_from, &file.dest, created_dirs);
+        } else if let Some(source) = &file.source {
+            ctx.journal(source, &file.dest, file.moved, backup, created_dirs);
+            ctx.placed_files
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(
+                    file.dest.clone(),
+                    integrity::Placed {
+                        source: source.clone(),
+                        dest: file.dest.clone(),
+                        category_dir: file.category_dir.clone(),
+                        size: file.size,
+                        moved: file.moved,
+                    },
+                );
+        }
+        if let Some(checksums) = &ctx.checksums
+            && let Err(e) = checksums.add(&file.category_dir, &file.dest)
+        {
+            warn!("Failed to checksum '{}': {e}", file.dest.display());
+        }
+        if let Some(source) = file.source.as_ref().filter(|_| file.moved)
+            && let Err(e) = fs::remove_file(source)
+        {
+            warn!(
+                "Failed to remove '{}' after placing it: {e}",
+                source.display()
+            );
+        }
+    }
+    if let Err(e) = transaction.discard() {
+        warn!("Failed to remove the staging area: {e}");
+    }
+    true
+}
+
+fn get_blacklist(args: &Cli) -> std::result::Result<HashSet<String, RandomState>, DirsortError> {
+    load_blacklist(args)
+}
+
+fn get_config(path: &Option<String>) -> std::result::Result<SorterConfig, DirsortError> {
+    load_config(path.as_ref())
+}
+
+/// The options dirsort was started with, without its subcommand, for
+/// passing on to dirsort running in another directory. A relative
+/// `--config` is made absolute so it still points at the same file.
+fn forwarded_options(matches: &clap::ArgMatches) -> io::Result<Vec<String>> {
+    let mut forwarded = Vec::new();
+    for arg in Cli::command().get_arguments() {
+        let id = arg.get_id().as_str();
+        if matches.value_source(id) != Some(ValueSource::CommandLine) {
+            continue;
+        }
+        let flag = match (arg.get_long(), arg.get_short()) {
+            (Some(long), _) => format!("--{long}"),
+            (None, Some(short)) => format!("-{short}"),
+            (None, None) => continue,
+        };
+
+        match arg.get_action() {
+            ArgAction::SetTrue | ArgAction::SetFalse => forwarded.push(flag),
+            ArgAction::Count => {
+                forwarded.extend(std::iter::repeat_n(flag, matches.get_count(id).into()));
+            }
+            _ => {
+                for occurrence in matches.get_raw_occurrences(id).into_iter().flatten() {
+                    let mut values = Vec::new();
+                    for value in occurrence {
+                        let value = match id {
+                            "config" => std::path::absolute(value)?.into_os_string(),
+                            _ => value.to_os_string(),
+                        };
+                        values.push(value.to_string_lossy().into_owned());
+                    }
+                    // Joined with `=` so values starting with '-' aren't taken for options.
+                    match values.as_slice() {
+                        [value] if arg.get_long().is_some() => {
+                            forwarded.push(format!("{flag}={value}"));
+                        }
+                        _ => {
+                            forwarded.push(flag.clone());
+                            forwarded.extend(values);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(forwarded)
+}
+
+/// How `dirsort serve` and `--serve` serve the sorted directory.
+struct ServeOptions {
+    bind: SocketAddr,
+    /// Announce over mDNS, optionally under a name
+    mdns: Option<Option<String>>,
+    /// Regenerate the index and reload open index pages on changes
+    watch: bool,
+    cors: Option<String>,
+    log: bool,
+    /// Expose the management endpoints
+    manage: bool,
+}
+
+impl ServeOptions {
+    fn new(args: &Cli, watch: bool) -> Self {
+        Self {
+            bind: args.bind,
+            mdns: args.mdns.clone(),
+            watch,
+            cors: args.cors.clone(),
+            log: args.serve_log,
+            manage: args.serve_manage,
+        }
+    }
+}
+
+/// Serves `dir` until the process is stopped. The quarantine is never served.
+/// Responses are compressed, and browsers revalidate them with their ETag or
+/// modification time, since a sorted directory changes between runs.
+async fn serve(dir: PathBuf, options: ServeOptions) -> std::io::Result<()> {
+    let addr = options.bind;
+    info!("{}", t!("serving", url = format!("http://{addr}")));
+    let clients = web::Data::new(live::Clients::default());
+    if options.watch {
+        live::watch(dir.clone(), clients.clone());
+    }
+    let manager = options.manage.then(|| {
+        let manager = manage::Manager::new(dir.clone());
+        info!(
+            "Management endpoints enabled under '{}', token: {}",
+            manage::MANAGE_PATH,
+            manager.token()
+        );
+        web::Data::new(manager)
+    });
+    let ServeOptions {
+        cors,
+        log,
+        mdns: announce,
+        ..
+    } = options;
+    let server = HttpServer::new(move || {
+        let root = dir.clone();
+        let mut headers = DefaultHeaders::new().add(("Cache-Control", "no-cache"));
+        if let Some(origin) = &cors {
+            headers = headers.add(("Access-Control-Allow-Origin", origin.as_str()));
+        }
+
+        App::new()
+            .wrap(from_fn(media::content_types))
+            .wrap(Compress::default())
+            .wrap(headers)
+            .wrap(Condition::new(log, from_fn(access_log::log)))
+            .app_data(clients.clone())
+            .app_data(web::Data::new(dir.clone()))
+            .route(live::EVENTS_PATH, web::get().to(live::events))
+            .route(
+                overview::CATEGORIES_PATH,
+                web::get().to(overview::categories),
+            )
+            .route(
+                &format!("{}/{{path:.*}}", media::PLAY_PATH),
+                web::get().to(media::player),
+            )
+            .configure(|config| {
+                if let Some(manager) = &manager {
+                    config.service(
+                        web::scope(manage::MANAGE_PATH)
+                            .app_data(manager.clone())
+                            .route("/delete", web::post().to(manage::delete))
+                            .route("/rename", web::post().to(manage::rename))
+                            .route("/recategorize", web::post().to(manage::recategorize)),
+                    );
+                }
+            })
+            .service(
+                // Range requests are supported, so media can be seeked.
+                Files::new("/", &dir)
+                    .path_filter(move |path, _| !in_quarantine(&root, path))
+                    .prefer_utf8(true)
+                    .use_etag(true)
+                    .use_last_modified(true)
+                    .show_files_listing()
+                    .index_file("index.html"),
+            )
+    })
+    .bind(addr)
+    .map_err(|source| DirsortError::Serve {
+        addr: addr.to_string(),
+        source,
+    });
+    match server {
+        Ok(server) => {
+            qr::print(addr);
+            // Dropping the daemon when the server stops withdraws the announcement.
+            let _announcement = announce.and_then(|name| {
+                mdns::announce(name.as_deref(), addr)
+                    .inspect_err(|e| warn!("Failed to announce over mDNS: {e}"))
+                    .ok()
+            });
+            server.run().await
+        }
+        Err(e) => {
+            error!("{e}");
+            process::exit(1);
+        }
+    }
+}
+
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    // `--help` is printed while parsing, so the language has to be known first.
+    i18n::init(i18n::requested_lang().as_deref());
+    let matches = i18n::localize(Cli::command()).get_matches();
+    let mut args = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    logging::init(args.log_format);
+    index::set_theme(args.index_theme);
+
+    if args.gen_docs {
+        println!("{}", help_markdown::<Cli>());
+        process::exit(1);
+    }
+
+    if let Some(Command::Init) = args.command {
+        if let Err(e) = init::run() {
+            error!("Setup failed: {e}");
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Serve { dir, watch }) = &args.command {
+        if !dir.is_dir() {
+            error!("'{}' is not a directory", dir.display());
+            process::exit(1);
+        }
+        let config = get_config(&args.config).unwrap_or_else(|e| {
+            error!("{e}");
+            process::exit(1);
+        });
+        icons::configure(config.icons);
+        if args.gen_html || args.index_auto {
+            gen_html_index(dir)?;
+        }
+        return serve(dir.clone(), ServeOptions::new(&args, *watch)).await;
+    }
+
+    if let Some(Command::Index { dir, json }) = &args.command {
+        if !dir.is_dir() {
+            error!("'{}' is not a directory", dir.display());
+            process::exit(1);
+        }
+        let config = get_config(&args.config).unwrap_or_else(|e| {
+            error!("{e}");
+            process::exit(1);
+        });
+        icons::configure(config.icons);
+        gen_html_index(dir)?;
+        if *json {
+            index::gen_json_index(dir)?;
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::ApplyRenames { plan }) = &args.command {
+        if let Err(e) = renames::apply(plan, args.mv, args.on_conflict) {
+            error!("Failed to apply '{}': {e}", plan.display());
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Undo { run, since }) = &args.command {
+        if let Err(e) = journal::undo(run.as_deref(), *since) {
+            error!("Undo failed: {e}");
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Simulate {
+        files,
+        profile,
+        seed,
+        keep,
+    }) = args.command
+    {
+        let result = forwarded_options(&matches)
+            .map_err(Into::into)
+            .and_then(|forwarded| simulate::run(files, profile, seed, keep, &forwarded));
+        if let Err(e) = result {
+            error!("Simulation failed: {e}");
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Integrate {
+        explorer,
+        finder,
+        remove,
+    }) = args.command
+    {
+        let result = forwarded_options(&matches)
+            .map_err(Into::into)
+            .and_then(|forwarded| match (explorer, finder) {
+                (true, _) => integrate::explorer(&forwarded, remove),
+                _ => integrate::finder(&forwarded, remove),
+            });
+        if let Err(e) = result {
+            error!("Integration failed: {e}");
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Log { action }) = &args.command {
+        let result = match action {
+            None => history::list(),
+            Some(LogAction::Show { id }) => history::show(id),
+        };
+        if let Err(e) = result {
+            error!("Failed to read the run history: {e}");
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Worker threads only inherit the priority if it is set before they start.
+    if let Some(nice) = args.nice
+        && let Err(e) = priority::set_nice(nice)
+    {
+        warn!("Failed to set nice value {nice}: {e}");
+    }
+    if let Some(ionice) = args.ionice
+        && let Err(e) = priority::set_io_priority(ionice)
+    {
+        warn!("Failed to lower the disk priority: {e}");
+    }
+
+    if let Err(e) = setup_thread_pool(args.threads) {
+        error!("Error configuring threads: {e}");
+        process::exit(1);
+    }
+
+    if cfg!(not(target_os = "macos"))
+        && (args.finder_tag || args.quarantine != QuarantinePolicy::Preserve)
+    {
+        warn!("{}", t!("macos-only"));
+    }
+
+    if cfg!(not(unix)) && (args.chmod.is_some() || args.dir_mode.is_some()) {
+        warn!("{}", t!("unix-only", options = "--chmod and --dir-mode"));
+    }
+
+    if cfg!(not(target_os = "linux")) && !args.preserve.is_empty() {
+        warn!("{}", t!("linux-only", options = "--preserve"));
+    }
+
+    let blacklist = get_blacklist(&args).unwrap_or_else(|e| {
+        error!("{e}");
+        process::exit(1);
+    });
+
+    if !blacklist.is_empty() {
+        let extensions = blacklist
+            .iter()
+            .map(|s| format!(".{s}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        info!("{}", t!("blacklisted", extensions = extensions));
+    }
+
+    let mut config = get_config(&args.config).unwrap_or_else(|e| {
+        error!("{e}");
+        process::exit(1);
+    });
+    icons::configure(config.icons.clone());
+
+    pause::listen();
+
+    if let Some(Command::Daemon) = args.command {
+        if let Err(e) = daemon::run(args.config.as_ref(), &config.watch, args.index_auto) {
+            error!("Daemon stopped: {e}");
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if args.only_categorized {
+        config.uncategorized.mode = UncategorizedMode::Skip;
+    }
+
+    if args.backup.is_some() && args.on_conflict != ConflictStrategy::Overwrite {
+        warn!("{}", t!("backup-ignored"));
+    }
+
+    if let Some(addr) = &args.scan
+        && let Err(e) = clamav::ping(addr)
+    {
+        error!("Failed to reach clamd: {e}");
+        process::exit(1);
+    }
+
+    if args.par2.is_some() && !par2::available() {
+        warn!(
+            "{}",
+            t!(
+                "tool-missing",
+                option = "--par2",
+                tool = "par2 (par2cmdline)"
+            )
+        );
+        args.par2 = None;
+    }
+
+    args.convert.retain(|conversion| {
+        let available = conversion.available();
+        if !available {
+            warn!(
+                "{}",
+                t!(
+                    "convert-unavailable",
+                    from = conversion.from.as_str(),
+                    conversion = conversion.to_string()
+                )
+            );
+        }
+        available
+    });
+
+    if args.video_tiers && !video::ffprobe_available() {
+        warn!(
+            "{}",
+            t!(
+                "tool-missing",
+                option = "--video-tiers",
+                tool = "ffprobe (FFmpeg)"
+            )
+        );
+        args.video_tiers = false;
+    }
+
+    if args.by_audio_language && !video::ffprobe_available() {
+        warn!(
+            "{}",
+            t!(
+                "tool-missing",
+                option = "--by-audio-language",
+                tool = "ffprobe (FFmpeg)"
+            )
+        );
+        args.by_audio_language = false;
+    }
+
+    // A rename plan is a dry run whose plan goes into a file, and a
+    // conflict listing one that only shows the conflicts.
+    if args.rename_plan.is_some() || args.list_conflicts {
+        args.dry_run = true;
+    }
+
+    if args.snapshot && args.mv {
+        warn!("{}", t!("copies-instead", option = "--snapshot"));
+        args.mv = false;
+    }
+
+    let sources = if args.sources.is_empty() {
+        vec![sources::current()]
+    } else {
+        args.sources.clone()
+    };
+
+    if args.assert_no_writes {
+        if args.mv {
+            warn!("{}", t!("copies-instead", option = "--assert-no-writes"));
+            args.mv = false;
+        }
+        if let Err(e) = readonly::protect(sources.iter().map(|source| source.path.as_path())) {
+            error!("Failed to resolve the source tree: {e}");
+            process::exit(1);
+        }
+
+        let out_dirs: Vec<&Path> = if args.output_dir.is_empty() {
+            vec![Path::new("sorted")]
+        } else {
+            args.output_dir.iter().map(Path::new).collect()
+        };
+        let backup_dir = match &args.backup {
+            Some(BackupMode::Dir(dir)) => Some(dir.as_path()),
+            _ => None,
+        };
+        let writable = out_dirs
+            .into_iter()
+            .chain(config.destinations.values().map(PathBuf::as_path))
+            .chain(config.quotas.values().filter_map(quotas::Quota::overflow))
+            .chain(backup_dir);
+        for dir in writable {
+            if readonly::is_protected(dir).unwrap_or(true) {
+                error!(
+                    "'{}' is inside the source tree, which --assert-no-writes keeps untouched; sort into a directory elsewhere with -o",
+                    dir.display()
+                );
+                process::exit(1);
+            }
+        }
+    }
+
+    let screenshot_patterns = match config.screenshots.as_ref().map(|screenshots| {
+        screenshots
+            .patterns
+            .iter()
+            .map(|pattern| glob::Pattern::new(pattern))
+            .collect::<std::result::Result<Vec<_>, _>>()
+    }) {
+        Some(Ok(patterns)) => patterns,
+        Some(Err(e)) => {
+            error!("Invalid screenshot pattern: {e}");
+            process::exit(1);
+        }
+        None => Vec::new(),
+    };
+
+    if let Some(Command::Bench {
+        files,
+        file_size,
+        json,
+    }) = args.command
+    {
+        // Copies are measured on the disk that would be sorted into.
+        let dir = args
+            .output_dir
+            .first()
+            .map_or_else(std::env::temp_dir, PathBuf::from);
+        let bench = bench::Bench {
+            config: &config,
+            screenshot_patterns: &screenshot_patterns,
+            dir: &dir,
+            files,
+            file_size: file_size.0,
+            threads: args.threads,
+            hash: args.hash,
+            mv: args.mv,
+            json,
+        };
+        if let Err(e) = bench::run(&bench) {
+            error!("Benchmark failed: {e}");
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Diff { dir_a, dir_b }) = &args.command {
+        if let Err(e) = diff::run(dir_a, dir_b, &config, &screenshot_patterns, args.hash) {
+            error!("Failed to compare directories: {e}");
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Ingest { device, eject }) = &args.command {
+        // The card keeps its files, so `ingest` can always be run again.
+        if args.mv {
+            error!("ingest only copies, --move can't be used with it");
+            process::exit(1);
+        }
+        let ingest = ingest::Ingest {
+            config: &config,
+            screenshot_patterns: &screenshot_patterns,
+            blacklist: &blacklist,
+            out_dir: Path::new(args.output_dir.first().map_or("sorted", String::as_str)),
+            hash: args.hash,
+            dry_run: args.dry_run,
+        };
+        if let Err(e) = ingest::run(&ingest, device.as_deref(), *eject) {
+            error!("Import failed: {e}");
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Classify { name }) = &args.command {
+        let spooled = match classify::Spooled::from_stdin(name.as_deref()) {
+            Ok(spooled) => spooled,
+            Err(e) => {
+                error!("Failed to read stdin: {e}");
+                process::exit(1);
+            }
+        };
+        let path = spooled.path();
+        if is_blacklisted(path, &blacklist) {
+            println!("{LEFT_IN_PLACE}");
+            return Ok(());
+        }
+
+        let folder = planned_folder(path, &args, &config, &screenshot_patterns);
+        if folder == LEFT_IN_PLACE {
+            println!("{folder}");
+            return Ok(());
+        }
+        let out_dirs: Vec<PathBuf> = if args.output_dir.is_empty() {
+            vec![PathBuf::from("sorted")]
+        } else {
+            args.output_dir.iter().map(PathBuf::from).collect()
+        };
+        let size = path.metadata().map_or(0, |metadata| metadata.len());
+        let ext = path.extension().and_then(|ext| ext.to_str());
+        let categorized = categorize(path, ext, &config, &screenshot_patterns).is_some();
+        let folders = FolderNames::new(config.folders.clone(), config.folder_name.clone());
+        let on_disk = |root: &Path, folder: &str| match categorized {
+            true => folders.resolve(root, folder),
+            false => folder.to_string(),
+        };
+        let top_level = folder.split('/').next().unwrap_or_default();
+        let root =
+            Roots::new(&out_dirs, config.destinations.clone())
+                .pick(top_level, size, |root| on_disk(root, top_level));
+        let dest = root
+            .join(on_disk(&root, &folder))
+            .join(path.file_name().unwrap_or_default());
+        println!("{folder}\t{}", dest.display());
+        return Ok(());
+    }
+
+    let started = Local::now();
+    let source_dirs: Vec<PathBuf> = sources.iter().map(|source| source.path.clone()).collect();
+    let mut progress = if args.tui || args.confirm.is_some() {
+        Progress::Hidden
+    } else {
+        Progress::new(args.progress)
+    };
+    let collected = collect_files(&source_dirs, args.max_depth, &config, &progress);
+    progress.scanned();
+    let (mut entries, whole_dirs) = match collected {
+        Ok(collected) => collected,
+        Err(e) => {
+            error!("Error scanning files: {e}");
+            process::exit(1);
+        }
+    };
+
+    // Kept until the end of the run, the extracted files are removed with it.
+    let mut attachments = if args.extract_attachments {
+        let mut attachments = mail::Attachments::new()?;
+        let mut extracted = 0;
+        for entry in &entries {
+            let is_mail = entry
+                .path()
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(mail::is_mail);
+            if !is_mail {
+                continue;
+            }
+            match attachments.extract(entry.path()) {
+                Ok(count) => extracted += count,
+                Err(e) => warn!(
+                    "Failed to extract attachments from '{}': {e}",
+                    entry.path().display()
+                ),
+            }
+        }
+        info!("{}", t!("attachments-extracted", count = extracted));
+        entries.extend(
+            WalkDir::new(attachments.dir())
+                .into_iter()
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.file_type().is_file()),
+        );
+        Some(attachments)
+    } else {
+        None
+    };
+
+    let analyzed = || {
+        entries
+            .iter()
+            .filter(|entry| !is_blacklisted(entry.path(), &blacklist))
+            .map(|entry| {
+                let folder = planned_folder(entry.path(), &args, &config, &screenshot_patterns);
+                let size = entry.metadata().map_or(0, |metadata| metadata.len());
+                (entry.path(), folder, size)
+            })
+    };
+    match args.command {
+        Some(Command::Usage { format }) => {
+            let files = analyzed().map(|(path, folder, size)| {
+                let ext = path
+                    .extension()
+                    .map(|ext| ext.to_string_lossy().to_lowercase())
+                    .unwrap_or_default();
+                (folder, ext, size)
+            });
+            return usage::print(files, format);
+        }
+        Some(Command::Analyze { top }) => return usage::largest(analyzed(), top),
+        _ => {}
+    }
+
+    let order = match (
+        args.order,
+        args.limit.is_some() || args.limit_bytes.is_some(),
+    ) {
+        (None, true) => Some(ProcessOrder::OldestFirst),
+        (order, _) => order,
+    };
+    if let Some(order) = order {
+        order_entries(&mut entries, order);
+    }
+    if !config.priorities.is_empty() {
+        prioritize_entries(&mut entries, &config, &screenshot_patterns);
+    }
+
+    let seeding = config
+        .seeding
+        .as_ref()
+        .map(SeedingConfig::load)
+        .filter(|seeding| !seeding.is_empty());
+    if let Some(seeding) = &seeding
+        && seeding.torrents > 0
+    {
+        info!("{}", t!("torrents-found", count = seeding.torrents));
+    }
+
+    let open_files = (args.mv && args.in_use != InUsePolicy::Ignore).then(OpenFiles::snapshot);
+
+    if args.limit.is_some() || args.limit_bytes.is_some() {
+        let found = entries.len();
+        limit_batch(
+            &mut entries,
+            |entry| {
+                always_left(
+                    entry,
+                    &blacklist,
+                    &args,
+                    &config,
+                    &screenshot_patterns,
+                    seeding.as_ref(),
+                    open_files.as_ref(),
+                )
+            },
+            args.limit,
+            args.limit_bytes.map(|limit| limit.0),
+        );
+        if entries.len() < found {
+            info!("{}", t!("limited", count = entries.len(), found = found));
+        }
+    }
+
+    if entries.is_empty() && whole_dirs.is_empty() {
+        warn!("{}", t!("no-files"));
+        return Ok(());
+    }
+
+    let mut out_dirs: Vec<PathBuf> = if args.output_dir.is_empty() {
+        vec![PathBuf::from("sorted")]
+    } else {
+        args.output_dir.iter().map(PathBuf::from).collect()
+    };
+    let snapshot = args.snapshot.then(|| {
+        for dir in out_dirs.iter_mut().chain(config.destinations.values_mut()) {
+            *dir = snapshot::folder(dir, started);
+        }
+        let folders: Vec<PathBuf> = out_dirs
+            .iter()
+            .chain(config.destinations.values())
+            .cloned()
+            .collect();
+        Snapshot::new(&folders)
+    });
+    let out_dir = out_dirs[0].clone();
+    let errors = Arc::new(Mutex::new(Vec::new()));
+    let skipped = Arc::new(AtomicU64::new(0));
+
+    if !args.dry_run && io::stdin().is_terminal() && io::stderr().is_terminal() {
+        estimate_run(
+            &entries,
+            &blacklist,
+            &args,
+            &config,
+            &screenshot_patterns,
+            &out_dir,
+        )
+        .print();
+        if !args.yes && !estimate::confirm() {
+            info!("{}", t!("stopped-before"));
+            return Ok(());
+        }
+    }
+
+    for dir in out_dirs.iter().chain(config.destinations.values()) {
+        if !args.dry_run
+            && let Err(e) = create_dir_all(dir)
+        {
+            error!(
+                "Failed to create output directory '{}': {}",
+                dir.to_str().unwrap(),
+                e
+            );
+            mail::discard();
+            process::exit(1);
+        }
+    }
+
+    if !args.dry_run {
+        let removed: usize = out_dirs
+            .iter()
+            .chain(config.destinations.values())
+            .map(|dir| remove_stale_temp_files(dir))
+            .sum();
+        if removed > 0 {
+            info!("{}", t!("removed-stale", count = removed));
+        }
+    }
+
+    if args.mv
+        && !args.dry_run
+        && let Some((same, other)) =
+            volumes::split_by_volume(entries.iter().map(|entry| entry.path()), &out_dir)
+    {
+        if other == 0 {
+            info!(
+                "{}",
+                t!("same-filesystem", dir = out_dir.display().to_string())
+            );
+        } else {
+            let seconds = other / volumes::ASSUMED_COPY_RATE;
+            warn!(
+                "{}",
+                t!(
+                    "other-filesystem",
+                    size = tui::human_bytes(other),
+                    dir = out_dir.display().to_string(),
+                    minutes = seconds / 60,
+                    seconds = seconds % 60,
+                    rate = tui::human_bytes(volumes::ASSUMED_COPY_RATE),
+                    renamed = tui::human_bytes(same),
+                )
+            );
+        }
+    }
+
+    let operation = if args.mv { "move" } else { "copy" };
+    info!(
+        "{}",
+        t!(
+            "starting",
+            operation = operation,
+            count = entries.len(),
+            dir = out_dir.display().to_string(),
+        )
+    );
+
+    if !config.categories.is_empty() {
+        info!("{}", t!("loaded-categories"));
+        for (cat, exts) in &config.categories {
+            info!("  {cat}: {exts:?}");
+        }
+    }
+
+    let journal = if args.dry_run {
+        None
+    } else {
+        match Journal::create(&started) {
+            Ok(journal) => Some(journal),
+            Err(e) => {
+                warn!("{}", t!("journal-failed", error = e.to_string()));
+                None
+            }
+        }
+    };
+    let run_id = journal.as_ref().map_or_else(
+        || started.format("%Y%m%d-%H%M%S").to_string(),
+        |journal| journal.id().to_string(),
+    );
+
+    let events =
+        args.mqtt
+            .as_deref()
+            .and_then(|url| match mqtt::Events::connect(url, &args.mqtt_topic) {
+                Ok(events) => Some(events),
+                Err(e) => {
+                    warn!("Failed to set up MQTT: {e}");
+                    None
+                }
+            });
+    let dir = std::env::current_dir().unwrap_or_default();
+    if let Some(events) = &events {
+        events.publish(&serde_json::json!({
+            "event": "started",
+            "run": run_id,
+            "dir": dir,
+            "files": entries.len(),
+            "dry_run": args.dry_run,
+        }));
+    }
+
+    let mut ctx = SortContext {
+        args: &args,
+        blacklist: &blacklist,
+        config: &config,
+        primaries: index_primaries(&entries, &config.sidecars),
+        screenshot_patterns,
+        placements: Placements::new(&out_dir),
+        roots: Roots::new(&out_dirs, config.destinations.clone()),
+        folders: FolderNames::new(config.folders.clone(), config.folder_name.clone()),
+        quotas: Quotas::new(&config.quotas),
+        over_quota: AtomicU64::new(0),
+        journal,
+        uncategorized_skipped: AtomicU64::new(0),
+        incomplete: AtomicU64::new(0),
+        seeding,
+        seeding_skipped: AtomicU64::new(0),
+        open_files,
+        deferred: Mutex::new(Vec::new()),
+        retrying: AtomicBool::new(false),
+        in_use: Mutex::new(Vec::new()),
+        backups: AtomicU64::new(0),
+        moved_by_rename: AtomicU64::new(0),
+        moved_by_copy: AtomicU64::new(0),
+        rename_counter: AtomicU64::new(0),
+        bursts: if args.group_bursts {
+            find_bursts(&entries, &config, args.burst_gap)
+        } else {
+            HashMap::new()
+        },
+        near_duplicates: if args.dedup_perceptual {
+            find_near_duplicates(&entries, &config)
+        } else {
+            HashMap::new()
+        },
+        detections: Mutex::new(Vec::new()),
+        dashboard: args
+            .tui
+            .then(|| tui::Dashboard::start(entries.len() as u64)),
+        plan: args.dry_run.then(Plan::default),
+        confirmer: args.confirm.map(Confirmer::new),
+        report: args.report.map(|_| Report::default()),
+        checksums: args.emit_checksums.map(Checksums::new),
+        category_dirs: Mutex::new(HashSet::new()),
+        snapshot,
+        sources,
+        transaction: (args.transactional && !args.dry_run).then(|| {
+            let roots = out_dirs
+                .iter()
+                .chain(config.destinations.values())
+                .cloned()
+                .chain(
+                    config
+                        .quotas
+                        .values()
+                        .filter_map(quotas::Quota::overflow)
+                        .map(Path::to_path_buf),
+                )
+                .collect();
+            Transaction::new(roots, &run_id)
+        }),
+        placed_files: Mutex::new(HashMap::new()),
+        extracted: attachments
+            .as_mut()
+            .map(mail::Attachments::take_sources)
+            .unwrap_or_default(),
+    };
+
+    progress.begin(entries.len() as u64);
+    let handle = |entry: &walkdir::DirEntry| {
+        progress.start(entry.path());
+        process_file(entry, &ctx, &errors, &skipped);
+        match &ctx.dashboard {
+            Some(dashboard) => dashboard.advance(),
+            None => progress.inc(),
+        }
+    };
+
+    let ordered = order.is_some() || !config.priorities.is_empty();
+    let devices = volumes::group_by_device(&entries);
+    if devices.len() > 1 && args.device_threads > 0 {
+        info!(
+            "Files are on {} devices, using {} workers for each",
+            devices.len(),
+            args.device_threads
+        );
+        thread::scope(|scope| {
+            for device in &devices {
+                let pool = rayon::ThreadPoolBuilder::new()
+                    .num_threads(args.device_threads)
+                    .build();
+                scope.spawn(|| match pool {
+                    Ok(pool) => pool.install(|| for_each_entry(device, ordered, handle)),
+                    Err(_) => for_each_entry(device, ordered, handle),
+                });
+            }
+        });
+    } else {
+        for_each_entry(&entries.iter().collect::<Vec<_>>(), ordered, handle);
+    }
+
+    progress.finish();
+    if let Some(dashboard) = ctx.dashboard.take()
+        && let Err(e) = dashboard.finish()
+    {
+        error!("Dashboard failed: {e}");
+    }
+
+    let deferred = std::mem::take(&mut *ctx.deferred.lock().unwrap());
+    if !deferred.is_empty() {
+        info!("{}", t!("retrying-in-use", count = deferred.len()));
+        ctx.open_files = Some(OpenFiles::snapshot());
+        ctx.retrying.store(true, Ordering::Relaxed);
+        for entry in &deferred {
+            process_file(entry, &ctx, &errors, &skipped);
+        }
+    }
+
+    let committed = match &ctx.transaction {
+        Some(transaction) => {
+            let failed = errors.lock().map_or(true, |errors| !errors.is_empty());
+            commit(&ctx, transaction, failed)
+        }
+        None => true,
+    };
+
+    // Directories are moved as a whole, so there is nothing to stage.
+    for dir in whole_dirs.iter().filter(|_| committed) {
+        if let Some(folder) = &dir.folder {
+            process_dir(&dir.entry, folder, &ctx, &errors);
+        }
+    }
+
+    let placed_files = std::mem::take(&mut *ctx.placed_files.lock().unwrap());
+    let damaged = match committed {
+        true => check_integrity(placed_files.into_values().collect(), &ctx),
+        false => Vec::new(),
+    };
+
+    if let Some(snapshot) = &ctx.snapshot {
+        let linked = snapshot.linked.load(Ordering::Relaxed);
+        if linked > 0 {
+            info!("{}", t!("snapshot-linked", count = linked));
+        }
+    }
+
+    let recounted = match args.dry_run {
+        true => Vec::new(),
+        false => ctx.folders.recount(),
+    };
+    for (old, new) in &recounted {
+        if let Err(e) = journal::relocate(old, Some(new)) {
+            warn!("Failed to update the journal for '{}': {e}", new.display());
+        }
+        if let Some(report) = &ctx.report {
+            report.relocate(old, new);
+        }
+        if let Some(checksums) = &ctx.checksums {
+            checksums.relocate(old, new);
+        }
+    }
+
+    // After the recount, so '<folder>.sfv' has the folder's final name.
+    if let Some(checksums) = &ctx.checksums
+        && !args.dry_run
+    {
+        match checksums.write() {
+            Ok(count) => {
+                info!("{}", t!("checksums-written", count = count))
+            }
+            Err(e) => {
+                error!("Failed to write checksum files: {e}")
+            }
+        }
+    }
+
+    // After the recount, so the sets are named after the folders' final names.
+    if let Some(redundancy) = args.par2
+        && !args.dry_run
+    {
+        let dirs = ctx.category_dirs.lock().unwrap_or_else(|e| e.into_inner());
+        info!("{}", t!("par2-creating", count = dirs.len()));
+        for dir in dirs.iter() {
+            let dir = recounted
+                .iter()
+                .find_map(|(old, new)| {
+                    let rest = dir.strip_prefix(old).ok()?;
+                    Some(journal::relocated(new, rest))
+                })
+                .unwrap_or_else(|| dir.clone());
+            if let Err(e) = par2::create(&dir, redundancy) {
+                error!(
+                    "Failed to create recovery data for '{}': {e}",
+                    dir.display()
+                );
+            }
+        }
+    }
+
+    if let Some(plan) = &ctx.plan {
+        let printed = match args.list_conflicts {
+            true => plan.print_conflicts(),
+            false => plan.print(&out_dir, args.mv),
+        };
+        if let Err(e) = printed {
+            error!("Failed to print the plan: {e}");
+        }
+    }
+
+    if let (Some(plan), Some(path)) = (&ctx.plan, &args.rename_plan) {
+        match plan.write_renames(path) {
+            Ok(()) => info!(
+                "{}",
+                t!("rename-plan-written", path = path.display().to_string())
+            ),
+            Err(e) => error!("Failed to write the rename plan '{}': {e}", path.display()),
+        }
+    }
+
+    if !args.dry_run
+        && !ctx.near_duplicates.is_empty()
+        && let Err(e) = write_near_duplicates_report(&out_dir, &ctx.near_duplicates)
+    {
+        error!("Failed to write near-duplicates report: {e}");
+    }
+
+    let mut report_path = None;
+    if let Some(report) = &ctx.report
+        && !args.dry_run
+    {
+        let path = out_dir.join("dirsort-report.csv");
+        match report.write_csv(&path) {
+            Ok(()) => {
+                info!(
+                    "{}",
+                    t!("report-written", path = path.display().to_string())
+                );
+                report_path = fs::canonicalize(&path).ok();
+            }
+            Err(e) => error!("Failed to write report: {e}"),
+        }
+    }
+
+    // An index from an earlier run is kept current even without --index.
+    if !args.dry_run
+        && let Err(e) = index::refresh(&out_dir, args.gen_html || args.index_auto)
+    {
+        error!("Failed to generate html index: {e}");
+    }
+
+    let skipped_count = skipped.load(Ordering::Relaxed);
+    let conflict_count = ctx.placements.conflicts.load(Ordering::Relaxed);
+    let uncategorized_count = ctx.uncategorized_skipped.load(Ordering::Relaxed);
+    let processed_count = entries.len() as u64 - skipped_count - uncategorized_count;
+
+    if let Ok(errors_vec) = errors.lock()
+        && !errors_vec.is_empty()
+    {
+        if args.verbose {
+            error!("{}", t!("errors-listed"));
+            for error in errors_vec.iter() {
+                error!("  {error}");
+            }
+        }
+        info!("{}", t!("errors-completed", count = errors_vec.len()));
+        for (kind, count) in errors::summarize(&errors_vec) {
+            info!("  {kind}: {count}");
+        }
+        if !args.verbose {
+            info!("{}", t!("errors-hint"));
+        }
+    }
+
+    info!("{}", t!("summary"));
+    info!("  {}", t!("summary-processed", count = processed_count));
+    if !damaged.is_empty() {
+        error!("  {}", t!("summary-damaged", count = damaged.len()));
+        for file in &damaged {
+            error!("    {} ({})", file.placed.dest.display(), file.problem);
+        }
+        if !args.repair {
+            error!("  {}", t!("summary-damaged-hint"));
+        }
+    }
+    if skipped_count > 0 {
+        info!("  {}", t!("summary-blacklisted", count = skipped_count));
+    }
+    let placed_dirs = whole_dirs.iter().filter(|dir| dir.folder.is_some()).count();
+    if placed_dirs > 0 {
+        info!("  {}", t!("summary-dirs-placed", count = placed_dirs));
+    }
+    if whole_dirs.len() > placed_dirs {
+        info!(
+            "  {}",
+            t!(
+                "summary-dirs-projects",
+                count = whole_dirs.len() - placed_dirs
+            )
+        );
+    }
+    if uncategorized_count > 0 {
+        info!(
+            "  {}",
+            t!("summary-uncategorized", count = uncategorized_count)
+        );
+    }
+    if let Ok(in_use) = ctx.in_use.lock()
+        && !in_use.is_empty()
+    {
+        warn!("  {}", t!("summary-in-use", count = in_use.len()));
+        for path in in_use.iter() {
+            warn!("    {}", path.display());
+        }
+    }
+    let seeding_count = ctx.seeding_skipped.load(Ordering::Relaxed);
+    if seeding_count > 0 {
+        info!("  {}", t!("summary-seeding", count = seeding_count));
+    }
+    let incomplete_count = ctx.incomplete.load(Ordering::Relaxed);
+    if incomplete_count > 0 {
+        info!("  {}", t!("summary-incomplete", count = incomplete_count));
+    }
+    if let Ok(detections) = ctx.detections.lock()
+        && !detections.is_empty()
+    {
+        warn!(
+            "  {}",
+            t!(
+                "summary-infected",
+                dir = QUARANTINE_DIR,
+                count = detections.len()
+            )
+        );
+        for (path, signature) in detections.iter() {
+            warn!("    {}: {signature}", path.display());
+        }
+    }
+    if conflict_count > 0 {
+        info!("  {}", t!("summary-conflicts", count = conflict_count));
+    }
+    let over_quota_count = ctx.over_quota.load(Ordering::Relaxed);
+    if over_quota_count > 0 {
+        warn!("  {}", t!("summary-over-quota", count = over_quota_count));
+    }
+    let moved_by_rename = ctx.moved_by_rename.load(Ordering::Relaxed);
+    let moved_by_copy = ctx.moved_by_copy.load(Ordering::Relaxed);
+    if moved_by_rename > 0 {
+        info!(
+            "  {}",
+            t!("summary-renamed", size = tui::human_bytes(moved_by_rename))
+        );
+    }
+    if moved_by_copy > 0 {
+        info!(
+            "  {}",
+            t!(
+                "summary-copied-across",
+                size = tui::human_bytes(moved_by_copy)
+            )
+        );
+    }
+    let backup_count = ctx.backups.load(Ordering::Relaxed);
+    if backup_count > 0 {
+        info!("  {}", t!("summary-backed-up", count = backup_count));
+    }
+    if let Some(confirmer) = &ctx.confirmer {
+        let declined = confirmer.declined.load(Ordering::Relaxed);
+        if declined > 0 {
+            info!("  {}", t!("summary-declined", count = declined));
+        }
+        if confirmer.quit() {
+            info!("  {}", t!("summary-stopped"));
+        }
+    }
+
+    info!("  {}", t!("summary-total", count = entries.len()));
+
+    if let Some(events) = events {
+        if let Ok(errors) = errors.lock() {
+            for error in errors.iter() {
+                let (source, dest) = error.paths();
+                events.publish(&serde_json::json!({
+                    "event": "error",
+                    "run": run_id,
+                    "kind": error.kind(),
+                    "source": source,
+                    "dest": dest,
+                    "message": error.message(),
+                }));
+            }
+        }
+        events.publish(&serde_json::json!({
+            "event": "finished",
+            "run": run_id,
+            "dir": dir,
+            "duration_secs": (Local::now() - started).as_seconds_f64(),
+            "found": entries.len(),
+            "processed": processed_count,
+            "skipped": skipped_count,
+            "uncategorized": uncategorized_count,
+            "conflicts": conflict_count,
+            "errors": errors.lock().map_or(0, |errors| errors.len()),
+        }));
+        events.finish();
+    }
+
+    if !args.dry_run {
+        let run = history::Run {
+            id: run_id,
+            started: started.to_rfc3339(),
+            duration_secs: (Local::now() - started).as_seconds_f64(),
+            dir,
+            args: std::env::args().skip(1).collect(),
+            found: entries.len() as u64,
+            processed: processed_count,
+            skipped: skipped_count,
+            uncategorized: uncategorized_count,
+            conflicts: conflict_count,
+            errors: errors
+                .lock()
+                .map(|errors| errors.iter().map(ToString::to_string).collect())
+                .unwrap_or_default(),
+            report: report_path,
+            journal: ctx
+                .journal
+                .as_ref()
+                .map(|journal| journal.path().to_path_buf()),
+        };
+        if let Err(e) = history::record(&run) {
+            warn!("Failed to record the run: {e}");
+        }
+    }
+
+    if args.serve && !args.dry_run {
+        return serve(out_dir, ServeOptions::new(&args, false)).await;
     }
 
     if args.notify {
-        let operation = if args.mv { "moving" } else { "sorting" };
+        let operation = if args.mv { "move" } else { "sort" };
         send_finished_notif(operation);
     }
 
@@ -0,0 +1,226 @@
+use {
+    crate::{in_quarantine, index, journal, move_file},
+    actix_web::{
+        HttpRequest, HttpResponse,
+        http::{StatusCode, header::AUTHORIZATION},
+        web,
+    },
+    serde::Deserialize,
+    std::{
+        env, fs, io,
+        path::{Component, Path, PathBuf},
+    },
+    tracing::{info, warn},
+};
+
+/// Where the management endpoints are served, followed by the action.
+pub const MANAGE_PATH: &str = "/_dirsort/manage";
+
+/// State for `--serve-manage`: the served directory and the token every
+/// management request has to send as `Authorization: Bearer <token>`.
+pub struct Manager {
+    root: PathBuf,
+    token: String,
+}
+
+impl Manager {
+    /// Uses `DIRSORT_TOKEN` as the token if it is set, or else a random one.
+    pub fn new(root: PathBuf) -> Self {
+        let token = env::var("DIRSORT_TOKEN")
+            .ok()
+            .filter(|token| !token.is_empty())
+            .unwrap_or_else(random_token);
+        Self { root, token }
+    }
+
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    fn authorized(&self, req: &HttpRequest) -> bool {
+        let given = req
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .unwrap_or_default();
+        // Compares every byte, so the time taken doesn't give the token away.
+        given.len() == self.token.len()
+            && given
+                .bytes()
+                .zip(self.token.bytes())
+                .fold(0, |diff, (a, b)| diff | (a ^ b))
+                == 0
+    }
+
+    /// Resolves a path relative to the served directory, refusing anything
+    /// that would leave it or reach into the quarantine, also by way of a
+    /// symlink.
+    fn resolve(&self, relative: &str) -> Result<PathBuf, Refusal> {
+        let invalid = || (StatusCode::BAD_REQUEST, "invalid path".to_string());
+        let relative = Path::new(relative.trim_start_matches('/'));
+        let safe = relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+        if !safe || relative.as_os_str().is_empty() {
+            return Err(invalid());
+        }
+
+        let path = self.root.join(relative);
+        // What doesn't exist yet is checked by the folder it would be made in.
+        let real = path
+            .ancestors()
+            .find(|dir| dir.exists())
+            .and_then(|dir| dir.canonicalize().ok())
+            .ok_or_else(invalid)?;
+        let root = self.root.canonicalize().map_err(internal)?;
+        match real.starts_with(&root) && !in_quarantine(&root, relative) {
+            true => Ok(path),
+            false => Err(invalid()),
+        }
+    }
+}
+
+fn random_token() -> String {
+    let mut bytes = [0; 16];
+    if let Err(e) = getrandom::fill(&mut bytes) {
+        eprintln!("Failed to generate a management token: {e}");
+        std::process::exit(1);
+    }
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// A failed management request, as the status and body to answer with.
+type Refusal = (StatusCode, String);
+
+fn internal(e: io::Error) -> Refusal {
+    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
+
+/// Runs `work` on the blocking thread pool, as it touches the filesystem,
+/// and answers with its outcome.
+async fn run(work: impl FnOnce() -> Result<(), Refusal> + Send + 'static) -> HttpResponse {
+    match web::block(work).await {
+        Ok(Ok(())) => HttpResponse::NoContent().finish(),
+        Ok(Err((status, body))) => HttpResponse::build(status).body(body),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct Delete {
+    path: String,
+}
+
+#[derive(Deserialize)]
+pub struct Rename {
+    path: String,
+    /// The new file name, in the same folder
+    name: String,
+}
+
+#[derive(Deserialize)]
+pub struct Recategorize {
+    path: String,
+    /// The category folder to move the file into, e.g. 'Documents'
+    category: String,
+}
+
+/// `POST /_dirsort/manage/delete` with `{"path": "Images/a.jpg"}`
+pub async fn delete(
+    manager: web::Data<Manager>,
+    req: HttpRequest,
+    body: web::Json<Delete>,
+) -> HttpResponse {
+    if !manager.authorized(&req) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let body = body.into_inner();
+    run(move || {
+        let path = manager.resolve(&body.path)?;
+        if !path.is_file() {
+            return Err((StatusCode::NOT_FOUND, String::new()));
+        }
+        done(
+            &manager,
+            fs::remove_file(&path).and_then(|()| journal::relocate(&path, None)),
+            format!("Deleted '{}'", body.path),
+        )
+    })
+    .await
+}
+
+/// `POST /_dirsort/manage/rename` with `{"path": "Images/a.jpg", "name": "b.jpg"}`
+pub async fn rename(
+    manager: web::Data<Manager>,
+    req: HttpRequest,
+    body: web::Json<Rename>,
+) -> HttpResponse {
+    if !manager.authorized(&req) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    if body.name.contains(['/', '\\']) || matches!(body.name.as_str(), "" | "." | "..") {
+        return HttpResponse::BadRequest().body("invalid name");
+    }
+    let body = body.into_inner();
+    run(move || {
+        let path = manager.resolve(&body.path)?;
+        let dest = path.with_file_name(&body.name);
+        relocate(&manager, &path, &dest)
+    })
+    .await
+}
+
+/// `POST /_dirsort/manage/recategorize` with `{"path": "Images/a.pdf", "category": "Documents"}`
+pub async fn recategorize(
+    manager: web::Data<Manager>,
+    req: HttpRequest,
+    body: web::Json<Recategorize>,
+) -> HttpResponse {
+    if !manager.authorized(&req) {
+        return HttpResponse::Unauthorized().finish();
+    }
+    let body = body.into_inner();
+    run(move || {
+        let path = manager.resolve(&body.path)?;
+        let folder = manager.resolve(&body.category)?;
+        let name = path
+            .file_name()
+            .ok_or((StatusCode::BAD_REQUEST, "invalid path".to_string()))?;
+        let dest = folder.join(name);
+        relocate(&manager, &path, &dest)
+    })
+    .await
+}
+
+fn relocate(manager: &Manager, path: &Path, dest: &Path) -> Result<(), Refusal> {
+    if !path.is_file() {
+        return Err((StatusCode::NOT_FOUND, String::new()));
+    }
+    if fs::symlink_metadata(dest).is_ok() {
+        return Err((
+            StatusCode::CONFLICT,
+            "the destination already exists".to_string(),
+        ));
+    }
+
+    let result = dest
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|()| move_file(path, dest))
+        .and_then(|_| journal::relocate(path, Some(dest)));
+    done(
+        manager,
+        result,
+        format!("Moved '{}' to '{}'", path.display(), dest.display()),
+    )
+}
+
+fn done(manager: &Manager, result: io::Result<()>, done: String) -> Result<(), Refusal> {
+    result.map_err(internal)?;
+    info!("{done}");
+    if let Err(e) = index::refresh(&manager.root, false) {
+        warn!("Failed to regenerate the index: {e}");
+    }
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use {
+    mdns_sd::{ServiceDaemon, ServiceInfo},
+    std::net::SocketAddr,
+    tracing::{info, warn},
+};
+
+const SERVICE_TYPE: &str = "_http._tcp.local.";
+
+/// Announces the server at `addr` as `name`, by default "dirsort on <host>".
+/// The announcement lasts as long as the returned daemon.
+pub fn announce(name: Option<&str>, addr: SocketAddr) -> Result<ServiceDaemon, mdns_sd::Error> {
+    if addr.ip().is_loopback() {
+        warn!(
+            "Announcing a server that only listens on {}, other devices can't reach it; try --bind 0.0.0.0:{}",
+            addr.ip(),
+            addr.port()
+        );
+    }
+
+    let host = hostname::get()
+        .ok()
+        .and_then(|host| host.into_string().ok())
+        .unwrap_or_else(|| "dirsort".to_string());
+    let name = name.map_or_else(|| format!("dirsort on {host}"), str::to_string);
+    let host_name = format!("{}.local.", host.split('.').next().unwrap_or(&host));
+
+    let properties = [("path", "/")];
+    let info = if addr.ip().is_unspecified() {
+        // Listening everywhere, so every address of the host is announced.
+        ServiceInfo::new(
+            SERVICE_TYPE,
+            &name,
+            &host_name,
+            (),
+            addr.port(),
+            &properties[..],
+        )?
+        .enable_addr_auto()
+    } else {
+        ServiceInfo::new(
+            SERVICE_TYPE,
+            &name,
+            &host_name,
+            addr.ip(),
+            addr.port(),
+            &properties[..],
+        )?
+    };
+
+    let daemon = ServiceDaemon::new()?;
+    daemon.register(info)?;
+    info!("Announced as '{name}' over mDNS");
+    Ok(daemon)
+}
@@ -0,0 +1,132 @@
+use {
+    actix_web::{
+        Error, HttpRequest, HttpResponse,
+        body::MessageBody,
+        dev::{ServiceRequest, ServiceResponse},
+        http::header::{CONTENT_ENCODING, CONTENT_TYPE, HeaderValue},
+        middleware::Next,
+    },
+    std::path::Path,
+};
+
+/// Where the player page for a file is served, followed by the file's path.
+pub const PLAY_PATH: &str = "/_dirsort/play";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Audio,
+    Video,
+}
+
+/// Content types browsers can play, including the ones `mime_guess` gets
+/// wrong for them (e.g. `audio/m4a`, which Safari refuses).
+const MEDIA_TYPES: &[(&str, &str, Kind)] = &[
+    ("mp3", "audio/mpeg", Kind::Audio),
+    ("m4a", "audio/mp4", Kind::Audio),
+    ("aac", "audio/aac", Kind::Audio),
+    ("flac", "audio/flac", Kind::Audio),
+    ("wav", "audio/wav", Kind::Audio),
+    ("ogg", "audio/ogg", Kind::Audio),
+    ("oga", "audio/ogg", Kind::Audio),
+    ("opus", "audio/ogg", Kind::Audio),
+    ("weba", "audio/webm", Kind::Audio),
+    ("mp4", "video/mp4", Kind::Video),
+    ("m4v", "video/mp4", Kind::Video),
+    ("mov", "video/quicktime", Kind::Video),
+    ("webm", "video/webm", Kind::Video),
+    ("ogv", "video/ogg", Kind::Video),
+    ("mkv", "video/x-matroska", Kind::Video),
+];
+
+pub fn media_type(path: &str) -> Option<(&'static str, Kind)> {
+    let ext = Path::new(path).extension()?.to_str()?.to_lowercase();
+    MEDIA_TYPES
+        .iter()
+        .find(|(candidate, _, _)| *candidate == ext)
+        .map(|&(_, mime, kind)| (mime, kind))
+}
+
+/// Middleware that gives media files the content type from [`MEDIA_TYPES`]
+/// and keeps them from being compressed, which only costs time for them.
+pub async fn content_types(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let media = media_type(req.path());
+    let mut res = next.call(req).await?;
+    if let Some((mime, _)) = media
+        && res.status().is_success()
+    {
+        let headers = res.headers_mut();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static(mime));
+        headers.insert(CONTENT_ENCODING, HeaderValue::from_static("identity"));
+    }
+    Ok(res)
+}
+
+/// An HTML5 player for the file after [`PLAY_PATH`], so phones can stream
+/// it without a separate app.
+pub async fn player(req: HttpRequest) -> HttpResponse {
+    // Still percent-encoded, which is what the `src` attribute needs.
+    let src = req.path().strip_prefix(PLAY_PATH).unwrap_or_default();
+    let Some((mime, kind)) = media_type(src) else {
+        return HttpResponse::NotFound().finish();
+    };
+
+    let title = req
+        .match_info()
+        .query("path")
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    let tag = match kind {
+        Kind::Audio => "audio",
+        Kind::Video => "video",
+    };
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+    <title>{title}</title>
+    <style>
+        body {{ font-family: Arial, sans-serif; margin: 0; background: #111; color: #eee; }}
+        h1 {{ font-size: 1em; margin: 10px; word-break: break-all; }}
+        video, audio {{ width: 100%; max-height: 90vh; }}
+    </style>
+</head>
+<body>
+    <h1>{title}</h1>
+    <{tag} controls autoplay playsinline preload="metadata">
+        <source src="{src}" type="{mime}">
+    </{tag}>
+</body>
+</html>"#,
+            title = escape(&title),
+            src = escape(src),
+        ))
+}
+
+/// Percent-encodes `path` for use in a URL.
+pub fn encode_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-_.~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,161 @@
+use {
+    chrono::{DateTime, Local, NaiveDate, NaiveDateTime},
+    lofty::prelude::{Accessor, TaggedFileExt},
+    std::{
+        fs::File,
+        io::{BufReader, Read},
+        path::Path,
+    },
+};
+
+fn read_exif(path: &Path) -> Option<exif::Exif> {
+    let file = File::open(path).ok()?;
+    exif::Reader::new()
+        .read_from_container(&mut BufReader::new(file))
+        .ok()
+}
+
+/// When the photo was taken, according to its EXIF data.
+pub fn exif_date(path: &Path) -> Option<NaiveDateTime> {
+    let exif = read_exif(path)?;
+    let field = exif
+        .get_field(exif::Tag::DateTimeOriginal, exif::In::PRIMARY)
+        .or_else(|| exif.get_field(exif::Tag::DateTime, exif::In::PRIMARY))?;
+
+    let exif::Value::Ascii(ref raw) = field.value else {
+        return None;
+    };
+    let date = exif::DateTime::from_ascii(raw.first()?).ok()?;
+
+    NaiveDate::from_ymd_opt(date.year.into(), date.month.into(), date.day.into())?.and_hms_opt(
+        date.hour.into(),
+        date.minute.into(),
+        date.second.into(),
+    )
+}
+
+/// Where the photo was taken as latitude and longitude in degrees, according
+/// to its EXIF GPS data.
+pub fn gps_coordinates(path: &Path) -> Option<(f64, f64)> {
+    let exif = read_exif(path)?;
+    let coordinate = |tag, reference_tag, negative: &[u8]| {
+        let exif::Value::Rational(ref parts) = exif.get_field(tag, exif::In::PRIMARY)?.value else {
+            return None;
+        };
+        let [degrees, minutes, seconds] = parts.get(..3)? else {
+            return None;
+        };
+        let value = degrees.to_f64() + minutes.to_f64() / 60.0 + seconds.to_f64() / 3600.0;
+        let exif::Value::Ascii(ref reference) =
+            exif.get_field(reference_tag, exif::In::PRIMARY)?.value
+        else {
+            return None;
+        };
+        let is_negative = reference
+            .first()
+            .is_some_and(|reference| reference.as_slice() == negative);
+        value
+            .is_finite()
+            .then_some(if is_negative { -value } else { value })
+    };
+
+    Some((
+        coordinate(exif::Tag::GPSLatitude, exif::Tag::GPSLatitudeRef, b"S")?,
+        coordinate(exif::Tag::GPSLongitude, exif::Tag::GPSLongitudeRef, b"W")?,
+    ))
+}
+
+pub fn mtime(path: &Path) -> Option<NaiveDateTime> {
+    let modified = path.metadata().ok()?.modified().ok()?;
+    Some(DateTime::<Local>::from(modified).naive_local())
+}
+
+/// The best guess at when a file was created: the EXIF capture date if there
+/// is one, the modification time otherwise.
+pub fn file_date(path: &Path) -> Option<NaiveDateTime> {
+    exif_date(path).or_else(|| mtime(path))
+}
+
+#[derive(Default)]
+pub struct AudioTags {
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub title: Option<String>,
+    pub track: Option<u32>,
+    pub year: Option<u32>,
+}
+
+/// Reads the artist/album/title tags of an audio file (ID3, Vorbis
+/// comments, MP4 atoms, ...).
+pub fn audio_tags(path: &Path) -> Option<AudioTags> {
+    let tagged = lofty::read_from_path(path).ok()?;
+    let tag = tagged.primary_tag().or_else(|| tagged.first_tag())?;
+
+    Some(AudioTags {
+        artist: tag.artist().map(|s| s.into_owned()),
+        album: tag.album().map(|s| s.into_owned()),
+        title: tag.title().map(|s| s.into_owned()),
+        track: tag.track(),
+        year: tag.year(),
+    })
+}
+
+/// Collects the `tEXt` and uncompressed `iTXt` chunks of a PNG as
+/// `(keyword, text)` pairs, stopping at the image data.
+pub fn png_text(path: &Path) -> Vec<(String, String)> {
+    const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+    let mut chunks = Vec::new();
+    let Ok(file) = File::open(path) else {
+        return chunks;
+    };
+    let mut reader = BufReader::new(file);
+
+    let mut signature = [0; 8];
+    if reader.read_exact(&mut signature).is_err() || signature != SIGNATURE {
+        return chunks;
+    }
+
+    let mut header = [0; 8];
+    while reader.read_exact(&mut header).is_ok() {
+        let len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let kind = &header[4..8];
+
+        if kind == b"IDAT" || kind == b"IEND" || len > 1 << 24 {
+            break;
+        }
+
+        // Chunk data followed by its CRC.
+        let mut data = vec![0; len + 4];
+        if reader.read_exact(&mut data).is_err() {
+            break;
+        }
+        data.truncate(len);
+
+        let Some(nul) = data.iter().position(|&b| b == 0) else {
+            continue;
+        };
+        let keyword = String::from_utf8_lossy(&data[..nul]).into_owned();
+
+        match kind {
+            b"tEXt" => {
+                chunks.push((
+                    keyword,
+                    data[nul + 1..].iter().map(|&b| char::from(b)).collect(),
+                ));
+            }
+            // Compression flag, method, then NUL-terminated language tag and
+            // translated keyword.
+            b"iTXt" if data.get(nul + 1) == Some(&0) => {
+                let text = data[nul + 3..]
+                    .splitn(3, |&b| b == 0)
+                    .nth(2)
+                    .unwrap_or_default();
+                chunks.push((keyword, String::from_utf8_lossy(text).into_owned()));
+            }
+            _ => {}
+        }
+    }
+
+    chunks
+}
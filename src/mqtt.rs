@@ -0,0 +1,80 @@
+use {
+    rumqttc::{Client, MqttOptions, QoS},
+    serde_json::Value,
+    std::{
+        error::Error,
+        process,
+        thread::{self, JoinHandle},
+        time::{Duration, Instant},
+    },
+    tracing::warn,
+};
+
+/// How long finishing waits for queued events to reach the broker.
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Publishes run events as JSON to an MQTT topic, for `--mqtt`.
+pub struct Events {
+    client: Client,
+    topic: String,
+    connection: JoinHandle<()>,
+}
+
+impl Events {
+    /// Connects to `url`, e.g. "tcp://broker:1883" or "mqtts://broker". The
+    /// connection is made in the background, a broker that can't be reached
+    /// only costs the events.
+    pub fn connect(url: &str, topic: &str) -> Result<Self, Box<dyn Error>> {
+        let url = if url.contains("client_id=") {
+            url.to_string()
+        } else {
+            let separator = if url.contains('?') { '&' } else { '?' };
+            format!("{url}{separator}client_id=dirsort-{}", process::id())
+        };
+        let options = MqttOptions::parse_url(url)?;
+        let (client, mut connection) = Client::new(options, 64);
+
+        // The connection runs its own runtime, which can't be nested in ours.
+        let connection = thread::spawn(move || {
+            for notification in connection.iter() {
+                if let Err(e) = notification {
+                    warn!("MQTT connection failed: {e}");
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            client,
+            topic: topic.to_string(),
+            connection,
+        })
+    }
+
+    /// Queues `event`; an `"event"` field should say what happened. Waits
+    /// while the queue is full, so a run with many errors doesn't lose any.
+    pub fn publish(&self, event: &Value) {
+        // The connection already warned when it gave up.
+        if self.connection.is_finished() {
+            return;
+        }
+        if let Err(e) = self.client.publish(
+            &self.topic,
+            QoS::AtLeastOnce,
+            false,
+            event.to_string().into_bytes(),
+        ) {
+            warn!("Failed to publish an MQTT event: {e}");
+        }
+    }
+
+    /// Disconnects once the queued events are sent, or gives up after a few seconds.
+    pub fn finish(self) {
+        // The connection still sends what was queued when this fails.
+        let _ = self.client.disconnect();
+        let started = Instant::now();
+        while !self.connection.is_finished() && started.elapsed() < FLUSH_TIMEOUT {
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use {
+    crate::index,
+    actix_web::{HttpResponse, web},
+    serde::Serialize,
+    std::{
+        collections::BTreeMap,
+        path::{Path, PathBuf},
+    },
+    walkdir::WalkDir,
+};
+
+/// Where `dirsort serve` answers with the statistics of each category.
+pub const CATEGORIES_PATH: &str = "/api/categories";
+
+/// How many files a category folder holds, and how large they are together.
+#[derive(Default, Serialize)]
+pub struct CategoryStats {
+    pub name: String,
+    pub files: u64,
+    pub bytes: u64,
+}
+
+/// Counts the files in each top-level folder of `dir`, which are the
+/// categories, sorted by name. What `index::is_hidden_at_root` leaves out
+/// of the index is left out here too, as are files directly in `dir`.
+pub fn collect(dir: &Path) -> Vec<CategoryStats> {
+    let mut categories: BTreeMap<String, CategoryStats> = BTreeMap::new();
+    for entry in WalkDir::new(dir)
+        .min_depth(1)
+        .into_iter()
+        .filter_entry(|entry| entry.depth() != 1 || !index::is_hidden_at_root(entry.file_name()))
+        .filter_map(Result::ok)
+        .filter(|entry| entry.depth() > 1 && entry.file_type().is_file())
+    {
+        let Some(category) = entry
+            .path()
+            .strip_prefix(dir)
+            .ok()
+            .and_then(|relative| relative.components().next())
+            .map(|category| category.as_os_str().to_string_lossy().into_owned())
+        else {
+            continue;
+        };
+        let stats = categories
+            .entry(category.clone())
+            .or_insert_with(|| CategoryStats {
+                name: category,
+                ..CategoryStats::default()
+            });
+        stats.files += 1;
+        stats.bytes += entry.metadata().map_or(0, |metadata| metadata.len());
+    }
+    categories.into_values().collect()
+}
+
+/// `GET /api/categories`: `[{"name": "Images", "files": 120, "bytes": 52428800}, ...]`
+pub async fn categories(dir: web::Data<PathBuf>) -> HttpResponse {
+    let dir = dir.get_ref().clone();
+    match web::block(move || collect(&dir)).await {
+        Ok(categories) => HttpResponse::Ok().json(categories),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
@@ -0,0 +1,122 @@
+use {
+    std::{
+        fs, io,
+        path::{Path, PathBuf},
+        process::Command,
+    },
+    walkdir::WalkDir,
+};
+
+/// How many bytes of paths one `par2` call is given, well below what a
+/// command line can take; bigger folders get a recovery set per batch.
+const ARGS_LIMIT: usize = 64 * 1024;
+
+pub fn available() -> bool {
+    Command::new("par2")
+        .arg("-V")
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+/// Parses `--par2`: how much recovery data to create, e.g. '10%'.
+pub fn parse_redundancy(redundancy: &str) -> Result<u8, String> {
+    let percent = redundancy
+        .trim()
+        .trim_end_matches('%')
+        .parse::<u8>()
+        .map_err(|_| format!("invalid redundancy '{redundancy}', expected e.g. '10%'"))?;
+    match percent {
+        1..=100 => Ok(percent),
+        _ => Err("redundancy must be between 1% and 100%".to_string()),
+    }
+}
+
+/// Creates `<folder name>.par2` recovery files for everything in `folder`,
+/// replacing the ones an earlier run made, since they no longer cover the
+/// folder's contents. Folders with too many files for one command line get
+/// `<folder name>.partN.par2` sets for the files beyond the first batch.
+pub fn create(folder: &Path, redundancy: u8) -> io::Result<()> {
+    let name = folder
+        .file_name()
+        .ok_or_else(|| io::Error::other("folder has no name"))?
+        .to_string_lossy()
+        .to_string();
+    remove_existing(folder, &name)?;
+
+    let files: Vec<PathBuf> = WalkDir::new(folder)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.into_path())
+        .collect();
+    if files.is_empty() {
+        return Ok(());
+    }
+
+    let mut batches: Vec<Vec<&PathBuf>> = Vec::new();
+    let mut batch_len = 0;
+    for file in &files {
+        let len = file.as_os_str().len() + 1;
+        match batches.last_mut() {
+            Some(batch) if batch_len + len <= ARGS_LIMIT => {
+                batch.push(file);
+                batch_len += len;
+            }
+            _ => {
+                batches.push(vec![file]);
+                batch_len = len;
+            }
+        }
+    }
+
+    for (index, batch) in batches.iter().enumerate() {
+        let set = match index {
+            0 => format!("{name}.par2"),
+            index => format!("{name}.part{}.par2", index + 1),
+        };
+        let output = Command::new("par2")
+            .arg("create")
+            .arg("-q")
+            .arg(format!("-r{redundancy}"))
+            .arg("-B")
+            .arg(folder)
+            .arg(folder.join(set))
+            .arg("--")
+            .args(batch)
+            .output()?;
+        if !output.status.success() {
+            return Err(io::Error::other(format!(
+                "par2 failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Removes `<name>.par2`, `<name>.partN.par2` and their `.volN+M.par2`
+/// volumes.
+fn remove_existing(folder: &Path, name: &str) -> io::Result<()> {
+    for entry in fs::read_dir(folder)? {
+        let entry = entry?;
+        if is_set(&entry.file_name().to_string_lossy(), name) {
+            fs::remove_file(entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+/// Whether `file_name` is `<name>[.partN].par2` or `<name>[.partN].volN+M.par2`.
+fn is_set(file_name: &str, name: &str) -> bool {
+    let Some(rest) = file_name
+        .strip_prefix(name)
+        .and_then(|rest| rest.strip_suffix(".par2"))
+    else {
+        return false;
+    };
+    let rest = match rest.strip_prefix(".part") {
+        Some(part) => part.trim_start_matches(|c: char| c.is_ascii_digit()),
+        None => rest,
+    };
+    rest.is_empty() || rest.starts_with(".vol")
+}
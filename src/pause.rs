@@ -0,0 +1,104 @@
+use {
+    crate::i18n::t,
+    std::sync::{
+        Condvar, Mutex,
+        atomic::{AtomicBool, AtomicU32, Ordering},
+    },
+    tracing::{info, warn},
+};
+
+/// Checked by `wait` without locking, so unpaused workers don't contend.
+static PAUSED: AtomicBool = AtomicBool::new(false);
+/// Held while `PAUSED` changes, so a resume can't slip in between a
+/// worker's check and its wait.
+static STATE: Mutex<()> = Mutex::new(());
+static RESUMED: Condvar = Condvar::new();
+
+/// The run `dirsort daemon` currently has going, which signals are passed on to.
+static CHILD: AtomicU32 = AtomicU32::new(0);
+
+/// Pauses on SIGUSR1 and resumes on SIGUSR2, for when a sort is saturating a
+/// disk that's needed for something else. Only on Unix.
+#[cfg(unix)]
+pub fn listen() {
+    use {
+        signal_hook::{
+            consts::{SIGUSR1, SIGUSR2},
+            iterator::Signals,
+        },
+        std::thread,
+    };
+
+    let mut signals = match Signals::new([SIGUSR1, SIGUSR2]) {
+        Ok(signals) => signals,
+        Err(e) => {
+            warn!("Failed to listen for SIGUSR1/SIGUSR2: {e}");
+            return;
+        }
+    };
+
+    thread::spawn(move || {
+        for received in signals.forever() {
+            set_paused(received == SIGUSR1);
+        }
+    });
+}
+
+#[cfg(not(unix))]
+pub fn listen() {}
+
+/// Pauses or resumes this run, and the run `dirsort daemon` has going.
+pub fn set_paused(paused: bool) {
+    let state = STATE.lock().unwrap_or_else(|e| e.into_inner());
+    if PAUSED.swap(paused, Ordering::AcqRel) == paused {
+        return;
+    }
+    drop(state);
+    forward(paused);
+
+    if paused {
+        info!("{}", t!("paused", pid = std::process::id()));
+    } else {
+        info!("{}", t!("resumed"));
+        RESUMED.notify_all();
+    }
+}
+
+/// Blocks while the run is paused. Workers call this before each file, so
+/// files already being transferred are finished first.
+pub fn wait() {
+    if !PAUSED.load(Ordering::Acquire) {
+        return;
+    }
+    let state = STATE.lock().unwrap_or_else(|e| e.into_inner());
+    drop(
+        RESUMED
+            .wait_while(state, |_| PAUSED.load(Ordering::Acquire))
+            .unwrap_or_else(|e| e.into_inner()),
+    );
+}
+
+#[cfg(unix)]
+fn forward(paused: bool) {
+    use nix::{
+        sys::signal::{self, Signal},
+        unistd::Pid,
+    };
+
+    let child = CHILD.load(Ordering::Relaxed);
+    if child != 0 {
+        let received = match paused {
+            true => Signal::SIGUSR1,
+            false => Signal::SIGUSR2,
+        };
+        let _ = signal::kill(Pid::from_raw(child as i32), received);
+    }
+}
+
+#[cfg(not(unix))]
+fn forward(_paused: bool) {}
+
+/// Passes pause and resume signals on to `pid` from now on, or stops with `None`.
+pub fn forward_to(pid: Option<u32>) {
+    CHILD.store(pid.unwrap_or_default(), Ordering::Relaxed);
+}
@@ -0,0 +1,82 @@
+use {
+    image_hasher::{HashAlg, HasherConfig, ImageHash},
+    rayon::iter::{IntoParallelRefIterator, ParallelIterator},
+    std::{
+        collections::HashMap,
+        path::{Path, PathBuf},
+    },
+};
+
+/// Maximum Hamming distance between two hashes for the images to count as
+/// the same picture. Recompression and resizing typically stay well below it.
+const MAX_DISTANCE: u32 = 6;
+
+struct Hashed<'a> {
+    path: &'a Path,
+    hash: ImageHash,
+    pixels: u64,
+    bytes: u64,
+}
+
+fn find(parents: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    parents[i] = root;
+    root
+}
+
+/// Groups visually identical images (same picture at a different resolution
+/// or compression level) by their perceptual hash.
+///
+/// Returns every image that isn't the best copy of its group, mapped to the
+/// best copy, which is the one with the most pixels (then the biggest file).
+pub fn near_duplicates(paths: &[&Path]) -> HashMap<PathBuf, PathBuf> {
+    let hasher = HasherConfig::new()
+        .hash_alg(HashAlg::Mean)
+        .preproc_dct()
+        .to_hasher();
+
+    let hashed = paths
+        .par_iter()
+        .filter_map(|path| {
+            let image = image::open(path).ok()?;
+            Some(Hashed {
+                path,
+                hash: hasher.hash_image(&image),
+                pixels: u64::from(image.width()) * u64::from(image.height()),
+                bytes: path.metadata().map(|m| m.len()).unwrap_or(0),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut parents = (0..hashed.len()).collect::<Vec<_>>();
+    for i in 0..hashed.len() {
+        for j in i + 1..hashed.len() {
+            if hashed[i].hash.dist(&hashed[j].hash) <= MAX_DISTANCE {
+                let (a, b) = (find(&mut parents, i), find(&mut parents, j));
+                parents[a] = b;
+            }
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<&Hashed>> = HashMap::new();
+    for (i, image) in hashed.iter().enumerate() {
+        let root = find(&mut parents, i);
+        groups.entry(root).or_default().push(image);
+    }
+
+    let mut duplicates = HashMap::new();
+    for members in groups.values().filter(|members| members.len() > 1) {
+        let Some(best) = members.iter().max_by_key(|m| (m.pixels, m.bytes)) else {
+            continue;
+        };
+
+        for member in members.iter().filter(|m| m.path != best.path) {
+            duplicates.insert(member.path.to_path_buf(), best.path.to_path_buf());
+        }
+    }
+
+    duplicates
+}
@@ -0,0 +1,64 @@
+use {
+    crate::{Cli, Owner},
+    nix::unistd::{Group, User},
+    std::{
+        fs::{self, Permissions},
+        io::Result,
+        os::unix::fs::{PermissionsExt, chown},
+        path::Path,
+    },
+};
+
+pub fn lookup_uid(name: &str) -> std::result::Result<u32, String> {
+    if let Ok(uid) = name.parse() {
+        return Ok(uid);
+    }
+
+    User::from_name(name)
+        .map_err(|e| format!("Failed to look up user '{name}': {e}"))?
+        .map(|user| user.uid.as_raw())
+        .ok_or_else(|| format!("No such user '{name}'"))
+}
+
+pub fn lookup_gid(name: &str) -> std::result::Result<u32, String> {
+    if let Ok(gid) = name.parse() {
+        return Ok(gid);
+    }
+
+    Group::from_name(name)
+        .map_err(|e| format!("Failed to look up group '{name}': {e}"))?
+        .map(|group| group.gid.as_raw())
+        .ok_or_else(|| format!("No such group '{name}'"))
+}
+
+fn apply_owner(path: &Path, owner: Option<Owner>) -> Result<()> {
+    if let Some(owner) = owner {
+        chown(path, owner.uid, owner.gid)?;
+    }
+
+    Ok(())
+}
+
+/// Applies `--chmod` and `--chown` to a file that was just placed.
+pub fn apply_file_policy(path: &Path, args: &Cli) -> Result<()> {
+    if let Some(mode) = args.chmod {
+        fs::set_permissions(path, Permissions::from_mode(mode))?;
+    }
+
+    apply_owner(path, args.chown)
+}
+
+/// Applies `--dir-mode` and `--chown` to a category directory, so that the
+/// owner of the placed files can also manage the folders they live in.
+pub fn apply_dir_policy(path: &Path, args: &Cli) -> Result<()> {
+    if let Some(mode) = args.dir_mode {
+        fs::set_permissions(path, Permissions::from_mode(mode))?;
+    }
+
+    apply_owner(path, args.chown)
+}
+
+pub fn strip_exec_bits(path: &Path) -> Result<()> {
+    let mode = fs::metadata(path)?.permissions().mode();
+    fs::set_permissions(path, Permissions::from_mode(mode & !0o111))
+}
@@ -0,0 +1,42 @@
+use {
+    crate::{metadata, rename::sanitize},
+    reverse_geocoder::ReverseGeocoder,
+    std::{collections::HashMap, path::Path, sync::LazyLock},
+};
+
+/// Country names by ISO 3166 code, in the short form people file photos under.
+const COUNTRIES: &str = include_str!("countries.tsv");
+
+/// Loading the bundled GeoNames places takes a moment, so it only happens
+/// once a photo with GPS data turns up.
+static GEOCODER: LazyLock<ReverseGeocoder> = LazyLock::new(ReverseGeocoder::new);
+
+static COUNTRY_NAMES: LazyLock<HashMap<&str, &str>> = LazyLock::new(|| {
+    COUNTRIES
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .collect()
+});
+
+/// `--photo-by-place`: the `<country>/<region>` folder for a photo with GPS
+/// data, e.g. 'Japan/Tokyo', found offline against the nearest known place.
+pub fn folder(path: &Path) -> Option<String> {
+    let (latitude, longitude) = metadata::gps_coordinates(path)?;
+    // Cameras without a fix write 0,0, in the sea off Africa.
+    if !(-90.0..=90.0).contains(&latitude)
+        || !(-180.0..=180.0).contains(&longitude)
+        || (latitude == 0.0 && longitude == 0.0)
+    {
+        return None;
+    }
+    let place = GEOCODER.search((latitude, longitude)).record;
+
+    let country = COUNTRY_NAMES
+        .get(place.cc.as_str())
+        .map_or_else(|| sanitize(&place.cc), |name| sanitize(name));
+    let region = sanitize(&place.admin1);
+    Some(match region.is_empty() {
+        true => country,
+        false => format!("{country}/{region}"),
+    })
+}
@@ -0,0 +1,241 @@
+use {
+    crate::{renames, report},
+    std::{
+        collections::BTreeMap,
+        env,
+        fs::File,
+        io::{self, BufWriter, IsTerminal, Write},
+        path::{self, Path, PathBuf},
+        sync::Mutex,
+    },
+};
+
+/// What a placement would do to the destination.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Nothing is at the destination yet
+    New,
+    /// The destination is taken, so a numbered name is used instead
+    Rename,
+    /// The destination is replaced
+    Overwrite,
+    /// The destination is taken and the source is left alone
+    Skip,
+}
+
+impl Action {
+    fn marker(self) -> &'static str {
+        match self {
+            Action::New => "+",
+            Action::Rename => "~",
+            Action::Overwrite => "!",
+            Action::Skip => "=",
+        }
+    }
+
+    fn color(self) -> &'static str {
+        match self {
+            Action::New => "\x1b[32m",
+            Action::Rename => "\x1b[33m",
+            Action::Overwrite => "\x1b[31m",
+            Action::Skip => "\x1b[2m",
+        }
+    }
+}
+
+struct Planned {
+    source: PathBuf,
+    /// Where the file would go if nothing were in the way
+    wanted: PathBuf,
+    dest: PathBuf,
+    action: Action,
+}
+
+/// Placements collected during `--dry-run`, printed afterwards grouped by the
+/// top-level folder they would land in.
+#[derive(Default)]
+pub struct Plan {
+    entries: Mutex<Vec<Planned>>,
+}
+
+impl Plan {
+    pub fn record(&self, source: &Path, wanted: PathBuf, dest: PathBuf, action: Action) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.push(Planned {
+                source: source.to_path_buf(),
+                wanted,
+                dest,
+                action,
+            });
+        }
+    }
+
+    /// Prints the plan like `rsync -n`: green for new files, yellow for
+    /// renames, red for overwrites. Colors are left out when stdout isn't a
+    /// terminal or `NO_COLOR` is set.
+    pub fn print(&self, out_dir: &Path, mv: bool) -> io::Result<()> {
+        let entries = self
+            .entries
+            .lock()
+            .map_err(|_| io::Error::other("plan lock poisoned"))?;
+        let color = io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none();
+        let paint = |action: Action, text: &str| {
+            if color {
+                format!("{}{text}\x1b[0m", action.color())
+            } else {
+                text.to_string()
+            }
+        };
+
+        let mut groups: BTreeMap<String, Vec<&Planned>> = BTreeMap::new();
+        for planned in entries.iter() {
+            let relative = planned.dest.strip_prefix(out_dir).unwrap_or(&planned.dest);
+            let group = relative
+                .components()
+                .next()
+                .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                .unwrap_or_default();
+            groups.entry(group).or_default().push(planned);
+        }
+
+        let mut stdout = io::stdout().lock();
+        let verb = if mv { "move" } else { "copy" };
+        writeln!(stdout, "Dry run, nothing was changed. Would {verb}:")?;
+
+        for (group, mut planned) in groups {
+            planned.sort_by(|a, b| a.dest.cmp(&b.dest));
+            writeln!(stdout, "\n{group}/ ({})", planned.len())?;
+
+            for planned in planned {
+                let dest = planned.dest.strip_prefix(out_dir).unwrap_or(&planned.dest);
+                let note = match planned.action {
+                    Action::New => "",
+                    Action::Rename => " (renamed)",
+                    Action::Overwrite => " (overwrites)",
+                    Action::Skip => " (skipped, destination exists)",
+                };
+                let line = format!(
+                    "  {} {} -> {}{note}",
+                    planned.action.marker(),
+                    planned.source.display(),
+                    dest.display()
+                );
+                writeln!(stdout, "{}", paint(planned.action, &line))?;
+            }
+        }
+
+        let count = |action: Action| entries.iter().filter(|p| p.action == action).count();
+        writeln!(
+            stdout,
+            "\n{} new, {} renamed, {} overwritten, {} skipped",
+            paint(Action::New, &count(Action::New).to_string()),
+            paint(Action::Rename, &count(Action::Rename).to_string()),
+            paint(Action::Overwrite, &count(Action::Overwrite).to_string()),
+            paint(Action::Skip, &count(Action::Skip).to_string()),
+        )
+    }
+
+    /// Prints every destination that more than one file would go to, or
+    /// that already exists, with what `--on-conflict` would do about each
+    /// file.
+    pub fn print_conflicts(&self) -> io::Result<()> {
+        let entries = self
+            .entries
+            .lock()
+            .map_err(|_| io::Error::other("plan lock poisoned"))?;
+        let mut wanted: BTreeMap<&Path, Vec<&Planned>> = BTreeMap::new();
+        for planned in entries.iter() {
+            wanted.entry(&planned.wanted).or_default().push(planned);
+        }
+        // `Placements` also catches names that only differ in case on
+        // case-insensitive filesystems, which show up as single entries.
+        wanted.retain(|dest, planned| {
+            planned.len() > 1
+                || dest.exists()
+                || planned.iter().any(|planned| planned.action != Action::New)
+        });
+
+        let mut stdout = io::stdout().lock();
+        for (dest, planned) in wanted.iter_mut() {
+            planned.sort_by(|a, b| a.source.cmp(&b.source));
+            let exists = if dest.exists() {
+                ", already exists"
+            } else {
+                ""
+            };
+            let files = match planned.len() {
+                1 => "1 file".to_string(),
+                count => format!("{count} files"),
+            };
+            writeln!(stdout, "{} ({files}{exists})", dest.display())?;
+            for planned in planned.iter() {
+                let outcome = match planned.action {
+                    Action::New => "placed here".to_string(),
+                    Action::Rename => format!(
+                        "renamed to '{}'",
+                        planned
+                            .dest
+                            .file_name()
+                            .unwrap_or_default()
+                            .to_string_lossy()
+                    ),
+                    Action::Overwrite => "overwrites it".to_string(),
+                    Action::Skip => "left in place".to_string(),
+                };
+                writeln!(stdout, "  {} ({outcome})", planned.source.display())?;
+            }
+        }
+        match wanted.len() {
+            0 => writeln!(
+                stdout,
+                "No conflicts, every file has a destination of its own"
+            )?,
+            count => writeln!(
+                stdout,
+                "\n{count} destinations are wanted by more than one file or already exist"
+            )?,
+        }
+        Ok(())
+    }
+
+    /// Writes the plan as a rename plan for `dirsort apply-renames`: each
+    /// file with its current name, the name it would get and the folder it
+    /// would go into. Files that would be skipped are left out.
+    pub fn write_renames(&self, path: &Path) -> io::Result<()> {
+        let mut entries = self
+            .entries
+            .lock()
+            .map_err(|_| io::Error::other("plan lock poisoned"))?;
+        entries.sort_by(|a, b| a.source.cmp(&b.source));
+
+        let mut file = BufWriter::new(File::create(path)?);
+        writeln!(file, "{}", renames::HEADER.join(","))?;
+        for planned in entries
+            .iter()
+            .filter(|planned| planned.action != Action::Skip)
+        {
+            let name = |path: &Path| {
+                path.file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default()
+            };
+            // The plan is applied later, likely from somewhere else.
+            let source = path::absolute(&planned.source)?;
+            let dest = path::absolute(&planned.dest)?;
+            writeln!(
+                file,
+                "{},{},{},{}",
+                report::field(&source.display().to_string()),
+                report::field(&name(&source)),
+                report::field(&name(&dest)),
+                report::field(
+                    &dest
+                        .parent()
+                        .map(|dir| dir.display().to_string())
+                        .unwrap_or_default()
+                ),
+            )?;
+        }
+        file.flush()
+    }
+}
@@ -0,0 +1,119 @@
+use std::io;
+
+/// I/O scheduling class for `--ionice`.
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum IoPriority {
+    /// Only get disk time when nothing else wants it
+    Idle,
+}
+
+/// Lowers the CPU priority of the process by `nice` (-20 to 19, as with
+/// `nice(1)`). Has to run before any worker threads are started, since Linux
+/// only applies it to the calling thread and the threads it spawns later.
+#[cfg(unix)]
+pub fn set_nice(nice: i32) -> io::Result<()> {
+    use nix::libc;
+
+    // SAFETY: plain syscall, `who` 0 is the calling process.
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Windows only has priority classes, so positive values map to below normal
+/// and idle, and negative ones to above normal and high.
+#[cfg(windows)]
+pub fn set_nice(nice: i32) -> io::Result<()> {
+    use windows_sys::Win32::System::Threading::{
+        ABOVE_NORMAL_PRIORITY_CLASS, BELOW_NORMAL_PRIORITY_CLASS, GetCurrentProcess,
+        HIGH_PRIORITY_CLASS, IDLE_PRIORITY_CLASS, NORMAL_PRIORITY_CLASS, SetPriorityClass,
+    };
+
+    let class = match nice {
+        10.. => IDLE_PRIORITY_CLASS,
+        1..=9 => BELOW_NORMAL_PRIORITY_CLASS,
+        0 => NORMAL_PRIORITY_CLASS,
+        -9..=-1 => ABOVE_NORMAL_PRIORITY_CLASS,
+        _ => HIGH_PRIORITY_CLASS,
+    };
+    // SAFETY: `GetCurrentProcess` returns a pseudo handle that needs no closing.
+    if unsafe { SetPriorityClass(GetCurrentProcess(), class) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn set_nice(_nice: i32) -> io::Result<()> {
+    Err(io::Error::from(io::ErrorKind::Unsupported))
+}
+
+/// Like `set_nice`, on Linux this only covers threads spawned afterwards.
+#[cfg(target_os = "linux")]
+pub fn set_io_priority(priority: IoPriority) -> io::Result<()> {
+    use nix::libc;
+
+    const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+    const IOPRIO_CLASS_IDLE: libc::c_int = 3;
+    const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+
+    let class = match priority {
+        IoPriority::Idle => IOPRIO_CLASS_IDLE,
+    };
+    // SAFETY: plain syscall, `who` 0 is the calling thread.
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_ioprio_set,
+            IOPRIO_WHO_PROCESS,
+            0,
+            class << IOPRIO_CLASS_SHIFT,
+        )
+    };
+    if result == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Background mode lowers CPU, disk and network priority together.
+#[cfg(target_os = "macos")]
+pub fn set_io_priority(priority: IoPriority) -> io::Result<()> {
+    use nix::libc;
+
+    match priority {
+        IoPriority::Idle => {
+            // SAFETY: plain syscall, `who` 0 is the calling process.
+            if unsafe { libc::setpriority(libc::PRIO_DARWIN_PROCESS, 0, libc::PRIO_DARWIN_BG) }
+                == -1
+            {
+                return Err(io::Error::last_os_error());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Background mode lowers CPU, disk and memory priority together.
+#[cfg(windows)]
+pub fn set_io_priority(priority: IoPriority) -> io::Result<()> {
+    use windows_sys::Win32::System::Threading::{
+        GetCurrentProcess, PROCESS_MODE_BACKGROUND_BEGIN, SetPriorityClass,
+    };
+
+    match priority {
+        IoPriority::Idle => {
+            // SAFETY: `GetCurrentProcess` returns a pseudo handle that needs no closing.
+            if unsafe { SetPriorityClass(GetCurrentProcess(), PROCESS_MODE_BACKGROUND_BEGIN) } == 0
+            {
+                return Err(io::Error::last_os_error());
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn set_io_priority(_priority: IoPriority) -> io::Result<()> {
+    Err(io::Error::from(io::ErrorKind::Unsupported))
+}
@@ -0,0 +1,228 @@
+use {
+    indicatif::{MultiProgress, ProgressBar},
+    std::{
+        cell::RefCell,
+        io::{self, IsTerminal, Write},
+        path::Path,
+        sync::{
+            Mutex, OnceLock,
+            atomic::{AtomicU64, Ordering},
+        },
+        time::{Duration, Instant},
+    },
+    tracing::info,
+};
+
+/// How often the scan spinner turns while nothing else redraws it.
+const SPIN_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How many entries the scan finds between updates of its spinner.
+const SCAN_STEP: u64 = 64;
+
+/// How often `auto` logs a progress line when stderr isn't a terminal.
+const LINE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How progress is shown while sorting, with `--progress`.
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ProgressMode {
+    /// A progress bar, with a spinner for each worker showing the file it is on
+    Bar,
+    /// A dot for every percent done, without redrawing anything
+    Dots,
+    /// Nothing
+    None,
+    /// A bar in a terminal, otherwise a line every 10 seconds, as in logs from cron or CI
+    Auto,
+}
+
+thread_local! {
+    /// The spinner of the worker running on this thread, once it has one,
+    /// with the id of the bars it belongs to.
+    static WORKER: RefCell<Option<(u64, ProgressBar)>> = const { RefCell::new(None) };
+}
+
+/// Where every run's bars are drawn, shared with logging so log lines are
+/// written above the bars instead of through them.
+static MULTI: OnceLock<MultiProgress> = OnceLock::new();
+
+/// Numbers the bars, so a worker thread reused by a later run doesn't keep
+/// showing its spinner from an earlier one.
+static BARS: AtomicU64 = AtomicU64::new(0);
+
+/// Runs `write` with the bars taken off the screen while it writes to
+/// stderr, if any are shown.
+pub fn suspend<R>(write: impl FnOnce() -> R) -> R {
+    match MULTI.get() {
+        Some(multi) => multi.suspend(write),
+        None => write(),
+    }
+}
+
+/// The bars of `--progress bar`. Each of them is safe to update from any
+/// thread, so workers never wait on each other to show what they do.
+pub struct Bars {
+    id: u64,
+    multi: &'static MultiProgress,
+    scan: ProgressBar,
+    transfer: ProgressBar,
+    /// Every worker's spinner, to clear them at the end; only locked when a
+    /// worker shows up for the first time
+    workers: Mutex<Vec<ProgressBar>>,
+}
+
+/// Progress over the scan and the files of a run, shown as chosen with
+/// `--progress`.
+pub enum Progress {
+    Bars(Bars),
+    Dots {
+        done: AtomicU64,
+        per_dot: u64,
+    },
+    Lines {
+        done: AtomicU64,
+        total: u64,
+        last: Mutex<Instant>,
+    },
+    Hidden,
+}
+
+impl Progress {
+    /// Starts showing progress, beginning with the scan.
+    pub fn new(mode: ProgressMode) -> Self {
+        match mode {
+            ProgressMode::Auto if !io::stderr().is_terminal() => Progress::Lines {
+                done: AtomicU64::new(0),
+                total: 0,
+                last: Mutex::new(Instant::now()),
+            },
+            ProgressMode::Bar | ProgressMode::Auto => {
+                let multi = MULTI.get_or_init(MultiProgress::new);
+                let scan = multi.add(ProgressBar::new_spinner().with_message("Scanning..."));
+                scan.enable_steady_tick(SPIN_INTERVAL);
+                Progress::Bars(Bars {
+                    id: BARS.fetch_add(1, Ordering::Relaxed),
+                    multi,
+                    scan,
+                    transfer: ProgressBar::hidden(),
+                    workers: Mutex::new(Vec::new()),
+                })
+            }
+            ProgressMode::Dots => Progress::Dots {
+                done: AtomicU64::new(0),
+                per_dot: 1,
+            },
+            ProgressMode::None => Progress::Hidden,
+        }
+    }
+
+    /// Shows how many folders and files the scan has found so far.
+    pub fn scanning(&self, dirs: u64, files: u64) {
+        match self {
+            Progress::Bars(bars) if (dirs + files).is_multiple_of(SCAN_STEP) => {
+                bars.scan
+                    .set_message(format!("Scanning... {dirs} folders, {files} files"));
+            }
+            Progress::Lines { last, .. } => {
+                if let Ok(mut last) = last.try_lock()
+                    && last.elapsed() >= LINE_INTERVAL
+                {
+                    *last = Instant::now();
+                    info!("Scanning, found {dirs} folders and {files} files so far");
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The scan is over; its spinner is cleared before anything else is
+    /// logged.
+    pub fn scanned(&self) {
+        if let Progress::Bars(bars) = self {
+            bars.scan.finish_and_clear();
+        }
+    }
+
+    /// Files are about to be processed, `total` of them.
+    pub fn begin(&mut self, total: u64) {
+        match self {
+            Progress::Bars(bars) => bars.transfer = bars.multi.add(ProgressBar::new(total)),
+            Progress::Dots { per_dot, .. } => *per_dot = (total / 100).max(1),
+            Progress::Lines { total: lines, .. } => *lines = total,
+            Progress::Hidden => {}
+        }
+    }
+
+    /// Shows the file the current worker is on.
+    pub fn start(&self, path: &Path) {
+        let Progress::Bars(bars) = self else {
+            return;
+        };
+        WORKER.with_borrow_mut(|worker| {
+            if worker.as_ref().is_none_or(|(id, _)| *id != bars.id) {
+                let spinner = bars.multi.add(ProgressBar::new_spinner());
+                bars.workers
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .push(spinner.clone());
+                *worker = Some((bars.id, spinner));
+            }
+            let Some((_, spinner)) = worker else {
+                return;
+            };
+            spinner.set_message(
+                path.file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned(),
+            );
+            spinner.tick();
+        });
+    }
+
+    pub fn inc(&self) {
+        match self {
+            Progress::Bars(bars) => bars.transfer.inc(1),
+            Progress::Dots { done, per_dot } => {
+                if (done.fetch_add(1, Ordering::Relaxed) + 1).is_multiple_of(*per_dot) {
+                    let mut stderr = io::stderr().lock();
+                    let _ = write!(stderr, ".");
+                    let _ = stderr.flush();
+                }
+            }
+            Progress::Lines { done, total, last } => {
+                let done = done.fetch_add(1, Ordering::Relaxed) + 1;
+                // Workers that find the clock taken just carry on.
+                if let Ok(mut last) = last.try_lock()
+                    && last.elapsed() >= LINE_INTERVAL
+                {
+                    *last = Instant::now();
+                    info!(
+                        "Processed {done} of {total} files ({}%)",
+                        done * 100 / (*total).max(1)
+                    );
+                }
+            }
+            Progress::Hidden => {}
+        }
+    }
+
+    pub fn finish(&self) {
+        match self {
+            Progress::Bars(bars) => {
+                for spinner in bars
+                    .workers
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .iter()
+                {
+                    spinner.finish_and_clear();
+                }
+                bars.transfer.finish();
+            }
+            Progress::Dots { done, per_dot } if done.load(Ordering::Relaxed) >= *per_dot => {
+                eprintln!();
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use {
+    qrcode::{QrCode, render::unicode::Dense1x2},
+    std::{
+        io::{self, IsTerminal},
+        net::{IpAddr, SocketAddr, UdpSocket},
+    },
+    tracing::{info, warn},
+};
+
+/// The URL other devices can open the server at, or `None` when it only
+/// listens on loopback.
+pub fn reachable_url(addr: SocketAddr) -> Option<String> {
+    let ip = match addr.ip() {
+        ip if ip.is_loopback() => return None,
+        ip if ip.is_unspecified() => lan_ip()?,
+        ip => ip,
+    };
+    Some(format!("http://{}", SocketAddr::new(ip, addr.port())))
+}
+
+/// The address of the interface that routes to the outside. Connecting a UDP
+/// socket only picks the route, nothing is sent.
+fn lan_ip() -> Option<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("192.0.2.1:80").ok()?;
+    Some(socket.local_addr().ok()?.ip()).filter(|ip| !ip.is_loopback())
+}
+
+/// Prints a QR code of the URL the server can be reached at, so a phone can
+/// open it by pointing the camera at the terminal.
+pub fn print(addr: SocketAddr) {
+    if !io::stderr().is_terminal() {
+        return;
+    }
+    let Some(url) = reachable_url(addr) else {
+        info!(
+            "Only reachable from this machine, use --bind 0.0.0.0:{} to open it from a phone",
+            addr.port()
+        );
+        return;
+    };
+
+    match QrCode::new(&url) {
+        Ok(code) => {
+            // Dark terminals: the code is drawn in light on dark, so swap the colors.
+            let image = code
+                .render::<Dense1x2>()
+                .dark_color(Dense1x2::Light)
+                .light_color(Dense1x2::Dark)
+                .quiet_zone(true)
+                .build();
+            eprintln!("\n{image}\n");
+            info!("Scan to open {url}");
+        }
+        Err(e) => warn!("Failed to draw a QR code: {e}"),
+    }
+}
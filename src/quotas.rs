@@ -0,0 +1,142 @@
+use {
+    crate::{ByteSize, i18n::t, tui::human_bytes},
+    serde::{Deserialize, Serialize},
+    std::{
+        collections::HashMap,
+        path::{Path, PathBuf},
+        sync::Mutex,
+    },
+    tracing::warn,
+    walkdir::WalkDir,
+};
+
+/// A `[quotas]` entry capping how much a category's folder may hold.
+#[derive(Serialize, Deserialize)]
+pub struct Quota {
+    max_bytes: ByteSize,
+    /// Output root that files go into once the quota is reached; without
+    /// one they are left in place
+    overflow: Option<PathBuf>,
+}
+
+/// Where a file may go under its category's quota.
+pub enum Admission<'q> {
+    /// The file fits, and its bytes are counted until the reservation is
+    /// dropped without being kept
+    Fits(Reservation<'q>),
+    /// The category is full, the file goes into this root instead
+    Overflow(PathBuf),
+    Full,
+}
+
+/// Keeps track of how full the capped categories are: what their folder
+/// held before the run, plus what this run has placed there.
+pub struct Quotas<'a> {
+    quotas: &'a HashMap<String, Quota>,
+    used: Mutex<HashMap<String, Usage>>,
+}
+
+struct Usage {
+    bytes: u64,
+    full: bool,
+}
+
+/// The bytes `admit` counted against a quota for a file. Unless the file is
+/// placed and `keep` is called, they are given back when it is dropped, so
+/// skipped, declined and failed files don't use up the quota.
+pub struct Reservation<'q> {
+    quotas: &'q Quotas<'q>,
+    category: String,
+    size: u64,
+    kept: bool,
+}
+
+impl Reservation<'_> {
+    pub fn keep(mut self) {
+        self.kept = true;
+    }
+}
+
+impl Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        if self.kept {
+            return;
+        }
+        let mut used = self.quotas.used.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(usage) = used.get_mut(&self.category) {
+            usage.bytes = usage.bytes.saturating_sub(self.size);
+        }
+    }
+}
+
+impl Quota {
+    pub fn overflow(&self) -> Option<&Path> {
+        self.overflow.as_deref()
+    }
+}
+
+impl<'a> Quotas<'a> {
+    pub fn new(quotas: &'a HashMap<String, Quota>) -> Self {
+        Self {
+            quotas,
+            used: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether a `size`-byte file fits into `category`, whose folder is at
+    /// `folder`. Files that fit are counted against the quota.
+    pub fn admit(&'a self, category: &str, folder: &Path, size: u64) -> Admission<'a> {
+        let reservation = |size| Reservation {
+            quotas: self,
+            category: category.to_string(),
+            size,
+            kept: false,
+        };
+        let Some(quota) = self.quotas.get(category) else {
+            return Admission::Fits(reservation(0));
+        };
+
+        let mut used = self.used.lock().unwrap_or_else(|e| e.into_inner());
+        let usage = used.entry(category.to_string()).or_insert_with(|| Usage {
+            bytes: folder_size(folder),
+            full: false,
+        });
+        // Once full, the category stays full, so a run doesn't flip between
+        // the two destinations for smaller files.
+        if !usage.full && usage.bytes + size <= quota.max_bytes.0 {
+            usage.bytes += size;
+            return Admission::Fits(reservation(size));
+        }
+
+        if !usage.full {
+            usage.full = true;
+            let quota_size = human_bytes(quota.max_bytes.0);
+            warn!(
+                "{}",
+                match &quota.overflow {
+                    Some(overflow) => t!(
+                        "quota-overflow",
+                        category = category,
+                        size = quota_size,
+                        dir = overflow.display().to_string()
+                    ),
+                    None => t!("quota-full", category = category, size = quota_size),
+                }
+            );
+        }
+        match &quota.overflow {
+            Some(overflow) => Admission::Overflow(overflow.clone()),
+            None => Admission::Full,
+        }
+    }
+}
+
+fn folder_size(folder: &Path) -> u64 {
+    WalkDir::new(folder)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
@@ -0,0 +1,69 @@
+use std::{
+    fs, io,
+    path::{self, Path, PathBuf},
+    sync::OnceLock,
+};
+
+/// The source trees `--assert-no-writes` protects.
+static SOURCES: OnceLock<Vec<PathBuf>> = OnceLock::new();
+
+/// Refuses every later write under `sources`, checked with `check`.
+pub fn protect<'a>(sources: impl IntoIterator<Item = &'a Path>) -> io::Result<()> {
+    let sources = sources
+        .into_iter()
+        .map(resolve)
+        .collect::<io::Result<Vec<_>>>()?;
+    SOURCES.get_or_init(|| sources);
+    Ok(())
+}
+
+/// Whether `path` is inside a protected source tree.
+pub fn is_protected(path: &Path) -> io::Result<bool> {
+    match SOURCES.get() {
+        Some(sources) => {
+            let path = resolve(path)?;
+            Ok(sources.iter().any(|source| path.starts_with(source)))
+        }
+        None => Ok(false),
+    }
+}
+
+/// Fails if `path` is inside the protected source tree, before anything is
+/// written there.
+pub fn check(path: &Path) -> io::Result<()> {
+    if is_protected(path)? {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!(
+                "refusing to write to '{}' in the source tree (--assert-no-writes)",
+                path.display()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// `path` with symlinks resolved as far as it exists, so a link pointing back
+/// into the source can't get around the check.
+fn resolve(path: &Path) -> io::Result<PathBuf> {
+    let path = path::absolute(path)?;
+    let mut existing = path.as_path();
+    let mut rest = Vec::new();
+    loop {
+        match fs::canonicalize(existing) {
+            Ok(resolved) => {
+                return Ok(rest
+                    .iter()
+                    .rev()
+                    .fold(resolved, |path, part| path.join(part)));
+            }
+            Err(_) => match (existing.parent(), existing.file_name()) {
+                (Some(parent), Some(name)) => {
+                    rest.push(name.to_os_string());
+                    existing = parent;
+                }
+                _ => return Ok(path.clone()),
+            },
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use {
+    crate::{
+        hashing::{self, HashAlgorithm},
+        metadata::{self, AudioTags},
+    },
+    serde::{Deserialize, Serialize},
+    std::{fmt, io, path::Path, str::FromStr},
+};
+
+#[derive(Clone)]
+enum Token {
+    Literal(String),
+    /// EXIF capture date, falling back to the modification time
+    Date,
+    /// File name without the extension
+    Stem,
+    /// Stem with copy markers like ` (copy)` or ` (2)` removed
+    Clean,
+    /// Original file name
+    Name,
+    /// Extension, cased according to `--normalize-ext-case`
+    Ext,
+    /// Running number, zero-padded to four digits
+    Counter,
+    /// Folder the file is being sorted into
+    Category,
+    /// First `n` hex digits of the file's hash
+    Hash(usize),
+    /// Audio tags, read from the file
+    Artist,
+    Album,
+    Title,
+    Track,
+    Year,
+}
+
+/// A destination file name pattern such as `{date}_{stem}_{hash8}.{ext}`.
+///
+/// Templates may contain `/` to place files in nested folders, e.g.
+/// `{artist}/{album}/{track} - {title}.{ext}`.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct RenameTemplate {
+    source: String,
+    tokens: Vec<Token>,
+}
+
+impl TryFrom<String> for RenameTemplate {
+    type Error = TemplateError;
+
+    fn try_from(template: String) -> Result<Self, Self::Error> {
+        template.parse()
+    }
+}
+
+impl From<RenameTemplate> for String {
+    fn from(template: RenameTemplate) -> Self {
+        template.source
+    }
+}
+
+#[derive(Debug)]
+pub struct TemplateError(String);
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+impl FromStr for RenameTemplate {
+    type Err = TemplateError;
+
+    fn from_str(template: &str) -> Result<Self, Self::Err> {
+        let mut tokens = Vec::new();
+        let mut rest = template;
+
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                tokens.push(Token::Literal(rest[..start].to_string()));
+            }
+
+            let end = rest[start..]
+                .find('}')
+                .ok_or_else(|| TemplateError(format!("Unclosed '{{' in '{template}'")))?;
+            let name = &rest[start + 1..start + end];
+
+            tokens.push(match name {
+                "date" => Token::Date,
+                "stem" => Token::Stem,
+                "clean" => Token::Clean,
+                "name" => Token::Name,
+                "ext" => Token::Ext,
+                "counter" => Token::Counter,
+                "category" => Token::Category,
+                "artist" => Token::Artist,
+                "album" => Token::Album,
+                "title" => Token::Title,
+                "track" => Token::Track,
+                "year" => Token::Year,
+                "hash" => Token::Hash(64),
+                _ => match name.strip_prefix("hash").map(str::parse) {
+                    Some(Ok(len)) if len > 0 => Token::Hash(len),
+                    _ => return Err(TemplateError(format!("Unknown token '{{{name}}}'"))),
+                },
+            });
+
+            rest = &rest[start + end + 1..];
+        }
+
+        if !rest.is_empty() {
+            tokens.push(Token::Literal(rest.to_string()));
+        }
+
+        Ok(Self {
+            source: template.to_string(),
+            tokens,
+        })
+    }
+}
+
+/// What a template can refer to for a single file.
+pub struct NameFields<'a> {
+    pub path: &'a Path,
+    pub file_name: &'a str,
+    pub stem: &'a str,
+    pub ext: Option<&'a str>,
+    pub category: &'a str,
+    pub counter: u64,
+    pub hash: HashAlgorithm,
+}
+
+/// Strips the noise that browsers and file managers add to duplicate names,
+/// e.g. `IMG_20240101 (copy) (2)` becomes `IMG_20240101`.
+fn clean_stem(stem: &str) -> &str {
+    let mut stem = stem.trim();
+
+    loop {
+        let before = stem;
+
+        if let Some(inner) = stem.strip_suffix(')')
+            && let Some(open) = inner.rfind('(')
+        {
+            let marker = &inner[open + 1..];
+            if marker.eq_ignore_ascii_case("copy") || marker.bytes().all(|b| b.is_ascii_digit()) {
+                stem = inner[..open].trim_end();
+            }
+        }
+
+        for suffix in [" - copy", " copy"] {
+            if stem.len() > suffix.len()
+                && stem.is_char_boundary(stem.len() - suffix.len())
+                && stem[stem.len() - suffix.len()..].eq_ignore_ascii_case(suffix)
+            {
+                stem = stem[..stem.len() - suffix.len()].trim_end();
+            }
+        }
+
+        if stem == before {
+            return stem;
+        }
+    }
+}
+
+/// Keeps values read from file metadata from introducing extra path
+/// components or characters Windows can't store.
+pub fn sanitize(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>()
+        .trim()
+        .trim_matches('.')
+        .to_string()
+}
+
+impl RenameTemplate {
+    pub fn render(&self, fields: &NameFields) -> io::Result<String> {
+        let stem = fields.stem;
+        let mut hash = None;
+        let mut tags: Option<AudioTags> = None;
+        let mut rendered = String::new();
+
+        for token in &self.tokens {
+            match token {
+                Token::Literal(text) => rendered.push_str(text),
+                Token::Date => {
+                    if let Some(date) = metadata::file_date(fields.path) {
+                        rendered.push_str(&date.format("%Y-%m-%d").to_string());
+                    }
+                }
+                Token::Stem => rendered.push_str(stem),
+                Token::Clean => rendered.push_str(clean_stem(stem)),
+                Token::Name => rendered.push_str(fields.file_name),
+                Token::Ext => rendered.push_str(fields.ext.unwrap_or_default()),
+                Token::Counter => rendered.push_str(&format!("{:04}", fields.counter)),
+                Token::Category => rendered.push_str(fields.category),
+                Token::Hash(len) => {
+                    if hash.is_none() {
+                        hash = Some(hashing::hash_file(fields.path, fields.hash)?);
+                    }
+                    let hash = hash.as_deref().unwrap_or_default();
+                    rendered.push_str(&hash[..(*len).min(hash.len())]);
+                }
+                Token::Artist | Token::Album | Token::Title | Token::Track | Token::Year => {
+                    let tags = tags.get_or_insert_with(|| {
+                        metadata::audio_tags(fields.path).unwrap_or_default()
+                    });
+                    let value = match token {
+                        Token::Artist => tags.artist.clone(),
+                        Token::Album => tags.album.clone(),
+                        Token::Title => tags.title.clone(),
+                        Token::Track => tags.track.map(|track| format!("{track:02}")),
+                        _ => tags.year.map(|year| year.to_string()),
+                    };
+                    let value = value.map(|value| sanitize(&value));
+
+                    rendered.push_str(match (token, value.as_deref()) {
+                        (_, Some(value)) if !value.is_empty() => value,
+                        (Token::Artist, _) => "Unknown Artist",
+                        (Token::Album, _) => "Unknown Album",
+                        (Token::Title, _) => stem,
+                        (Token::Track, _) => "00",
+                        _ => "Unknown Year",
+                    });
+                }
+            }
+        }
+
+        // A missing extension would otherwise leave a dangling dot behind.
+        Ok(rendered.trim_end_matches('.').to_string())
+    }
+}
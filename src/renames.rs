@@ -0,0 +1,179 @@
+use {
+    crate::{
+        ConflictStrategy, Placements, copy_file, history,
+        journal::{self, Journal},
+        move_file,
+        plan::Action,
+    },
+    chrono::Local,
+    std::{
+        fs, io,
+        path::{Path, PathBuf},
+        sync::atomic::Ordering,
+    },
+    tracing::{error, info, warn},
+};
+
+/// The columns of a rename plan, in the order `--rename-plan` writes them.
+pub const HEADER: [&str; 4] = ["source", "old_name", "new_name", "destination"];
+
+/// One row of a rename plan: the file, and where it goes under which name.
+struct Rename {
+    source: PathBuf,
+    dest: PathBuf,
+}
+
+/// Splits CSV text into rows of fields, per RFC 4180: fields may be quoted,
+/// and quoted fields may hold commas, doubled quotes and line breaks.
+/// Spreadsheets save with `\r\n`, which is accepted as well.
+fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            ('"', true) => quoted = false,
+            ('"', false) if field.is_empty() => quoted = true,
+            (',', false) => row.push(std::mem::take(&mut field)),
+            ('\r', false) if chars.peek() == Some(&'\n') => {}
+            ('\n', false) => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            (c, _) => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows.retain(|row| row.iter().any(|field| !field.is_empty()));
+    rows
+}
+
+/// Reads an edited rename plan. The columns are found by their names, so
+/// they may be reordered; `old_name` is only there for reference.
+fn read(path: &Path) -> io::Result<Vec<Rename>> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let mut rows = parse_csv(&fs::read_to_string(path)?).into_iter();
+    let header = rows
+        .next()
+        .ok_or_else(|| invalid("the plan is empty".to_string()))?;
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|column| column.trim() == name)
+            .ok_or_else(|| invalid(format!("the plan has no '{name}' column")))
+    };
+    let (source, new_name, destination) = (
+        column("source")?,
+        column("new_name")?,
+        column("destination")?,
+    );
+
+    rows.enumerate()
+        .map(|(index, row)| {
+            let row_number = index + 1;
+            let get = |column: usize| row.get(column).map(|field| field.trim()).unwrap_or("");
+            let name = get(new_name);
+            if name.is_empty() || name.contains(['/', '\\']) || name == "." || name == ".." {
+                return Err(invalid(format!(
+                    "row {row_number}: invalid new name '{name}'"
+                )));
+            }
+            if get(source).is_empty() || get(destination).is_empty() {
+                return Err(invalid(format!(
+                    "row {row_number}: the source and destination can't be empty"
+                )));
+            }
+            Ok(Rename {
+                source: PathBuf::from(get(source)),
+                dest: Path::new(get(destination)).join(name),
+            })
+        })
+        .collect()
+}
+
+/// `dirsort apply-renames`: places every file in the plan under its new
+/// name, journaled as a run so `dirsort undo` can put them back. Nothing is
+/// placed if any row is invalid.
+pub fn apply(plan: &Path, mv: bool, on_conflict: ConflictStrategy) -> io::Result<()> {
+    let renames = read(plan)?;
+    let started = Local::now();
+    let journal = Journal::create(&started)?;
+    let run_id = journal.id().to_string();
+    let placements = Placements::new(
+        renames
+            .first()
+            .and_then(|rename| rename.dest.parent())
+            .unwrap_or(Path::new(".")),
+    );
+
+    let mut placed = 0;
+    let mut skipped = 0;
+    let mut errors = Vec::new();
+    for rename in &renames {
+        let (dest, action) = placements.claim(rename.dest.clone(), on_conflict);
+        if action == Action::Skip {
+            warn!(
+                "Skipping '{}', '{}' exists",
+                rename.source.display(),
+                dest.display()
+            );
+            skipped += 1;
+            continue;
+        }
+
+        let result = (|| -> io::Result<()> {
+            let created = dest.parent().map(journal::missing_dirs).unwrap_or_default();
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            match mv {
+                true => move_file(&rename.source, &dest).map(|_| ()),
+                false => copy_file(&rename.source, &dest),
+            }?;
+            journal.record(&rename.source, &dest, mv, None, None, created)
+        })();
+        match result {
+            Ok(()) => {
+                info!("{} -> {}", rename.source.display(), dest.display());
+                placed += 1;
+            }
+            Err(e) => {
+                let error = format!("{}: {e}", rename.source.display());
+                error!("Failed to place {error}");
+                errors.push(error);
+            }
+        }
+    }
+
+    info!(
+        "Placed {placed} of {} files, {skipped} skipped, {} failed",
+        renames.len(),
+        errors.len()
+    );
+
+    history::record(&history::Run {
+        id: run_id,
+        started: started.to_rfc3339(),
+        duration_secs: (Local::now() - started).as_seconds_f64(),
+        dir: std::env::current_dir().unwrap_or_default(),
+        args: std::env::args().skip(1).collect(),
+        found: renames.len() as u64,
+        processed: placed,
+        skipped,
+        uncategorized: 0,
+        conflicts: placements.conflicts.load(Ordering::Relaxed),
+        errors,
+        report: None,
+        journal: Some(journal.path().to_path_buf()),
+    })
+}
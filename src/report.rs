@@ -0,0 +1,173 @@
+use {
+    crate::{errors::SortError, journal},
+    std::{
+        fs::File,
+        io::{self, BufWriter, Write},
+        path::{Path, PathBuf},
+        sync::Mutex,
+        time::Duration,
+    },
+};
+
+/// A failed file's error kind and message.
+type Failure = (&'static str, String);
+
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReportFormat {
+    /// One row per file with source, dest, category, bytes, duration and status, then origin and download URL
+    Csv,
+}
+
+/// What happened to a file.
+#[derive(Clone, Copy)]
+pub enum Status {
+    Placed,
+    Renamed,
+    Overwritten,
+    Quarantined,
+    Pending,
+    Planned,
+    Blacklisted,
+    Uncategorized,
+    Incomplete,
+    Seeding,
+    Deferred,
+    InUse,
+    Conflict,
+    OverQuota,
+    Declined,
+    Failed,
+    /// Staged with `--transactional`, then discarded as the run failed
+    RolledBack,
+}
+
+impl Status {
+    fn as_str(self) -> &'static str {
+        match self {
+            Status::Placed => "placed",
+            Status::Renamed => "renamed",
+            Status::Overwritten => "overwritten",
+            Status::Quarantined => "quarantined",
+            Status::Pending => "pending",
+            Status::Planned => "planned",
+            Status::Blacklisted => "skipped-blacklisted",
+            Status::Uncategorized => "skipped-uncategorized",
+            Status::Incomplete => "skipped-incomplete",
+            Status::Seeding => "skipped-seeding",
+            Status::Deferred => "deferred",
+            Status::InUse => "skipped-in-use",
+            Status::Conflict => "skipped-conflict",
+            Status::OverQuota => "skipped-over-quota",
+            Status::Declined => "declined",
+            Status::Failed => "failed",
+            Status::RolledBack => "rolled-back",
+        }
+    }
+}
+
+/// One file's line in the report. `process_file` fills in what it learns
+/// along the way, so rows for files that stop early are still useful.
+#[derive(Default)]
+pub struct Row {
+    pub source: PathBuf,
+    /// The label of the `--source` the file came from
+    pub origin: Option<String>,
+    pub dest: Option<PathBuf>,
+    pub category: Option<String>,
+    /// The URL the file was downloaded from, where the browser recorded it,
+    /// without its query string
+    pub downloaded_from: Option<String>,
+    pub bytes: u64,
+    pub duration: Duration,
+}
+
+/// Collects a row for every file with `--report`.
+#[derive(Default)]
+pub struct Report {
+    rows: Mutex<Vec<(Row, Status, Option<Failure>)>>,
+}
+
+impl Report {
+    pub fn record(&self, row: Row, status: Status, error: Option<&SortError>) {
+        let error = error.map(|error| (error.kind(), error.message()));
+        if let Ok(mut rows) = self.rows.lock() {
+            rows.push((row, status, error));
+        }
+    }
+
+    /// Marks every file that was going to be placed as rolled back, after a
+    /// `--transactional` run failed.
+    pub fn roll_back(&self) {
+        let mut rows = self.rows.lock().unwrap_or_else(|e| e.into_inner());
+        for (_, status, _) in rows.iter_mut() {
+            if matches!(
+                status,
+                Status::Placed
+                    | Status::Renamed
+                    | Status::Overwritten
+                    | Status::Quarantined
+                    | Status::Pending
+            ) {
+                *status = Status::RolledBack;
+            }
+        }
+    }
+
+    /// Points rows placed under `old` to `new`, after a folder was renamed.
+    pub fn relocate(&self, old: &Path, new: &Path) {
+        let mut rows = self.rows.lock().unwrap_or_else(|e| e.into_inner());
+        for (row, _, _) in rows.iter_mut() {
+            if let Some(dest) = &mut row.dest
+                && let Ok(rest) = dest.strip_prefix(old)
+            {
+                *dest = journal::relocated(new, rest);
+            }
+        }
+    }
+
+    pub fn write_csv(&self, path: &Path) -> io::Result<()> {
+        let mut rows = self
+            .rows
+            .lock()
+            .map_err(|_| io::Error::other("report lock poisoned"))?;
+        rows.sort_by(|a, b| a.0.source.cmp(&b.0.source));
+
+        let mut file = BufWriter::new(File::create(path)?);
+        writeln!(
+            file,
+            "source,dest,category,bytes,duration_ms,status,error_kind,error,origin,downloaded_from"
+        )?;
+        for (row, status, error) in rows.iter() {
+            writeln!(
+                file,
+                "{},{},{},{},{:.3},{},{},{},{},{}",
+                field(&row.source.display().to_string()),
+                field(
+                    &row.dest
+                        .as_ref()
+                        .map(|dest| dest.display().to_string())
+                        .unwrap_or_default()
+                ),
+                field(row.category.as_deref().unwrap_or_default()),
+                row.bytes,
+                row.duration.as_secs_f64() * 1000.0,
+                status.as_str(),
+                field(error.as_ref().map_or("", |(kind, _)| kind)),
+                field(error.as_ref().map_or("", |(_, message)| message)),
+                field(row.origin.as_deref().unwrap_or_default()),
+                field(row.downloaded_from.as_deref().unwrap_or_default()),
+            )?;
+        }
+
+        file.flush()
+    }
+}
+
+/// Quotes a CSV field when it needs it, per RFC 4180.
+pub fn field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,102 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+struct Root {
+    path: PathBuf,
+    /// Space left, minus what this run has already written there
+    free: u64,
+}
+
+#[derive(Default)]
+struct State {
+    roots: Vec<Root>,
+    /// The root each category was placed in first
+    assigned: HashMap<String, usize>,
+}
+
+/// Output roots given with repeated `-o`. Every category sticks to one root,
+/// picked by where its folder already exists or else by free space, and only
+/// spills over to the root with the most space left when a file doesn't fit.
+pub struct Roots {
+    /// The first root, which reports and the HTML index go into
+    primary: PathBuf,
+    /// Categories pinned to a root in `[destinations]`
+    mapping: HashMap<String, PathBuf>,
+    state: Mutex<State>,
+}
+
+impl Roots {
+    pub fn new(paths: &[PathBuf], mapping: HashMap<String, PathBuf>) -> Self {
+        let roots = paths
+            .iter()
+            .map(|path| Root {
+                path: path.clone(),
+                free: fs4::available_space(path).unwrap_or_default(),
+            })
+            .collect();
+
+        Self {
+            primary: paths[0].clone(),
+            mapping,
+            state: Mutex::new(State {
+                roots,
+                ..State::default()
+            }),
+        }
+    }
+
+    /// The root a `size`-byte file in `category` should go into. `folder`
+    /// gives the name of the category's folder on disk in a root.
+    pub fn pick(&self, category: &str, size: u64, folder: impl Fn(&Path) -> String) -> PathBuf {
+        if let Some(path) = self.mapping.get(category) {
+            return path.clone();
+        }
+
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.roots.len() == 1 {
+            return self.primary.clone();
+        }
+
+        let fits = |root: &Root| root.free >= size;
+        let existing = || {
+            state
+                .roots
+                .iter()
+                .position(|root| root.path.join(folder(&root.path)).is_dir() && fits(root))
+        };
+        let index = match state.assigned.get(category) {
+            Some(&index) if fits(&state.roots[index]) => index,
+            Some(_) => most_free(&state.roots),
+            None => existing().unwrap_or_else(|| most_free(&state.roots)),
+        };
+
+        state.assigned.entry(category.to_string()).or_insert(index);
+        state.roots[index].path.clone()
+    }
+
+    /// Counts a `size`-byte file that is about to be written into `root`
+    /// against its free space.
+    pub fn reserve(&self, root: &Path, size: u64) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(root) = state
+            .roots
+            .iter_mut()
+            .find(|candidate| candidate.path == root)
+        {
+            root.free = root.free.saturating_sub(size);
+        }
+    }
+}
+
+fn most_free(roots: &[Root]) -> usize {
+    roots
+        .iter()
+        .enumerate()
+        // `max_by_key` keeps the last of equal roots, so earlier ones win ties.
+        .rev()
+        .max_by_key(|(_, root)| root.free)
+        .map_or(0, |(index, _)| index)
+}
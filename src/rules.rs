@@ -0,0 +1,491 @@
+use {
+    crate::{Resolution, documents, journal, parse_byte_size, script},
+    chrono::{DateTime, Local},
+    serde::{Deserialize, Serialize},
+    std::{
+        cell::OnceCell,
+        collections::HashMap,
+        fs::Metadata,
+        path::{Path, PathBuf},
+        sync::{Mutex, OnceLock},
+        time::SystemTime,
+    },
+};
+
+/// Whether each rule's `keywords` were found in a file, by path and
+/// modification time. A file is matched more than once, for the estimate,
+/// the batch and when it is placed, and extracting its text is the slow
+/// part; only the outcome is kept, not the text.
+static KEYWORDS_FOUND: OnceLock<Mutex<HashMap<FileKey, Vec<bool>>>> = OnceLock::new();
+
+type FileKey = (PathBuf, Option<SystemTime>);
+
+/// A `[[rules]]` entry: files matching `when` and containing one of the
+/// `keywords` go into `folder`, before any category gets a look at them.
+#[derive(Serialize, Deserialize)]
+pub struct Rule {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<Condition>,
+    /// Words to look for, case-insensitively, in the text layer of PDFs and
+    /// in plain-text files; any one of them is enough
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<String>,
+    pub folder: String,
+}
+
+impl Rule {
+    /// Whether `file` matches this rule, the `index`th of `file.rules`.
+    fn matches(&self, index: usize, file: &File) -> bool {
+        self.when
+            .as_ref()
+            .is_none_or(|when| when.expr.matches(file))
+            && (self.keywords.is_empty() || file.keywords_found()[index])
+    }
+
+    /// The folder `path` goes into, with `{script}` replaced by the script
+    /// its name is written in.
+    pub fn folder(&self, path: &Path) -> String {
+        self.folder
+            .replace("{script}", script::of(&file_stem(path)))
+    }
+
+    fn specificity(&self) -> usize {
+        self.when.as_ref().map_or(0, |when| when.expr.specificity())
+            + usize::from(!self.keywords.is_empty())
+    }
+}
+
+impl std::fmt::Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match (&self.when, self.keywords.is_empty()) {
+            (Some(when), true) => write!(f, "{when}"),
+            (Some(when), false) => write!(f, "{when}, keywords {:?}", self.keywords),
+            (None, _) => write!(f, "keywords {:?}", self.keywords),
+        }
+    }
+}
+
+/// The rule `path` is placed by and its index: the first one it matches, or
+/// with `most-specific`, the one with the most conditions, ties going to the
+/// earlier one.
+pub fn winner<'a>(
+    rules: &'a [Rule],
+    path: &Path,
+    ext: Option<&str>,
+    resolution: Resolution,
+) -> Option<(usize, &'a Rule)> {
+    if rules.is_empty() {
+        return None;
+    }
+    let metadata = path.metadata().ok();
+    let file = File {
+        path,
+        ext: ext.map(str::to_lowercase),
+        metadata: metadata.as_ref(),
+        rules,
+        keywords_found: OnceCell::new(),
+    };
+    let mut matching = rules
+        .iter()
+        .enumerate()
+        .filter(|(index, rule)| rule.matches(*index, &file));
+    match resolution {
+        Resolution::FirstMatch => matching.next(),
+        // `max_by_key` keeps the last of equal rules, so go through them backwards.
+        Resolution::MostSpecific => matching.rev().max_by_key(|(_, rule)| rule.specificity()),
+    }
+}
+
+struct File<'a> {
+    path: &'a Path,
+    ext: Option<String>,
+    metadata: Option<&'a Metadata>,
+    rules: &'a [Rule],
+    /// Looked up the first time a `keywords` rule needs it
+    keywords_found: OnceCell<Vec<bool>>,
+}
+
+impl File<'_> {
+    /// Whether the text of the file contains one of the `keywords` of each
+    /// rule, reading it only if no earlier match this run did.
+    fn keywords_found(&self) -> &[bool] {
+        self.keywords_found.get_or_init(|| {
+            let modified = self.metadata.and_then(|metadata| metadata.modified().ok());
+            let key = (self.path.to_path_buf(), modified);
+            let found = KEYWORDS_FOUND.get_or_init(Default::default);
+            if let Some(cached) = found.lock().unwrap_or_else(|e| e.into_inner()).get(&key) {
+                return cached.clone();
+            }
+
+            let text = self
+                .ext
+                .as_deref()
+                .and_then(|ext| documents::text(self.path, ext));
+            let result: Vec<bool> = self
+                .rules
+                .iter()
+                .map(|rule| {
+                    text.as_ref().is_some_and(|text| {
+                        rule.keywords
+                            .iter()
+                            .any(|keyword| text.contains(&keyword.to_lowercase()))
+                    })
+                })
+                .collect();
+            found
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(key, result.clone());
+            result
+        })
+    }
+}
+
+/// A condition like `ext in [mp4, mkv] and size > 1GB and path contains 'OBS'`.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Condition {
+    source: String,
+    expr: Expr,
+}
+
+impl TryFrom<String> for Condition {
+    type Error = String;
+
+    fn try_from(source: String) -> Result<Self, Self::Error> {
+        let tokens = tokenize(&source)?;
+        let mut parser = Parser { tokens, next: 0 };
+        let expr = parser.or()?;
+        if let Some(token) = parser.peek() {
+            return Err(format!("unexpected '{token}' in '{source}'"));
+        }
+        Ok(Self { source, expr })
+    }
+}
+
+impl std::fmt::Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl From<Condition> for String {
+    fn from(condition: Condition) -> Self {
+        condition.source
+    }
+}
+
+#[derive(Clone)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    ExtIn(Vec<String>),
+    /// Lowercased script names, as `script::of` returns them
+    ScriptIn(Vec<String>),
+    NameMatches(glob::Pattern),
+    PathContains(String),
+    PathStartsWith(String),
+    Size(Comparison, u64),
+    /// Compares how long ago the file was modified, as the time it was
+    /// modified against a cutoff; older files were modified before it.
+    Age(Comparison, DateTime<Local>),
+}
+
+#[derive(Clone, Copy)]
+enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    NotEqual,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Comparison {
+    fn holds<T: PartialOrd>(self, left: T, right: T) -> bool {
+        match self {
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Equal => left == right,
+            Comparison::NotEqual => left != right,
+            Comparison::GreaterOrEqual => left >= right,
+            Comparison::Greater => left > right,
+        }
+    }
+
+    /// The comparison with its sides swapped, e.g. `>` for `<`.
+    fn flipped(self) -> Self {
+        match self {
+            Comparison::Less => Comparison::Greater,
+            Comparison::LessOrEqual => Comparison::GreaterOrEqual,
+            Comparison::GreaterOrEqual => Comparison::LessOrEqual,
+            Comparison::Greater => Comparison::Less,
+            other => other,
+        }
+    }
+}
+
+impl Expr {
+    /// How many conditions a file has to meet, at the least, to match.
+    fn specificity(&self) -> usize {
+        match self {
+            Expr::And(left, right) => left.specificity() + right.specificity(),
+            Expr::Or(left, right) => left.specificity().min(right.specificity()),
+            Expr::Not(expr) => expr.specificity(),
+            _ => 1,
+        }
+    }
+
+    fn matches(&self, file: &File) -> bool {
+        match self {
+            Expr::And(left, right) => left.matches(file) && right.matches(file),
+            Expr::Or(left, right) => left.matches(file) || right.matches(file),
+            Expr::Not(expr) => !expr.matches(file),
+            Expr::ExtIn(exts) => file.ext.as_ref().is_some_and(|ext| exts.contains(ext)),
+            Expr::ScriptIn(scripts) => {
+                scripts.contains(&script::of(&file_stem(file.path)).to_lowercase())
+            }
+            Expr::NameMatches(pattern) => file
+                .path
+                .file_name()
+                .is_some_and(|name| pattern.matches(&name.to_string_lossy())),
+            Expr::PathContains(text) => file.path.to_string_lossy().contains(text.as_str()),
+            Expr::PathStartsWith(prefix) => {
+                let path = file.path.strip_prefix(".").unwrap_or(file.path);
+                path.to_string_lossy().starts_with(prefix.as_str())
+            }
+            Expr::Size(comparison, size) => file
+                .metadata
+                .is_some_and(|metadata| comparison.holds(metadata.len(), *size)),
+            // Older means modified earlier, so the sides swap.
+            Expr::Age(comparison, cutoff) => file
+                .metadata
+                .and_then(|metadata| metadata.modified().ok())
+                .is_some_and(|modified| {
+                    comparison
+                        .flipped()
+                        .holds(DateTime::<Local>::from(modified), *cutoff)
+                }),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Text(String),
+    Symbol(&'static str),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Token::Word(word) => write!(f, "{word}"),
+            Token::Text(text) => write!(f, "'{text}'"),
+            Token::Symbol(symbol) => write!(f, "{symbol}"),
+        }
+    }
+}
+
+const SYMBOLS: &[&str] = &[
+    ">=", "<=", "!=", "==", ">", "<", "=", "(", ")", "[", "]", ",",
+];
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = source.trim_start();
+    while !rest.is_empty() {
+        if let Some(symbol) = SYMBOLS.iter().find(|symbol| rest.starts_with(**symbol)) {
+            tokens.push(Token::Symbol(symbol));
+            rest = &rest[symbol.len()..];
+        } else if let Some(quote) = rest.chars().next().filter(|c| *c == '\'' || *c == '"') {
+            let end = rest[1..]
+                .find(quote)
+                .ok_or_else(|| format!("unterminated string in '{source}'"))?;
+            tokens.push(Token::Text(rest[1..=end].to_string()));
+            rest = &rest[end + 2..];
+        } else {
+            let end = rest
+                .find(|c: char| c.is_whitespace() || "<>=!()[],'\"".contains(c))
+                .unwrap_or(rest.len());
+            if end == 0 {
+                return Err(format!("unexpected '{}' in '{source}'", &rest[..1]));
+            }
+            tokens.push(Token::Word(rest[..end].to_string()));
+            rest = &rest[end..];
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    next: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next)
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.next).cloned();
+        self.next += 1;
+        token
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let found =
+            matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword));
+        if found {
+            self.next += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, symbol: &str) -> Result<(), String> {
+        match self.advance() {
+            Some(Token::Symbol(found)) if found == symbol => Ok(()),
+            Some(token) => Err(format!("expected '{symbol}', found '{token}'")),
+            None => Err(format!("expected '{symbol}' at the end")),
+        }
+    }
+
+    /// A word or a quoted string.
+    fn value(&mut self) -> Result<String, String> {
+        match self.advance() {
+            Some(Token::Word(value) | Token::Text(value)) => Ok(value),
+            Some(token) => Err(format!("expected a value, found '{token}'")),
+            None => Err("expected a value at the end".to_string()),
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.not()?;
+        while self.keyword("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> Result<Expr, String> {
+        if self.keyword("not") {
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        if self.peek() == Some(&Token::Symbol("(")) {
+            self.next += 1;
+            let expr = self.or()?;
+            self.expect(")")?;
+            return Ok(expr);
+        }
+        self.condition()
+    }
+
+    /// `[a, b, c]`
+    fn list(&mut self) -> Result<Vec<String>, String> {
+        self.expect("[")?;
+        let mut values = Vec::new();
+        while self.peek() != Some(&Token::Symbol("]")) {
+            values.push(self.value()?);
+            if self.peek() == Some(&Token::Symbol(",")) {
+                self.next += 1;
+            }
+        }
+        self.expect("]")?;
+        Ok(values)
+    }
+
+    fn comparison(&mut self) -> Result<Comparison, String> {
+        match self.advance() {
+            Some(Token::Symbol("<")) => Ok(Comparison::Less),
+            Some(Token::Symbol("<=")) => Ok(Comparison::LessOrEqual),
+            Some(Token::Symbol("=" | "==")) => Ok(Comparison::Equal),
+            Some(Token::Symbol("!=")) => Ok(Comparison::NotEqual),
+            Some(Token::Symbol(">=")) => Ok(Comparison::GreaterOrEqual),
+            Some(Token::Symbol(">")) => Ok(Comparison::Greater),
+            Some(token) => Err(format!("expected a comparison, found '{token}'")),
+            None => Err("expected a comparison at the end".to_string()),
+        }
+    }
+
+    fn condition(&mut self) -> Result<Expr, String> {
+        let attribute = match self.advance() {
+            Some(Token::Word(word)) => word.to_lowercase(),
+            Some(token) => return Err(format!("expected a condition, found '{token}'")),
+            None => return Err("expected a condition at the end".to_string()),
+        };
+
+        match attribute.as_str() {
+            "ext" if self.keyword("in") => {
+                let exts = self.list()?;
+                Ok(Expr::ExtIn(
+                    exts.iter()
+                        .map(|ext| ext.trim_start_matches('.').to_lowercase())
+                        .collect(),
+                ))
+            }
+            "ext" => {
+                let comparison = self.comparison()?;
+                let ext = Expr::ExtIn(vec![self.value()?.trim_start_matches('.').to_lowercase()]);
+                match comparison {
+                    Comparison::Equal => Ok(ext),
+                    Comparison::NotEqual => Ok(Expr::Not(Box::new(ext))),
+                    _ => Err("extensions can only be compared with '=' or '!='".to_string()),
+                }
+            }
+            "script" => {
+                let (negated, scripts) = if self.keyword("in") {
+                    (false, self.list()?)
+                } else {
+                    match self.comparison()? {
+                        Comparison::Equal => (false, vec![self.value()?]),
+                        Comparison::NotEqual => (true, vec![self.value()?]),
+                        _ => {
+                            return Err("scripts can only be compared with '=' or '!='".to_string());
+                        }
+                    }
+                };
+                if let Some(unknown) = scripts.iter().find(|name| !script::is_known(name)) {
+                    return Err(format!("unknown script '{unknown}'"));
+                }
+                let expr = Expr::ScriptIn(scripts.iter().map(|name| name.to_lowercase()).collect());
+                Ok(match negated {
+                    true => Expr::Not(Box::new(expr)),
+                    false => expr,
+                })
+            }
+            "name" if self.keyword("matches") => glob::Pattern::new(&self.value()?)
+                .map(Expr::NameMatches)
+                .map_err(|e| format!("invalid pattern: {e}")),
+            "path" if self.keyword("contains") => Ok(Expr::PathContains(self.value()?)),
+            "path" if self.keyword("startswith") => Ok(Expr::PathStartsWith(self.value()?)),
+            "size" => {
+                let comparison = self.comparison()?;
+                Ok(Expr::Size(comparison, parse_byte_size(&self.value()?)?.0))
+            }
+            "age" => {
+                let comparison = self.comparison()?;
+                Ok(Expr::Age(comparison, journal::parse_since(&self.value()?)?))
+            }
+            other => Err(format!(
+                "unknown condition '{other}', expected ext, name, path, size, age or script"
+            )),
+        }
+    }
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
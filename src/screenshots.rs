@@ -0,0 +1,94 @@
+use {crate::metadata, std::path::Path};
+
+/// Resolutions of common monitors, laptops and phones, in both orientations
+/// where it matters.
+const DISPLAY_SIZES: &[(u32, u32)] = &[
+    (1280, 720),
+    (1280, 800),
+    (1366, 768),
+    (1440, 900),
+    (1536, 864),
+    (1600, 900),
+    (1680, 1050),
+    (1920, 1080),
+    (1920, 1200),
+    (2560, 1080),
+    (2560, 1440),
+    (2560, 1600),
+    (2880, 1800),
+    (3024, 1964),
+    (3440, 1440),
+    (3456, 2234),
+    (3840, 2160),
+    (5120, 2880),
+    (1080, 2400),
+    (1170, 2532),
+    (1179, 2556),
+    (1284, 2778),
+    (1290, 2796),
+    (1440, 3200),
+];
+
+/// Screenshot tools that sign their PNGs in a `Software` text chunk.
+const SCREENSHOT_SOFTWARE: &[&str] = &[
+    "screenshot",
+    "greenshot",
+    "sharex",
+    "snipping",
+    "spectacle",
+    "flameshot",
+    "lightshot",
+];
+
+fn matches_display(path: &Path) -> bool {
+    image::image_dimensions(path).is_ok_and(|(w, h)| {
+        DISPLAY_SIZES
+            .iter()
+            .any(|&(dw, dh)| (w, h) == (dw, dh) || (w, h) == (dh, dw))
+    })
+}
+
+fn has_screenshot_metadata(path: &Path) -> bool {
+    metadata::png_text(path).iter().any(|(keyword, text)| {
+        let text = text.to_lowercase();
+        match keyword.as_str() {
+            "Software" => SCREENSHOT_SOFTWARE.iter().any(|tool| text.contains(tool)),
+            // macOS records "Screenshot" as the XMP user comment.
+            "XML:com.adobe.xmp" => text.contains("screenshot"),
+            _ => false,
+        }
+    })
+}
+
+/// Checks a file name against the configured patterns, then (for PNGs) the
+/// text chunks screenshot tools write and the image's dimensions.
+pub fn is_screenshot(
+    path: &Path,
+    patterns: &[glob::Pattern],
+    check_metadata: bool,
+    check_dimensions: bool,
+) -> bool {
+    let options = glob::MatchOptions {
+        case_sensitive: false,
+        ..Default::default()
+    };
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+
+    if patterns
+        .iter()
+        .any(|pattern| pattern.matches_with(&name, options))
+    {
+        return true;
+    }
+
+    let is_png = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("png"));
+
+    is_png
+        && ((check_metadata && has_screenshot_metadata(path))
+            || (check_dimensions && matches_display(path)))
+}
@@ -0,0 +1,73 @@
+/// Unicode scripts file names are bucketed by, with the ranges of their
+/// letters. Chinese, Japanese and Korean share one bucket, as their names mix
+/// Han characters with kana or hangul.
+const SCRIPTS: &[(&str, &[(char, char)])] = &[
+    (
+        "Latin",
+        &[
+            ('A', 'Z'),
+            ('a', 'z'),
+            ('\u{00C0}', '\u{024F}'),
+            ('\u{1E00}', '\u{1EFF}'),
+        ],
+    ),
+    (
+        "Greek",
+        &[('\u{0370}', '\u{03FF}'), ('\u{1F00}', '\u{1FFF}')],
+    ),
+    ("Cyrillic", &[('\u{0400}', '\u{052F}')]),
+    ("Hebrew", &[('\u{0590}', '\u{05FF}')]),
+    (
+        "Arabic",
+        &[
+            ('\u{0600}', '\u{06FF}'),
+            ('\u{0750}', '\u{077F}'),
+            ('\u{FB50}', '\u{FDFF}'),
+            ('\u{FE70}', '\u{FEFF}'),
+        ],
+    ),
+    ("Devanagari", &[('\u{0900}', '\u{097F}')]),
+    ("Thai", &[('\u{0E00}', '\u{0E7F}')]),
+    (
+        "CJK",
+        &[
+            ('\u{3040}', '\u{30FF}'),
+            ('\u{3400}', '\u{4DBF}'),
+            ('\u{4E00}', '\u{9FFF}'),
+            ('\u{AC00}', '\u{D7AF}'),
+            ('\u{1100}', '\u{11FF}'),
+            ('\u{F900}', '\u{FAFF}'),
+            ('\u{FF66}', '\u{FF9F}'),
+            ('\u{20000}', '\u{2FA1F}'),
+        ],
+    ),
+];
+
+/// The script most of the letters in `name` are written in, e.g. "Cyrillic";
+/// "Other" for names without letters or in scripts not listed above.
+pub fn of(name: &str) -> &'static str {
+    let mut counts = [0usize; SCRIPTS.len()];
+    for c in name.chars().filter(|c| c.is_alphabetic()) {
+        if let Some(index) = SCRIPTS
+            .iter()
+            .position(|(_, ranges)| ranges.iter().any(|(low, high)| (*low..=*high).contains(&c)))
+        {
+            counts[index] += 1;
+        }
+    }
+    // Ties go to the script listed first.
+    counts
+        .iter()
+        .enumerate()
+        .filter(|(_, count)| **count > 0)
+        .max_by_key(|(index, count)| (**count, std::cmp::Reverse(*index)))
+        .map_or("Other", |(index, _)| SCRIPTS[index].0)
+}
+
+/// Whether `script` is one `of` can return, ignoring case.
+pub fn is_known(script: &str) -> bool {
+    script.eq_ignore_ascii_case("other")
+        || SCRIPTS
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case(script))
+}
@@ -0,0 +1,188 @@
+use {
+    std::{
+        env,
+        error::Error,
+        fs::{self, File},
+        io::Write,
+        path::Path,
+        process,
+        time::{Duration, Instant, SystemTime},
+    },
+    tracing::info,
+};
+
+/// What kind of directory `dirsort simulate` generates.
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Profile {
+    /// A typical downloads folder: installers, archives, documents, media and leftovers
+    DownloadsMix,
+    /// Camera imports with sidecars, RAW files, screenshots and the odd video
+    Photos,
+    /// Office documents, PDFs, spreadsheets and e-books
+    Documents,
+}
+
+/// `(weight, name template, extensions)`; `{n}` is replaced with a counter.
+type Kind = (u32, &'static str, &'static [&'static str]);
+
+const DOWNLOADS_MIX: &[Kind] = &[
+    (12, "IMG_{n}", &["jpg", "jpeg", "png", "heic"]),
+    (4, "Screenshot 2024-05-01 at 10.{n}", &["png"]),
+    (10, "invoice-{n}", &["pdf", "docx", "xlsx"]),
+    (6, "setup-{n}", &["exe", "msi", "dmg", "deb", "AppImage"]),
+    (8, "archive-{n}", &["zip", "tar.gz", "7z", "rar"]),
+    (8, "track {n}", &["mp3", "flac", "m4a"]),
+    (5, "movie.{n}", &["mkv", "mp4"]),
+    (2, "movie.{n}.en", &["srt"]),
+    (5, "notes{n}", &["txt", "md"]),
+    (3, "script_{n}", &["py", "rs", "js", "sh"]),
+    (2, "download-{n}", &["crdownload", "part"]),
+    (2, "file{n}", &[""]),
+    (1, "Photo {n} (1)", &["jpg"]),
+    (2, "mystery-{n}", &["xyz", "dat", "bin"]),
+];
+
+const PHOTOS: &[Kind] = &[
+    (40, "DSC_{n}", &["jpg", "JPG"]),
+    (15, "DSC_{n}", &["nef", "cr2", "arw"]),
+    (10, "DSC_{n}", &["xmp"]),
+    (10, "IMG_{n}", &["heic", "png"]),
+    (8, "Screenshot_{n}", &["png"]),
+    (5, "VID_{n}", &["mp4", "mov"]),
+    (2, "IMG_{n}", &["aae"]),
+];
+
+const DOCUMENTS: &[Kind] = &[
+    (20, "report-{n}", &["pdf"]),
+    (15, "letter {n}", &["docx", "doc", "odt", "rtf"]),
+    (10, "budget-{n}", &["xlsx", "csv", "ods"]),
+    (8, "slides-{n}", &["pptx", "key"]),
+    (8, "book-{n}", &["epub", "mobi"]),
+    (5, "notes-{n}", &["txt", "md"]),
+    (2, "scan{n}", &["tiff", "png"]),
+];
+
+/// Folders some of the files are put into, as a real directory would have.
+const FOLDERS: &[&str] = &[
+    "",
+    "",
+    "",
+    "",
+    "old",
+    "misc/2023",
+    "Camera Uploads",
+    "stuff/new",
+];
+
+/// Deterministic xorshift so the same seed always generates the same tree.
+pub struct Rng(pub u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound.max(1)
+    }
+}
+
+/// Fills `dir` with `count` files following `profile`. Files get small
+/// contents and modification times spread over the past year.
+pub fn generate(dir: &Path, count: usize, profile: Profile, seed: u64) -> std::io::Result<()> {
+    let kinds = match profile {
+        Profile::DownloadsMix => DOWNLOADS_MIX,
+        Profile::Photos => PHOTOS,
+        Profile::Documents => DOCUMENTS,
+    };
+    let total_weight: u32 = kinds.iter().map(|(weight, _, _)| weight).sum();
+    let mut rng = Rng(seed.max(1));
+    let now = SystemTime::now();
+
+    for n in 0..count {
+        let mut pick = rng.below(total_weight as u64) as u32;
+        let (_, template, exts) = kinds
+            .iter()
+            .find(|(weight, _, _)| {
+                let found = pick < *weight;
+                pick = pick.saturating_sub(*weight);
+                found
+            })
+            .unwrap_or(&kinds[0]);
+
+        let ext = exts[rng.below(exts.len() as u64) as usize];
+        let stem = template.replace("{n}", &format!("{n:05}"));
+        let name = if ext.is_empty() {
+            stem
+        } else {
+            format!("{stem}.{ext}")
+        };
+        let folder = FOLDERS[rng.below(FOLDERS.len() as u64) as usize];
+        let path = dir.join(folder).join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut file = File::create(&path)?;
+        let size = 64 + rng.below(4096) as usize;
+        let line = format!("dirsort simulated file {n}\n");
+        file.write_all(&line.repeat(size / line.len() + 1).as_bytes()[..size])?;
+
+        // At least an hour old, so nothing looks like an unfinished download.
+        let age = Duration::from_secs(3600 + rng.below(365 * 24 * 3600));
+        file.set_modified(now - age)?;
+    }
+
+    Ok(())
+}
+
+/// Generates a temporary directory and dry-runs the current configuration
+/// against it. `args` are the options given before `simulate`, from
+/// `forwarded_options`, which are passed on to the dry run.
+pub fn run(
+    count: usize,
+    profile: Profile,
+    seed: u64,
+    keep: bool,
+    args: &[String],
+) -> Result<(), Box<dyn Error>> {
+    let dir = env::temp_dir().join(format!("dirsort-simulate-{}", process::id()));
+    fs::create_dir_all(&dir)?;
+
+    let generating = Instant::now();
+    let result = generate(&dir, count, profile, seed)
+        .map_err(Box::<dyn Error>::from)
+        .and_then(|()| {
+            info!(
+                "Generated {count} files in '{}' in {:.2?}",
+                dir.display(),
+                generating.elapsed()
+            );
+            dry_run(&dir, args)
+        });
+
+    if keep {
+        info!("Kept '{}'", dir.display());
+    } else {
+        fs::remove_dir_all(&dir)?;
+    }
+    result
+}
+
+fn dry_run(dir: &Path, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let running = Instant::now();
+    let status = process::Command::new(env::current_exe()?)
+        .args(args)
+        .arg("--dry-run")
+        .current_dir(dir)
+        .status()?;
+    info!("Dry run took {:.2?}", running.elapsed());
+
+    if !status.success() {
+        return Err(format!("dry run failed: {status}").into());
+    }
+    Ok(())
+}
@@ -0,0 +1,98 @@
+use {
+    chrono::{DateTime, Local, NaiveDateTime},
+    std::{
+        collections::HashMap,
+        fs::{self, File},
+        io,
+        path::{Path, PathBuf},
+        sync::atomic::{AtomicU64, Ordering},
+    },
+};
+
+/// Snapshot folders are named after the time of the run, e.g. `2024-06-01T12-00`.
+const NAME_FORMAT: &str = "%Y-%m-%dT%H-%M";
+/// Used instead when a run in the same minute already took the name.
+const PRECISE_NAME_FORMAT: &str = "%Y-%m-%dT%H-%M-%S";
+
+/// `--snapshot`: every run sorts into a new folder under each output root,
+/// and files that haven't changed since the previous snapshot are hardlinked
+/// to it instead of copied, as rsnapshot does.
+pub struct Snapshot {
+    /// The previous snapshot under each of this run's snapshot folders
+    previous: HashMap<PathBuf, PathBuf>,
+    pub linked: AtomicU64,
+}
+
+/// The folder this run's snapshot goes into under `root`.
+pub fn folder(root: &Path, started: DateTime<Local>) -> PathBuf {
+    let folder = root.join(started.format(NAME_FORMAT).to_string());
+    if folder.exists() {
+        root.join(started.format(PRECISE_NAME_FORMAT).to_string())
+    } else {
+        folder
+    }
+}
+
+impl Snapshot {
+    /// Pairs every snapshot folder with the latest snapshot already in its
+    /// root, if there is one.
+    pub fn new(folders: &[PathBuf]) -> Self {
+        let previous = folders
+            .iter()
+            .filter_map(|folder| {
+                let root = folder.parent()?;
+                let name = folder.file_name()?;
+                let latest = fs::read_dir(root)
+                    .ok()?
+                    .filter_map(Result::ok)
+                    .map(|entry| entry.file_name())
+                    .filter(|other| *other != name && other.to_str().is_some_and(is_snapshot))
+                    .max()?;
+                Some((folder.clone(), root.join(latest)))
+            })
+            .collect();
+
+        Self {
+            previous,
+            linked: AtomicU64::new(0),
+        }
+    }
+
+    /// Places a copy of `source` at `dest`, hardlinking the previous
+    /// snapshot's copy when it has the same size and modification time.
+    /// Returns whether it was linked.
+    pub fn place(
+        &self,
+        source: &Path,
+        dest: &Path,
+        copy: impl FnOnce() -> io::Result<()>,
+    ) -> io::Result<bool> {
+        let metadata = source.metadata()?;
+        let previous = self
+            .previous
+            .iter()
+            .find_map(|(folder, previous)| Some(previous.join(dest.strip_prefix(folder).ok()?)));
+        if let Some(previous) = previous
+            && let Ok(old) = previous.metadata()
+            && old.len() == metadata.len()
+            && old.modified().ok() == metadata.modified().ok()
+            && fs::hard_link(&previous, dest).is_ok()
+        {
+            self.linked.fetch_add(1, Ordering::Relaxed);
+            return Ok(true);
+        }
+
+        copy()?;
+        // The next snapshot compares modification times, so they have to match.
+        File::options()
+            .write(true)
+            .open(dest)?
+            .set_modified(metadata.modified()?)?;
+        Ok(false)
+    }
+}
+
+fn is_snapshot(name: &str) -> bool {
+    NaiveDateTime::parse_from_str(name, NAME_FORMAT).is_ok()
+        || NaiveDateTime::parse_from_str(name, PRECISE_NAME_FORMAT).is_ok()
+}
@@ -0,0 +1,88 @@
+use std::path::{Path, PathBuf};
+
+/// A directory given with `--source`, and the label its files are traced
+/// back to in the journal, the report and with `--tag-origin`.
+#[derive(Clone, Debug)]
+pub struct Source {
+    pub label: String,
+    pub path: PathBuf,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum OriginTag {
+    /// Add the label to the file name, e.g. 'photo [from laptop].jpg'
+    Name,
+    /// Set the 'user.dirsort.origin' extended attribute to the label
+    Xattr,
+}
+
+/// The extended attribute `--tag-origin xattr` sets.
+#[cfg(unix)]
+const ORIGIN_ATTR: &str = "user.dirsort.origin";
+
+/// Parses `--source`: 'laptop=/mnt/laptop', or just a directory, which is
+/// labelled with its name.
+pub fn parse(source: &str) -> Result<Source, String> {
+    let (label, path) = match source.split_once('=') {
+        Some((label, path)) if !label.is_empty() && !label.contains(['/', '\\']) => {
+            (Some(label.to_string()), PathBuf::from(path))
+        }
+        _ => (None, PathBuf::from(source)),
+    };
+    if !path.is_dir() {
+        return Err(format!("'{}' is not a directory", path.display()));
+    }
+    let label = match label {
+        Some(label) => label,
+        None => label_for(&path),
+    };
+    Ok(Source { label, path })
+}
+
+/// The current directory, for when no `--source` was given.
+pub fn current() -> Source {
+    Source {
+        label: label_for(Path::new(".")),
+        path: PathBuf::from("."),
+    }
+}
+
+fn label_for(path: &Path) -> String {
+    path.canonicalize()
+        .ok()
+        .and_then(|path| {
+            path.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+        })
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+/// The label of the source `path` was found in.
+pub fn origin<'a>(sources: &'a [Source], path: &Path) -> Option<&'a str> {
+    sources
+        .iter()
+        .filter(|source| path.starts_with(&source.path))
+        .max_by_key(|source| source.path.components().count())
+        .map(|source| source.label.as_str())
+}
+
+/// `name` with ` [from <label>]` added before its extension.
+pub fn tag_name(name: &str, label: &str) -> String {
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => format!("{stem} [from {label}].{ext}"),
+        _ => format!("{name} [from {label}]"),
+    }
+}
+
+#[cfg(unix)]
+pub fn tag_xattr(dest: &Path, label: &str) -> std::io::Result<()> {
+    xattr::set(dest, ORIGIN_ATTR, label.as_bytes())
+}
+
+#[cfg(not(unix))]
+pub fn tag_xattr(_dest: &Path, _label: &str) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "extended attributes are not supported on this platform",
+    ))
+}
@@ -0,0 +1,224 @@
+use {
+    std::{
+        collections::HashMap,
+        fs,
+        path::{Path, PathBuf},
+    },
+    walkdir::WalkDir,
+};
+
+/// How deeply lists and dictionaries may nest in a `.torrent` file; real
+/// ones go about five levels deep, so a broken or hostile file can't run the
+/// decoder out of stack.
+const MAX_DEPTH: usize = 32;
+
+/// A decoded bencode value, as `.torrent` files are written in.
+enum Value {
+    Int(i64),
+    Bytes(Vec<u8>),
+    List(Vec<Value>),
+    Dict(Vec<(Vec<u8>, Value)>),
+}
+
+impl Value {
+    fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Dict(entries) => entries
+                .iter()
+                .find(|(name, _)| name == key.as_bytes())
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<String> {
+        match self {
+            Value::Bytes(bytes) => Some(String::from_utf8_lossy(bytes).to_string()),
+            _ => None,
+        }
+    }
+
+    fn as_u64(&self) -> Option<u64> {
+        match self {
+            Value::Int(n) => u64::try_from(*n).ok(),
+            _ => None,
+        }
+    }
+}
+
+/// Decodes the bencode value at the start of `data`, returning it and
+/// what's left after it, or nothing for values nested deeper than `depth`.
+fn decode(data: &[u8], depth: usize) -> Option<(Value, &[u8])> {
+    match data.first()? {
+        b'l' | b'd' if depth == 0 => None,
+        b'i' => {
+            let end = data.iter().position(|b| *b == b'e')?;
+            let n = std::str::from_utf8(&data[1..end]).ok()?.parse().ok()?;
+            Some((Value::Int(n), &data[end + 1..]))
+        }
+        b'l' => {
+            let mut rest = &data[1..];
+            let mut items = Vec::new();
+            while *rest.first()? != b'e' {
+                let (item, after) = decode(rest, depth - 1)?;
+                items.push(item);
+                rest = after;
+            }
+            Some((Value::List(items), &rest[1..]))
+        }
+        b'd' => {
+            let mut rest = &data[1..];
+            let mut entries = Vec::new();
+            while *rest.first()? != b'e' {
+                let (Value::Bytes(key), after) = decode(rest, depth - 1)? else {
+                    return None;
+                };
+                let (value, after) = decode(after, depth - 1)?;
+                entries.push((key, value));
+                rest = after;
+            }
+            Some((Value::Dict(entries), &rest[1..]))
+        }
+        b'0'..=b'9' => {
+            let colon = data.iter().position(|b| *b == b':')?;
+            let len: usize = std::str::from_utf8(&data[..colon]).ok()?.parse().ok()?;
+            let start = colon + 1;
+            let bytes = data.get(start..start.checked_add(len)?)?.to_vec();
+            Some((Value::Bytes(bytes), &data[start + len..]))
+        }
+        _ => None,
+    }
+}
+
+/// A file a torrent consists of: its path inside the torrent, e.g.
+/// `["Album", "01 Intro.flac"]`, and its size.
+struct TorrentFile {
+    components: Vec<String>,
+    length: u64,
+}
+
+/// The files torrent clients may still be seeding, so sorting a download
+/// folder doesn't pull them away from under the client.
+#[derive(Default)]
+pub struct Seeding {
+    /// Files listed in the `.torrent` files, by file name
+    files: HashMap<String, Vec<TorrentFile>>,
+    directories: Vec<PathBuf>,
+    pub torrents: usize,
+}
+
+impl Seeding {
+    /// Reads the `.torrent` files in `torrent_dirs`, like the ones clients
+    /// keep for their active torrents, and takes everything in `directories`
+    /// as seeding.
+    pub fn load(torrent_dirs: &[PathBuf], directories: &[PathBuf]) -> Self {
+        let mut seeding = Self {
+            directories: directories
+                .iter()
+                .map(|dir| fs::canonicalize(dir).unwrap_or_else(|_| dir.clone()))
+                .collect(),
+            ..Self::default()
+        };
+
+        for dir in torrent_dirs {
+            for entry in WalkDir::new(dir)
+                .max_depth(2)
+                .into_iter()
+                .filter_map(Result::ok)
+            {
+                let is_torrent = entry
+                    .path()
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("torrent"));
+                if !is_torrent || !entry.file_type().is_file() {
+                    continue;
+                }
+                if let Ok(data) = fs::read(entry.path())
+                    && let Some((torrent, _)) = decode(&data, MAX_DEPTH)
+                    && seeding.add(&torrent)
+                {
+                    seeding.torrents += 1;
+                }
+            }
+        }
+        seeding
+    }
+
+    fn add(&mut self, torrent: &Value) -> bool {
+        let Some(info) = torrent.get("info") else {
+            return false;
+        };
+        let Some(name) = info.get("name").and_then(Value::as_str) else {
+            return false;
+        };
+
+        let files = match info.get("files") {
+            // A torrent of several files, in a folder named after it.
+            Some(Value::List(files)) => files
+                .iter()
+                .filter_map(|file| {
+                    let Some(Value::List(path)) = file.get("path") else {
+                        return None;
+                    };
+                    let mut components = vec![name.clone()];
+                    components.extend(path.iter().filter_map(Value::as_str));
+                    Some(TorrentFile {
+                        components,
+                        length: file.get("length")?.as_u64()?,
+                    })
+                })
+                .collect(),
+            _ => match info.get("length").and_then(Value::as_u64) {
+                Some(length) => vec![TorrentFile {
+                    components: vec![name],
+                    length,
+                }],
+                None => return false,
+            },
+        };
+        for file in files {
+            if let Some(file_name) = file.components.last() {
+                self.files.entry(file_name.clone()).or_default().push(file);
+            }
+        }
+        true
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty() && self.directories.is_empty()
+    }
+
+    /// Whether `path` is in a seeding directory, or is a file of one of the
+    /// torrents: its path ends in the file's path inside the torrent and it
+    /// has the same size.
+    pub fn contains(&self, path: &Path, size: u64) -> bool {
+        let absolute = || fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        if !self.directories.is_empty() {
+            let absolute = absolute();
+            if self.directories.iter().any(|dir| absolute.starts_with(dir)) {
+                return true;
+            }
+        }
+
+        let Some(files) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| self.files.get(name))
+        else {
+            return false;
+        };
+        let mut candidates = files.iter().filter(|file| file.length == size).peekable();
+        if candidates.peek().is_none() {
+            return false;
+        }
+        let absolute = absolute();
+        candidates.any(|file| {
+            absolute.components().count() >= file.components.len()
+                && absolute
+                    .components()
+                    .rev()
+                    .zip(file.components.iter().rev())
+                    .all(|(component, expected)| component.as_os_str() == expected.as_str())
+        })
+    }
+}
@@ -0,0 +1,111 @@
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+/// Hidden folder in each output root that `--transactional` stages files in.
+pub const STAGING_DIR: &str = ".dirsort-staging";
+
+/// A file copied into the staging area, waiting to be moved into place.
+pub struct Staged {
+    /// The file it was copied from; `None` for files dirsort made, like
+    /// `--convert` output
+    pub source: Option<PathBuf>,
+    /// The file a file dirsort made was made from
+    pub made_from: Option<PathBuf>,
+    pub staged: PathBuf,
+    pub dest: PathBuf,
+    /// The output root `dest` is in, for `--backup`
+    pub root: PathBuf,
+    pub category_dir: PathBuf,
+    /// Whether the source goes away once the run is committed
+    pub moved: bool,
+    /// Whether `dest` is replaced
+    pub overwrite: bool,
+    /// Size of the source, for the integrity check
+    pub size: u64,
+}
+
+/// `--transactional`: every file of the run is first copied into a staging
+/// area in its output root, and only moved into place once all of them got
+/// there. Staging within the root keeps the final move a rename.
+pub struct Transaction {
+    roots: Vec<PathBuf>,
+    run_id: String,
+    staged: Mutex<Vec<Staged>>,
+    /// The run's staging folders, to remove afterwards
+    areas: Mutex<HashSet<PathBuf>>,
+    /// Numbers the staged files, so files headed for the same destination
+    /// don't replace each other in staging
+    staged_count: AtomicU64,
+}
+
+impl Transaction {
+    pub fn new(roots: Vec<PathBuf>, run_id: &str) -> Self {
+        Self {
+            roots,
+            run_id: run_id.to_string(),
+            staged: Mutex::new(Vec::new()),
+            areas: Mutex::new(HashSet::new()),
+            staged_count: AtomicU64::new(0),
+        }
+    }
+
+    /// Where to stage the file headed for `dest`, with its folder created:
+    /// a folder of its own in the staging area of `dest`'s output root.
+    pub fn stage(&self, dest: &Path) -> io::Result<PathBuf> {
+        let root = self
+            .roots
+            .iter()
+            .filter(|root| dest.starts_with(root))
+            .max_by_key(|root| root.components().count())
+            .map(PathBuf::as_path)
+            .or_else(|| dest.parent())
+            .unwrap_or(Path::new("."));
+        let area = root.join(STAGING_DIR).join(&self.run_id);
+        let folder = area.join(
+            self.staged_count
+                .fetch_add(1, Ordering::Relaxed)
+                .to_string(),
+        );
+        fs::create_dir_all(&folder)?;
+        let staged = folder.join(dest.file_name().unwrap_or_default());
+        self.areas
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(area);
+        Ok(staged)
+    }
+
+    pub fn record(&self, staged: Staged) {
+        self.staged
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(staged);
+    }
+
+    pub fn take(&self) -> Vec<Staged> {
+        std::mem::take(&mut *self.staged.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// Removes the run's staging folders with whatever is left in them, and
+    /// the staging area itself once no other run uses it.
+    pub fn discard(&self) -> io::Result<()> {
+        let areas = std::mem::take(&mut *self.areas.lock().unwrap_or_else(|e| e.into_inner()));
+        for area in areas {
+            match fs::remove_dir_all(&area) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+            if let Some(staging) = area.parent() {
+                let _ = fs::remove_dir(staging);
+            }
+        }
+        Ok(())
+    }
+}
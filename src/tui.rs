@@ -0,0 +1,258 @@
+use {
+    crate::mail,
+    ratatui::{
+        Frame,
+        crossterm::event::{self, Event, KeyCode, KeyModifiers},
+        layout::{Constraint, Layout},
+        style::{Color, Style},
+        text::Line,
+        widgets::{Block, Gauge, List, ListItem, Sparkline},
+    },
+    std::{
+        collections::{BTreeMap, HashMap, VecDeque},
+        io,
+        path::Path,
+        process,
+        sync::{
+            Arc, Mutex,
+            atomic::{AtomicBool, AtomicU64, Ordering},
+        },
+        thread::{self, JoinHandle},
+        time::{Duration, Instant},
+    },
+};
+
+/// How often the dashboard is redrawn and throughput is sampled.
+const TICK: Duration = Duration::from_millis(250);
+
+/// How many throughput samples the graph keeps.
+const HISTORY: usize = 240;
+
+#[derive(Default)]
+struct State {
+    total: u64,
+    done: AtomicU64,
+    bytes: AtomicU64,
+    categories: Mutex<BTreeMap<String, u64>>,
+    workers: Mutex<HashMap<usize, String>>,
+    errors: Mutex<Vec<String>>,
+    stop: AtomicBool,
+}
+
+/// Full-screen view of a running sort, replacing the progress bar with `--tui`.
+pub struct Dashboard {
+    state: Arc<State>,
+    render: Option<JoinHandle<io::Result<()>>>,
+}
+
+impl Dashboard {
+    /// Switches to the alternate screen and starts redrawing in the background.
+    pub fn start(total: u64) -> Self {
+        let state = Arc::new(State {
+            total,
+            ..State::default()
+        });
+
+        let render = {
+            let state = Arc::clone(&state);
+            thread::spawn(move || render_loop(&state))
+        };
+
+        Self {
+            state,
+            render: Some(render),
+        }
+    }
+
+    /// Shows `path` as the file the current worker is transferring.
+    pub fn begin(&self, path: &Path) {
+        let worker = rayon::current_thread_index().unwrap_or_default();
+        if let Ok(mut workers) = self.state.workers.lock() {
+            workers.insert(worker, path.display().to_string());
+        }
+    }
+
+    pub fn placed(&self, category: &str, bytes: u64) {
+        self.state.bytes.fetch_add(bytes, Ordering::Relaxed);
+        if let Ok(mut categories) = self.state.categories.lock() {
+            *categories.entry(category.to_string()).or_default() += 1;
+        }
+    }
+
+    pub fn error(&self, message: String) {
+        if let Ok(mut errors) = self.state.errors.lock() {
+            errors.push(message);
+        }
+    }
+
+    /// Marks one file as handled, whatever happened to it.
+    pub fn advance(&self) {
+        self.state.done.fetch_add(1, Ordering::Relaxed);
+        let worker = rayon::current_thread_index().unwrap_or_default();
+        if let Ok(mut workers) = self.state.workers.lock() {
+            workers.remove(&worker);
+        }
+    }
+
+    /// Stops redrawing and restores the terminal.
+    pub fn finish(mut self) -> io::Result<()> {
+        self.state.stop.store(true, Ordering::Relaxed);
+        match self.render.take().map(JoinHandle::join) {
+            Some(Ok(result)) => result,
+            Some(Err(_)) => Err(io::Error::other("dashboard thread panicked")),
+            None => Ok(()),
+        }
+    }
+}
+
+fn render_loop(state: &State) -> io::Result<()> {
+    let mut terminal = ratatui::init();
+    let mut history = VecDeque::with_capacity(HISTORY);
+    let mut last_bytes = 0;
+    let mut last_sample = Instant::now();
+
+    let result = loop {
+        let bytes = state.bytes.load(Ordering::Relaxed);
+        let elapsed = last_sample.elapsed().as_secs_f64();
+        if history.len() == HISTORY {
+            history.pop_front();
+        }
+        history.push_back(((bytes - last_bytes) as f64 / elapsed) as u64);
+        last_bytes = bytes;
+        last_sample = Instant::now();
+
+        if let Err(e) = terminal.draw(|frame| draw(frame, state, &history)) {
+            break Err(e);
+        }
+
+        if state.stop.load(Ordering::Relaxed) {
+            break Ok(());
+        }
+
+        // Raw mode swallows Ctrl+C, so interrupting has to be handled here.
+        match event::poll(TICK) {
+            Ok(true) => {
+                if let Ok(Event::Key(key)) = event::read()
+                    && (key.code == KeyCode::Char('q')
+                        || (key.code == KeyCode::Char('c')
+                            && key.modifiers.contains(KeyModifiers::CONTROL)))
+                {
+                    ratatui::restore();
+                    mail::discard();
+                    process::exit(130);
+                }
+            }
+            Ok(false) => {}
+            Err(e) => break Err(e),
+        }
+    };
+
+    ratatui::restore();
+    result
+}
+
+fn draw(frame: &mut Frame, state: &State, history: &VecDeque<u64>) {
+    let [progress_area, middle, graph_area, errors_area] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Min(6),
+        Constraint::Length(7),
+        Constraint::Length(8),
+    ])
+    .areas(frame.area());
+    let [categories_area, workers_area] =
+        Layout::horizontal([Constraint::Percentage(35), Constraint::Percentage(65)]).areas(middle);
+
+    let done = state.done.load(Ordering::Relaxed);
+    let ratio = if state.total == 0 {
+        1.0
+    } else {
+        (done as f64 / state.total as f64).min(1.0)
+    };
+    frame.render_widget(
+        Gauge::default()
+            .block(Block::bordered().title(" dirsort (q to quit) "))
+            .gauge_style(Style::default().fg(Color::Green))
+            .label(format!(
+                "{done}/{} files, {}",
+                state.total,
+                human_bytes(state.bytes.load(Ordering::Relaxed))
+            ))
+            .ratio(ratio),
+        progress_area,
+    );
+
+    let categories: Vec<ListItem> = state
+        .categories
+        .lock()
+        .map(|categories| {
+            categories
+                .iter()
+                .map(|(name, count)| ListItem::new(format!("{count:>7}  {name}")))
+                .collect()
+        })
+        .unwrap_or_default();
+    frame.render_widget(
+        List::new(categories).block(Block::bordered().title(" Categories ")),
+        categories_area,
+    );
+
+    let mut workers: Vec<(usize, String)> = state
+        .workers
+        .lock()
+        .map(|workers| workers.iter().map(|(w, p)| (*w, p.clone())).collect())
+        .unwrap_or_default();
+    workers.sort();
+    frame.render_widget(
+        List::new(
+            workers
+                .into_iter()
+                .map(|(worker, path)| ListItem::new(format!("#{worker:<3} {path}"))),
+        )
+        .block(Block::bordered().title(" Workers ")),
+        workers_area,
+    );
+
+    let rate = history.back().copied().unwrap_or_default();
+    let width = graph_area.width.saturating_sub(2) as usize;
+    let samples: Vec<u64> = history
+        .iter()
+        .skip(history.len().saturating_sub(width))
+        .copied()
+        .collect();
+    frame.render_widget(
+        Sparkline::default()
+            .block(Block::bordered().title(format!(" Throughput: {}/s ", human_bytes(rate))))
+            .style(Style::default().fg(Color::Cyan))
+            .data(&samples),
+        graph_area,
+    );
+
+    let visible = errors_area.height.saturating_sub(2) as usize;
+    let errors: Vec<ListItem> = state
+        .errors
+        .lock()
+        .map(|errors| {
+            errors
+                .iter()
+                .skip(errors.len().saturating_sub(visible))
+                .map(|error| ListItem::new(Line::styled(error.clone(), Color::Red)))
+                .collect()
+        })
+        .unwrap_or_default();
+    let error_count = state.errors.lock().map(|e| e.len()).unwrap_or_default();
+    frame.render_widget(
+        List::new(errors).block(Block::bordered().title(format!(" Errors ({error_count}) "))),
+        errors_area,
+    );
+}
+
+pub fn human_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1000.0 && unit < UNITS.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
@@ -0,0 +1,161 @@
+use {
+    crate::tui::human_bytes,
+    serde::Serialize,
+    std::{
+        io::{self, Write},
+        path::Path,
+    },
+};
+
+/// Width of the longest bar in the terminal chart.
+const BAR_WIDTH: usize = 40;
+
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum UsageFormat {
+    /// Bar chart for the terminal
+    Chart,
+    /// Nested `{name, size, files, children}` objects, as treemap tools like d3-hierarchy expect
+    Json,
+}
+
+#[derive(Default, Serialize)]
+struct Node {
+    name: String,
+    size: u64,
+    files: u64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn child(&mut self, name: &str) -> &mut Node {
+        let index = match self.children.iter().position(|child| child.name == name) {
+            Some(index) => index,
+            None => {
+                self.children.push(Node {
+                    name: name.to_string(),
+                    ..Node::default()
+                });
+                self.children.len() - 1
+            }
+        };
+        &mut self.children[index]
+    }
+
+    fn add(&mut self, size: u64) {
+        self.size += size;
+        self.files += 1;
+    }
+
+    fn sort(&mut self) {
+        self.children
+            .sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+        self.children.iter_mut().for_each(Node::sort);
+    }
+}
+
+/// Totals `(category, extension, bytes)` triples per category and extension
+/// and prints them in `format`.
+pub fn print(
+    files: impl IntoIterator<Item = (String, String, u64)>,
+    format: UsageFormat,
+) -> io::Result<()> {
+    let mut root = Node {
+        name: ".".to_string(),
+        ..Node::default()
+    };
+    for (category, ext, size) in files {
+        root.add(size);
+        let category = root.child(&category);
+        category.add(size);
+        category.child(&ext).add(size);
+    }
+    root.sort();
+
+    let mut stdout = io::stdout().lock();
+    match format {
+        UsageFormat::Json => {
+            serde_json::to_writer_pretty(&mut stdout, &root)?;
+            writeln!(stdout)
+        }
+        UsageFormat::Chart => chart(&mut stdout, &root),
+    }
+}
+
+fn chart(out: &mut impl Write, root: &Node) -> io::Result<()> {
+    let name_width = root
+        .children
+        .iter()
+        .flat_map(|category| {
+            std::iter::once(category.name.len()).chain(
+                category
+                    .children
+                    .iter()
+                    .map(|ext| ext.name.len().max(5) + 3),
+            )
+        })
+        .max()
+        .unwrap_or_default();
+    let largest = root.children.first().map_or(0, |category| category.size);
+
+    let mut line = |name: &str, node: &Node| {
+        let filled = (node.size as f64 / largest.max(1) as f64 * BAR_WIDTH as f64).round() as usize;
+        let share = node.size as f64 / root.size.max(1) as f64 * 100.0;
+        writeln!(
+            out,
+            "{name:<name_width$}  {}{}  {:>10}  {share:5.1}%  {} files",
+            "█".repeat(filled),
+            "░".repeat(BAR_WIDTH - filled),
+            human_bytes(node.size),
+            node.files
+        )
+    };
+
+    for category in &root.children {
+        line(&category.name, category)?;
+        for ext in &category.children {
+            let label = match ext.name.as_str() {
+                "" => "  (none)".to_string(),
+                name => format!("  .{name}"),
+            };
+            line(&label, ext)?;
+        }
+    }
+
+    writeln!(out, "\n{} in {} files", human_bytes(root.size), root.files)
+}
+
+/// Prints the `count` largest of the `(path, folder, bytes)` triples.
+pub fn largest<'a>(
+    files: impl IntoIterator<Item = (&'a Path, String, u64)>,
+    count: usize,
+) -> io::Result<()> {
+    let mut files: Vec<_> = files.into_iter().collect();
+    let total = files.len();
+    files.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.cmp(b.0)));
+    files.truncate(count);
+
+    let folder_width = files
+        .iter()
+        .map(|(_, folder, _)| folder.len())
+        .max()
+        .unwrap_or_default();
+
+    let mut stdout = io::stdout().lock();
+    for (path, folder, size) in &files {
+        writeln!(
+            stdout,
+            "{:>10}  {folder:<folder_width$}  {}",
+            human_bytes(*size),
+            path.display()
+        )?;
+    }
+
+    let shown: u64 = files.iter().map(|(_, _, size)| size).sum();
+    writeln!(
+        stdout,
+        "\n{} largest of {total} files, {} in total",
+        files.len(),
+        human_bytes(shown)
+    )
+}
@@ -0,0 +1,186 @@
+use {
+    serde::Deserialize,
+    std::{collections::HashMap, path::Path, process::Command},
+};
+
+pub struct VideoInfo {
+    pub height: u32,
+    pub duration: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct ProbeOutput {
+    #[serde(default)]
+    streams: Vec<ProbeStream>,
+    format: Option<ProbeFormat>,
+}
+
+#[derive(Deserialize)]
+struct ProbeStream {
+    height: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct LanguageOutput {
+    #[serde(default)]
+    streams: Vec<LanguageStream>,
+}
+
+#[derive(Deserialize)]
+struct LanguageStream {
+    #[serde(default)]
+    tags: HashMap<String, String>,
+    #[serde(default)]
+    disposition: HashMap<String, u8>,
+}
+
+#[derive(Deserialize)]
+struct ProbeFormat {
+    duration: Option<String>,
+}
+
+pub fn ffprobe_available() -> bool {
+    Command::new("ffprobe")
+        .arg("-version")
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+/// Asks `ffprobe` for the height of the first video stream and the duration.
+pub fn probe(path: &Path) -> Option<VideoInfo> {
+    let output = Command::new("ffprobe")
+        .args([
+            "-v",
+            "error",
+            "-select_streams",
+            "v:0",
+            "-show_entries",
+            "stream=height:format=duration",
+            "-of",
+            "json",
+        ])
+        .arg(path)
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let probed: ProbeOutput = serde_json::from_slice(&output.stdout).ok()?;
+
+    Some(VideoInfo {
+        height: probed.streams.first()?.height?,
+        duration: probed
+            .format
+            .and_then(|format| format.duration)
+            .and_then(|duration| duration.parse().ok()),
+    })
+}
+
+/// ISO 639-2 codes, as containers tag their tracks, with the English names
+/// of the languages. Both the bibliographic and the terminology code are
+/// listed where they differ, e.g. `ger` and `deu`.
+const LANGUAGES: &[(&str, &str)] = &[
+    ("ara", "Arabic"),
+    ("ben", "Bengali"),
+    ("bul", "Bulgarian"),
+    ("cat", "Catalan"),
+    ("ces", "Czech"),
+    ("chi", "Chinese"),
+    ("cze", "Czech"),
+    ("dan", "Danish"),
+    ("deu", "German"),
+    ("dut", "Dutch"),
+    ("ell", "Greek"),
+    ("eng", "English"),
+    ("est", "Estonian"),
+    ("fas", "Persian"),
+    ("fin", "Finnish"),
+    ("fra", "French"),
+    ("fre", "French"),
+    ("ger", "German"),
+    ("gre", "Greek"),
+    ("heb", "Hebrew"),
+    ("hin", "Hindi"),
+    ("hrv", "Croatian"),
+    ("hun", "Hungarian"),
+    ("ice", "Icelandic"),
+    ("ind", "Indonesian"),
+    ("isl", "Icelandic"),
+    ("ita", "Italian"),
+    ("jpn", "Japanese"),
+    ("kor", "Korean"),
+    ("lav", "Latvian"),
+    ("lit", "Lithuanian"),
+    ("may", "Malay"),
+    ("msa", "Malay"),
+    ("nld", "Dutch"),
+    ("nor", "Norwegian"),
+    ("per", "Persian"),
+    ("pol", "Polish"),
+    ("por", "Portuguese"),
+    ("ron", "Romanian"),
+    ("rum", "Romanian"),
+    ("rus", "Russian"),
+    ("slk", "Slovak"),
+    ("slo", "Slovak"),
+    ("slv", "Slovenian"),
+    ("spa", "Spanish"),
+    ("srp", "Serbian"),
+    ("swe", "Swedish"),
+    ("tam", "Tamil"),
+    ("tel", "Telugu"),
+    ("tha", "Thai"),
+    ("tur", "Turkish"),
+    ("ukr", "Ukrainian"),
+    ("urd", "Urdu"),
+    ("vie", "Vietnamese"),
+    ("zho", "Chinese"),
+];
+
+/// The language of the main audio track, e.g. "Japanese": the one marked
+/// as default, or else the first one with a language tag. Untagged tracks
+/// and `und` (undetermined) have none.
+pub fn audio_language(path: &Path) -> Option<String> {
+    let output = Command::new("ffprobe")
+        .args([
+            "-v",
+            "error",
+            "-select_streams",
+            "a",
+            "-show_entries",
+            "stream_tags=language:stream_disposition=default",
+            "-of",
+            "json",
+        ])
+        .arg(path)
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let probed: LanguageOutput = serde_json::from_slice(&output.stdout).ok()?;
+    let language = |stream: &LanguageStream| {
+        stream
+            .tags
+            .get("language")
+            .map(|code| code.trim().to_lowercase())
+            .filter(|code| !code.is_empty() && code != "und")
+    };
+    let code = probed
+        .streams
+        .iter()
+        .filter(|stream| stream.disposition.get("default") == Some(&1))
+        .find_map(language)
+        .or_else(|| probed.streams.iter().find_map(language))?;
+
+    Some(
+        LANGUAGES
+            .iter()
+            .find(|(known, _)| *known == code)
+            .map_or(code, |(_, name)| name.to_string()),
+    )
+}
@@ -0,0 +1,55 @@
+#[cfg(unix)]
+use std::{collections::BTreeMap, fs, os::unix::fs::MetadataExt};
+use {std::path::Path, walkdir::DirEntry};
+
+/// Copy speed assumed when estimating how long a cross-filesystem move takes.
+pub const ASSUMED_COPY_RATE: u64 = 100_000_000;
+
+/// Splits the total size of `paths` into bytes on the same filesystem as
+/// `dest`, which a move only has to rename, and bytes elsewhere, which have to
+/// be copied. `None` where filesystems can't be told apart.
+#[cfg(unix)]
+pub fn split_by_volume<'a>(
+    paths: impl IntoIterator<Item = &'a Path>,
+    dest: &Path,
+) -> Option<(u64, u64)> {
+    let dest_device = fs::metadata(dest).ok()?.dev();
+
+    Some(
+        paths
+            .into_iter()
+            .filter_map(|path| fs::metadata(path).ok())
+            .fold((0, 0), |(same, other), metadata| {
+                if metadata.dev() == dest_device {
+                    (same + metadata.len(), other)
+                } else {
+                    (same, other + metadata.len())
+                }
+            }),
+    )
+}
+
+#[cfg(not(unix))]
+pub fn split_by_volume<'a>(
+    _paths: impl IntoIterator<Item = &'a Path>,
+    _dest: &Path,
+) -> Option<(u64, u64)> {
+    None
+}
+
+/// Groups `entries` by the device they are on, so each device can be given
+/// its own workers. A single group where devices can't be told apart.
+#[cfg(unix)]
+pub fn group_by_device(entries: &[DirEntry]) -> Vec<Vec<&DirEntry>> {
+    let mut devices: BTreeMap<u64, Vec<&DirEntry>> = BTreeMap::new();
+    for entry in entries {
+        let device = entry.metadata().map_or(0, |metadata| metadata.dev());
+        devices.entry(device).or_default().push(entry);
+    }
+    devices.into_values().collect()
+}
+
+#[cfg(not(unix))]
+pub fn group_by_device(entries: &[DirEntry]) -> Vec<Vec<&DirEntry>> {
+    vec![entries.iter().collect()]
+}
@@ -0,0 +1,34 @@
+use {
+    crate::PreserveAttr,
+    std::{io::Result, path::Path},
+};
+
+const SELINUX_ATTR: &str = "security.selinux";
+
+/// Copies the requested extended attributes from `source` onto `dest`.
+///
+/// `xattrs` covers everything in the `user.` namespace, `context` the SELinux
+/// security label. Other namespaces (`trusted.`, `system.`) are left alone.
+pub fn preserve(source: &Path, dest: &Path, attrs: &[PreserveAttr]) -> Result<()> {
+    let want_user = attrs.contains(&PreserveAttr::Xattrs);
+    let want_context = attrs.contains(&PreserveAttr::Context);
+
+    for name in xattr::list(source)? {
+        let Some(name_str) = name.to_str() else {
+            continue;
+        };
+
+        let wanted = (want_user && name_str.starts_with("user."))
+            || (want_context && name_str == SELINUX_ATTR);
+
+        if !wanted {
+            continue;
+        }
+
+        if let Some(value) = xattr::get(source, &name)? {
+            xattr::set(dest, &name, &value)?;
+        }
+    }
+
+    Ok(())
+}